@@ -0,0 +1,315 @@
+use crate::error::{NestError, NestResult};
+use crate::guillotine::{GuillotineConfig, guillotine_sheets};
+use crate::pattern::sheet_patterns;
+use crate::planar::{
+    PlanarNestConfig, PlanarStrategy, RectPart, SheetLayout, SheetStock, partial_sheets,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookConfig {
+    /// tallest stack the saw takes, in the units of the stock's `thickness`
+    pub max_book_height: f64,
+    /// extra parts, over every part id together, the run may cut to save saw cycles
+    pub max_overproduction: usize,
+    /// sheets beyond the nest's own count the run may use to save saw cycles
+    pub max_extra_sheets: usize,
+}
+
+impl BookConfig {
+    fn sheets_per_book(&self, sheet: &SheetStock) -> NestResult<usize> {
+        if sheet.thickness <= 0.0 {
+            return Err(NestError::InvalidDimension(
+                "sheet thickness must be positive",
+            ));
+        }
+        if self.max_book_height < sheet.thickness - 1e-9 {
+            return Err(NestError::InvalidDimension(
+                "book height must take at least one sheet",
+            ));
+        }
+        Ok((self.max_book_height / sheet.thickness + 1e-9).floor() as usize)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookPattern {
+    pub layout: SheetLayout,
+    /// sheets cut to this pattern
+    pub sheets: usize,
+    /// sheets in each book, spread evenly
+    pub books: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverproducedPart {
+    pub part_id: String,
+    pub quantity: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookNestResult {
+    pub patterns: Vec<BookPattern>,
+    pub sheets: usize,
+    pub books: usize,
+    /// saw passes: every cut of a pattern once for each of its books
+    pub cycles: usize,
+    pub overproduced: Vec<OverproducedPart>,
+}
+
+struct Runs<'a> {
+    // sheets of each pattern's stock in one book
+    per_book: Vec<usize>,
+    demand: Vec<usize>,
+    // parts of each id on one sheet of each pattern
+    yields: Vec<Vec<usize>>,
+    cuts: Vec<usize>,
+    stock_ids: Vec<&'a str>,
+}
+
+impl Runs<'_> {
+    fn cycles(&self, runs: &[usize]) -> usize {
+        runs.iter()
+            .zip(&self.cuts)
+            .zip(&self.per_book)
+            .map(|((&n, &cuts), &per_book)| n.div_ceil(per_book) * cuts)
+            .sum()
+    }
+
+    fn produced(&self, runs: &[usize]) -> Vec<usize> {
+        (0..self.demand.len())
+            .map(|k| runs.iter().zip(&self.yields).map(|(&n, y)| n * y[k]).sum())
+            .collect()
+    }
+
+    fn overproduction(&self, runs: &[usize]) -> usize {
+        let produced: usize = self.produced(runs).iter().sum();
+        produced - self.demand.iter().sum::<usize>()
+    }
+
+    fn fits(&self, runs: &[usize], stock: &[SheetStock]) -> bool {
+        stock.iter().all(|s| {
+            let used: usize = runs
+                .iter()
+                .zip(&self.stock_ids)
+                .filter(|(_, id)| **id == s.id)
+                .map(|(&n, _)| n)
+                .sum();
+            used <= s.quantity
+        })
+    }
+
+    // Drops one pattern and makes up its parts with more sheets of the patterns still in use,
+    // or of `preferred`, each time taking the one that covers most of what is short.
+    // `preferred` wins ties, so it is tried even where a pattern in use covers as much.
+    fn without(
+        &self,
+        runs: &[usize],
+        dropped: usize,
+        preferred: Option<usize>,
+    ) -> Option<Vec<usize>> {
+        let mut trial = runs.to_vec();
+        trial[dropped] = 0;
+        loop {
+            let short: Vec<usize> = self
+                .demand
+                .iter()
+                .zip(self.produced(&trial))
+                .map(|(&want, got)| want.saturating_sub(got))
+                .collect();
+            if short.iter().all(|&s| s == 0) {
+                return Some(trial);
+            }
+            let (idx, covered) = (0..runs.len())
+                .filter(|&i| i != dropped && (runs[i] > 0 || preferred == Some(i)))
+                .map(|i| {
+                    let covered: usize = self.yields[i]
+                        .iter()
+                        .zip(&short)
+                        .map(|(&y, &s)| y.min(s))
+                        .sum();
+                    (i, covered)
+                })
+                .max_by(|a, b| {
+                    a.1.cmp(&b.1)
+                        .then((preferred == Some(a.0)).cmp(&(preferred == Some(b.0))))
+                        .then(b.0.cmp(&a.0))
+                })?;
+            if covered == 0 {
+                return None;
+            }
+            trial[idx] += 1;
+        }
+    }
+}
+
+// n sheets in as few books as the saw allows, as evenly as they go
+fn split_books(sheets: usize, per_book: usize) -> Vec<usize> {
+    let books = sheets.div_ceil(per_book);
+    (0..books)
+        .map(|b| sheets / books + usize::from(b < sheets % books))
+        .collect()
+}
+
+// a sheet of each stock cut with as many of one part as it takes
+fn single_part_layouts(
+    parts: &[RectPart],
+    stock: &[SheetStock],
+    config: &PlanarNestConfig,
+    guillotine: &GuillotineConfig,
+) -> NestResult<Vec<SheetLayout>> {
+    let mut layouts = Vec::new();
+    for part in parts {
+        for sheet in stock.iter().filter(|s| s.quantity > 0) {
+            let one = SheetStock {
+                quantity: 1,
+                ..sheet.clone()
+            };
+            let result = partial_sheets(
+                std::slice::from_ref(part),
+                &[one],
+                config,
+                PlanarStrategy::Guillotine(*guillotine),
+            )?;
+            layouts.extend(result.layouts);
+        }
+    }
+    Ok(layouts)
+}
+
+/// Nests for a beam saw that cuts books of identical sheets; a book is as many sheets as
+/// `books.max_book_height` takes at the stock's thickness. The guillotine layouts are grouped
+/// into patterns and joined by sheets cut with a single part. Patterns are then given up where
+/// more sheets of the others make up their parts: a change is kept when it saves saw cycles,
+/// uses no more than `books.max_extra_sheets` sheets over the nest and fits the stock, and the
+/// parts cut beyond `parts` stay within `books.max_overproduction`.
+pub fn book_sheets(
+    parts: &[RectPart],
+    stock: &[SheetStock],
+    config: &PlanarNestConfig,
+    guillotine: &GuillotineConfig,
+    books: &BookConfig,
+) -> NestResult<BookNestResult> {
+    let mut layouts = guillotine_sheets(parts, stock, config, guillotine)?;
+    let nested = layouts.len();
+    // numbered after the nest's sheets so the patterns can tell them apart
+    for (k, mut layout) in single_part_layouts(parts, stock, config, guillotine)?
+        .into_iter()
+        .enumerate()
+    {
+        layout.index = nested + k;
+        layouts.push(layout);
+    }
+    let patterns = sheet_patterns(&layouts);
+    let per_book = patterns
+        .iter()
+        .map(|p| {
+            let sheet = stock
+                .iter()
+                .find(|s| s.id == p.layout.stock_id)
+                .ok_or(NestError::InsufficientStock)?;
+            books.sheets_per_book(sheet)
+        })
+        .collect::<NestResult<Vec<usize>>>()?;
+
+    let mut ids: Vec<&str> = Vec::new();
+    let mut demand: Vec<usize> = Vec::new();
+    for part in parts {
+        match ids.iter().position(|id| *id == part.id) {
+            Some(k) => demand[k] += part.quantity,
+            None => {
+                ids.push(&part.id);
+                demand.push(part.quantity);
+            }
+        }
+    }
+    let plan = Runs {
+        per_book: per_book.clone(),
+        demand,
+        yields: patterns
+            .iter()
+            .map(|p| {
+                ids.iter()
+                    .map(|id| {
+                        p.layout
+                            .placements
+                            .iter()
+                            .filter(|q| q.part_id == *id)
+                            .count()
+                    })
+                    .collect()
+            })
+            .collect(),
+        cuts: patterns.iter().map(|p| p.layout.cuts.len()).collect(),
+        stock_ids: patterns
+            .iter()
+            .map(|p| p.layout.stock_id.as_str())
+            .collect(),
+    };
+
+    // only the nest's own sheets are cut to begin with
+    let mut runs: Vec<usize> = patterns
+        .iter()
+        .map(|p| p.indices.iter().filter(|&&i| i < nested).count())
+        .collect();
+    let allowed = nested + books.max_extra_sheets;
+    loop {
+        let current = plan.cycles(&runs);
+        let mut best: Option<(usize, usize, usize, Vec<usize>)> = None;
+        for dropped in (0..runs.len()).filter(|&i| runs[i] > 0) {
+            let preferences = (0..runs.len())
+                .filter(|&i| i != dropped && runs[i] == 0)
+                .map(Some);
+            for preferred in std::iter::once(None).chain(preferences) {
+                let Some(trial) = plan.without(&runs, dropped, preferred) else {
+                    continue;
+                };
+                let (cycles, over) = (plan.cycles(&trial), plan.overproduction(&trial));
+                let sheets: usize = trial.iter().sum();
+                if cycles >= current
+                    || best
+                        .as_ref()
+                        .is_some_and(|b| (cycles, sheets, over) >= (b.0, b.1, b.2))
+                    || sheets > allowed
+                    || over > books.max_overproduction
+                    || !plan.fits(&trial, stock)
+                {
+                    continue;
+                }
+                best = Some((cycles, sheets, over, trial));
+            }
+        }
+        match best {
+            Some((_, _, _, next)) => runs = next,
+            None => break,
+        }
+    }
+
+    let overproduced = ids
+        .iter()
+        .zip(plan.produced(&runs))
+        .zip(&plan.demand)
+        .filter(|((_, got), want)| got > *want)
+        .map(|((id, got), want)| OverproducedPart {
+            part_id: id.to_string(),
+            quantity: got - want,
+        })
+        .collect();
+    let cycles = plan.cycles(&runs);
+    let patterns: Vec<BookPattern> = patterns
+        .into_iter()
+        .zip(runs.iter().zip(&per_book))
+        .filter(|(_, (n, _))| **n > 0)
+        .map(|(pattern, (&n, &per_book))| BookPattern {
+            layout: pattern.layout,
+            sheets: n,
+            books: split_books(n, per_book),
+        })
+        .collect();
+    Ok(BookNestResult {
+        sheets: runs.iter().sum(),
+        books: patterns.iter().map(|p| p.books.len()).sum(),
+        cycles,
+        overproduced,
+        patterns,
+    })
+}
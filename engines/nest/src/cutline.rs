@@ -0,0 +1,163 @@
+use crate::exclusion::{carve_offcuts, excluded_area, union_area};
+use crate::guillotine::{CutAxis, GuillotineCut};
+use crate::metrics::{MetricKind, UtilizationBreakdown};
+use crate::planar::{OffcutRect, PlanarNestConfig, RectPlacement, SheetLayout, SheetStock};
+use crate::util::cmp_f64_desc;
+
+const EPS: f64 = 1e-9;
+
+/// One straight pass of the saw or router. As for guillotine cuts, `position` is where the kerf
+/// starts: the y of a cut along X, the x of a cut along Y.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CutSegment {
+    pub axis: CutAxis,
+    pub position: f64,
+    pub start: f64,
+    pub end: f64,
+    pub kerf: f64,
+}
+
+impl CutSegment {
+    // the strip of sheet the kerf takes
+    pub(crate) fn strip(&self) -> OffcutRect {
+        match self.axis {
+            CutAxis::AlongX => OffcutRect {
+                x: self.start,
+                y: self.position,
+                width: self.end - self.start,
+                height: self.kerf,
+            },
+            CutAxis::AlongY => OffcutRect {
+                x: self.position,
+                y: self.start,
+                width: self.kerf,
+                height: self.end - self.start,
+            },
+        }
+    }
+}
+
+impl From<&GuillotineCut> for CutSegment {
+    fn from(cut: &GuillotineCut) -> Self {
+        CutSegment {
+            axis: cut.axis,
+            position: cut.position,
+            start: cut.start,
+            end: cut.end,
+            kerf: cut.kerf,
+        }
+    }
+}
+
+// A cut round every side of every part, the kerf outside the part and running on a kerf past
+// each corner so the part comes free. Sides on the trim line are cut with the trim; the rest
+// is kept inside the trim. Passes on one line that meet are joined.
+fn edge_segments(
+    placements: &[RectPlacement],
+    sheet: &SheetStock,
+    kerf: f64,
+    trim: f64,
+) -> Vec<CutSegment> {
+    let (left, bottom) = (trim, trim);
+    let (right, top) = (sheet.width - trim, sheet.height - trim);
+    let mut segments = Vec::with_capacity(placements.len() * 4);
+    for p in placements {
+        let along_x = (p.x - kerf).max(left)..(p.x + p.width + kerf).min(right);
+        let along_y = (p.y - kerf).max(bottom)..(p.y + p.height + kerf).min(top);
+        let sides = [
+            (CutAxis::AlongY, p.x - kerf, p.x > left + EPS, &along_y),
+            (
+                CutAxis::AlongY,
+                p.x + p.width,
+                p.x + p.width < right - EPS,
+                &along_y,
+            ),
+            (CutAxis::AlongX, p.y - kerf, p.y > bottom + EPS, &along_x),
+            (
+                CutAxis::AlongX,
+                p.y + p.height,
+                p.y + p.height < top - EPS,
+                &along_x,
+            ),
+        ];
+        for (axis, position, inside, span) in sides {
+            if !inside || span.end - span.start <= EPS {
+                continue;
+            }
+            let limit = match axis {
+                CutAxis::AlongX => (bottom, top),
+                CutAxis::AlongY => (left, right),
+            };
+            // a part closer than a kerf to the trim shares that side of the cut with it
+            let (from, to) = (position.max(limit.0), (position + kerf).min(limit.1));
+            segments.push(CutSegment {
+                axis,
+                position: from,
+                start: span.start,
+                end: span.end,
+                kerf: (to - from).max(0.0),
+            });
+        }
+    }
+    merge(segments)
+}
+
+fn merge(mut segments: Vec<CutSegment>) -> Vec<CutSegment> {
+    let key = |s: &CutSegment| (s.axis == CutAxis::AlongY) as u8;
+    segments.sort_by(|a, b| {
+        key(a)
+            .cmp(&key(b))
+            .then(cmp_f64_desc(b.position, a.position))
+            .then(cmp_f64_desc(b.kerf, a.kerf))
+            .then(cmp_f64_desc(b.start, a.start))
+    });
+    let mut merged: Vec<CutSegment> = Vec::with_capacity(segments.len());
+    for segment in segments {
+        match merged.last_mut() {
+            Some(last)
+                if last.axis == segment.axis
+                    && (last.position - segment.position).abs() <= EPS
+                    && (last.kerf - segment.kerf).abs() <= EPS
+                    && segment.start <= last.end + EPS =>
+            {
+                last.end = last.end.max(segment.end);
+            }
+            _ => merged.push(segment),
+        }
+    }
+    merged
+}
+
+// area the kerf strips cover, counting crossings once
+fn kerf_area(segments: &[CutSegment]) -> f64 {
+    let strips: Vec<OffcutRect> = segments.iter().map(CutSegment::strip).collect();
+    union_area(&strips)
+}
+
+// Derives the cuts of a finished layout (its guillotine cuts where it has them, else a cut
+// round every part), takes their kerf out of the offcuts and works out the metrics from the
+// placements and the cuts.
+pub(crate) fn measure_layout(
+    layout: &mut SheetLayout,
+    sheet: &SheetStock,
+    config: &PlanarNestConfig,
+) {
+    let trim = config.trim;
+    layout.segments = if layout.cuts.is_empty() {
+        edge_segments(&layout.placements, sheet, config.kerf, trim)
+    } else {
+        layout.cuts.iter().map(CutSegment::from).collect()
+    };
+    let strips: Vec<OffcutRect> = layout.segments.iter().map(CutSegment::strip).collect();
+    layout.offcuts = carve_offcuts(std::mem::take(&mut layout.offcuts), &strips);
+
+    let mut metrics = UtilizationBreakdown::new(MetricKind::Area);
+    metrics.stock_total = sheet.width * sheet.height;
+    metrics.trim_loss = (2.0 * (sheet.width + sheet.height) * trim - 4.0 * trim * trim).max(0.0);
+    metrics.utilized = layout.placements.iter().map(|p| p.width * p.height).sum();
+    metrics.kerf_loss = kerf_area(&layout.segments);
+    metrics.defect_loss = excluded_area(sheet, trim);
+    let occupied = metrics.utilized + metrics.kerf_loss + metrics.trim_loss + metrics.defect_loss;
+    metrics.offcut_loss = (metrics.stock_total - occupied).max(0.0);
+    layout.metrics = metrics;
+}
@@ -0,0 +1,106 @@
+use crate::cutline::measure_layout;
+use crate::error::{NestError, NestResult};
+use crate::planar::{PlanarNestConfig, RectPart, SheetLayout, SheetStock};
+use crate::remnant::extract_remnants;
+use crate::verify::{LayoutViolation, check_placements};
+
+/// Placements are named by their position in `SheetLayout::placements`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayoutEdit {
+    /// lower-left corner to (x, y)
+    Move {
+        index: usize,
+        x: f64,
+        y: f64,
+    },
+    /// a quarter turn about the lower-left corner
+    Rotate {
+        index: usize,
+    },
+    /// the two trade lower-left corners
+    Swap {
+        first: usize,
+        second: usize,
+    },
+    Delete {
+        index: usize,
+    },
+}
+
+/// Applies one edit to a layout on `sheet` and checks the placements it moved against the
+/// trim, the zones, the kerf and the grain of their part in `parts`. A valid edit comes back
+/// with offcuts and metrics worked out again. Guillotine cuts are kept only for a delete, as
+/// other edits need not leave a guillotine layout.
+pub fn edit_layout(
+    layout: &SheetLayout,
+    edit: LayoutEdit,
+    sheet: &SheetStock,
+    parts: &[RectPart],
+    config: &PlanarNestConfig,
+) -> NestResult<SheetLayout> {
+    if sheet.id != layout.stock_id {
+        return Err(NestError::InvalidDimension(
+            "the layout is not on the given sheet",
+        ));
+    }
+    let count = layout.placements.len();
+    let named: Vec<usize> = match edit {
+        LayoutEdit::Move { index, .. }
+        | LayoutEdit::Rotate { index }
+        | LayoutEdit::Delete { index } => vec![index],
+        LayoutEdit::Swap { first, second } => vec![first, second],
+    };
+    let mut refused: Vec<LayoutViolation> = named
+        .iter()
+        .filter(|&&idx| idx >= count)
+        .map(|&idx| LayoutViolation::NoSuchPlacement(idx))
+        .collect();
+    refused.extend(
+        named
+            .iter()
+            .filter(|&&idx| idx < count && layout.placements[idx].locked)
+            .map(|&idx| LayoutViolation::Locked(idx)),
+    );
+    if !refused.is_empty() {
+        return Err(NestError::InvalidEdit(refused));
+    }
+
+    let mut next = layout.clone();
+    let touched = match edit {
+        LayoutEdit::Move { index, x, y } => {
+            next.placements[index].x = x;
+            next.placements[index].y = y;
+            vec![index]
+        }
+        LayoutEdit::Rotate { index } => {
+            let p = &mut next.placements[index];
+            std::mem::swap(&mut p.width, &mut p.height);
+            std::mem::swap(&mut p.finished_width, &mut p.finished_height);
+            p.rotated = !p.rotated;
+            vec![index]
+        }
+        LayoutEdit::Swap { first, second } => {
+            let (x, y) = (next.placements[first].x, next.placements[first].y);
+            next.placements[first].x = next.placements[second].x;
+            next.placements[first].y = next.placements[second].y;
+            next.placements[second].x = x;
+            next.placements[second].y = y;
+            vec![first, second]
+        }
+        LayoutEdit::Delete { index } => {
+            next.placements.remove(index);
+            Vec::new()
+        }
+    };
+    let violations = check_placements(&next, &touched, sheet, parts, config);
+    if !violations.is_empty() {
+        return Err(NestError::InvalidEdit(violations));
+    }
+
+    if !touched.is_empty() {
+        next.cuts.clear();
+    }
+    next.offcuts = extract_remnants(&next, sheet, config, 0.0, 0.0);
+    measure_layout(&mut next, sheet, config);
+    Ok(next)
+}
@@ -1,9 +1,13 @@
 use std::fmt::{self, Display};
 
+use crate::verify::LayoutViolation;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum NestError {
     InsufficientStock,
     InvalidDimension(&'static str),
+    GroupDoesNotFit(String),
+    InvalidEdit(Vec<LayoutViolation>),
 }
 
 impl Display for NestError {
@@ -11,6 +15,13 @@ impl Display for NestError {
         match self {
             NestError::InsufficientStock => write!(f, "insufficient stock to satisfy all parts"),
             NestError::InvalidDimension(msg) => write!(f, "invalid dimensions: {msg}"),
+            NestError::GroupDoesNotFit(id) => {
+                write!(f, "no sheet can hold part group `{id}` in one piece")
+            }
+            NestError::InvalidEdit(violations) => {
+                let reasons: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+                write!(f, "edit rejected: {}", reasons.join("; "))
+            }
         }
     }
 }
@@ -0,0 +1,266 @@
+use crate::geometry::{bounds, clip_half_plane, convex_parts, signed_area};
+use crate::planar::{OffcutRect, SheetStock};
+use crate::shape::Point;
+
+const EPS: f64 = 1e-9;
+
+/// Part of a sheet nothing may be cut from: a damaged corner, a clamp or a vacuum pod. Sheet
+/// coordinates, same origin as the placements. Zones on one sheet must not overlap.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExclusionZone {
+    Rect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    },
+    /// The shape solver keeps to its outline. The rectangle solvers approximate it by its
+    /// bounding box and keep clear of all of it; `defect_loss` still counts only the outline,
+    /// and the rest of the box is lost as `offcut_loss`.
+    Polygon(Vec<Point>),
+}
+
+impl ExclusionZone {
+    pub(crate) fn outline(&self) -> Vec<Point> {
+        match self {
+            ExclusionZone::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => vec![
+                Point { x: *x, y: *y },
+                Point {
+                    x: x + width,
+                    y: *y,
+                },
+                Point {
+                    x: x + width,
+                    y: y + height,
+                },
+                Point {
+                    x: *x,
+                    y: y + height,
+                },
+            ],
+            ExclusionZone::Polygon(points) => points.clone(),
+        }
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        match self {
+            ExclusionZone::Rect { width, height, .. } => *width > 0.0 && *height > 0.0,
+            ExclusionZone::Polygon(points) => points.len() >= 3 && signed_area(points).abs() > EPS,
+        }
+    }
+}
+
+pub(crate) fn intersects(a: &OffcutRect, b: &OffcutRect) -> bool {
+    a.x < b.x + b.width - EPS
+        && b.x < a.x + a.width - EPS
+        && a.y < b.y + b.height - EPS
+        && b.y < a.y + a.height - EPS
+}
+
+fn clip(rect: &OffcutRect, bounds: &OffcutRect) -> Option<OffcutRect> {
+    let x = rect.x.max(bounds.x);
+    let y = rect.y.max(bounds.y);
+    let width = (rect.x + rect.width).min(bounds.x + bounds.width) - x;
+    let height = (rect.y + rect.height).min(bounds.y + bounds.height) - y;
+    (width > EPS && height > EPS).then_some(OffcutRect {
+        x,
+        y,
+        width,
+        height,
+    })
+}
+
+fn usable(stock: &SheetStock, trim: f64) -> OffcutRect {
+    OffcutRect {
+        x: trim,
+        y: trim,
+        width: stock.width - 2.0 * trim,
+        height: stock.height - 2.0 * trim,
+    }
+}
+
+// Bounding box of every zone on the sheet, grown by `clearance` on each side and cut down to
+// the area inside the trim.
+pub(crate) fn blocked_rects(stock: &SheetStock, trim: f64, clearance: f64) -> Vec<OffcutRect> {
+    let usable = usable(stock, trim);
+    stock
+        .exclusions
+        .iter()
+        .filter_map(|zone| {
+            let (x0, y0, x1, y1) = bounds(&zone.outline());
+            clip(
+                &OffcutRect {
+                    x: x0 - clearance,
+                    y: y0 - clearance,
+                    width: x1 - x0 + 2.0 * clearance,
+                    height: y1 - y0 + 2.0 * clearance,
+                },
+                &usable,
+            )
+        })
+        .collect()
+}
+
+// Area of the zone outlines inside the trim; `zones_overlap` keeps them apart.
+pub(crate) fn excluded_area(stock: &SheetStock, trim: f64) -> f64 {
+    let usable = usable(stock, trim);
+    let corners = [
+        Point {
+            x: usable.x,
+            y: usable.y,
+        },
+        Point {
+            x: usable.x + usable.width,
+            y: usable.y,
+        },
+        Point {
+            x: usable.x + usable.width,
+            y: usable.y + usable.height,
+        },
+        Point {
+            x: usable.x,
+            y: usable.y + usable.height,
+        },
+    ];
+    stock
+        .exclusions
+        .iter()
+        .map(|zone| {
+            let mut ring = zone.outline();
+            if signed_area(&ring) < 0.0 {
+                ring.reverse();
+            }
+            for i in 0..4 {
+                ring = clip_half_plane(&ring, corners[i], corners[(i + 1) % 4]);
+            }
+            if ring.len() < 3 {
+                0.0
+            } else {
+                signed_area(&ring).abs()
+            }
+        })
+        .sum()
+}
+
+// area of one counter-clockwise zone outline inside another
+fn shared_area(a: &[Point], b: &[Point]) -> f64 {
+    let mut area = 0.0;
+    for piece in convex_parts(a) {
+        for other in convex_parts(b) {
+            let mut ring = piece.clone();
+            for i in 0..other.len() {
+                ring = clip_half_plane(&ring, other[i], other[(i + 1) % other.len()]);
+            }
+            if ring.len() >= 3 {
+                area += signed_area(&ring).abs();
+            }
+        }
+    }
+    area
+}
+
+// whether any two zones of the sheet share area; touching is fine
+pub(crate) fn zones_overlap(stock: &SheetStock) -> bool {
+    let rings: Vec<Vec<Point>> = stock
+        .exclusions
+        .iter()
+        .map(|zone| {
+            let mut ring = zone.outline();
+            if signed_area(&ring) < 0.0 {
+                ring.reverse();
+            }
+            ring
+        })
+        .collect();
+    (0..rings.len()).any(|i| (i + 1..rings.len()).any(|j| shared_area(&rings[i], &rings[j]) > EPS))
+}
+
+// area the rectangles cover, counting overlaps once
+pub(crate) fn union_area(rects: &[OffcutRect]) -> f64 {
+    let rects: Vec<&OffcutRect> = rects
+        .iter()
+        .filter(|r| r.width > EPS && r.height > EPS)
+        .collect();
+    let mut xs: Vec<f64> = rects.iter().flat_map(|r| [r.x, r.x + r.width]).collect();
+    xs.sort_by(|a, b| a.total_cmp(b));
+    xs.dedup_by(|a, b| (*a - *b).abs() <= EPS);
+    let mut area = 0.0;
+    for pair in xs.windows(2) {
+        let (x0, x1) = (pair[0], pair[1]);
+        let mut spans: Vec<(f64, f64)> = rects
+            .iter()
+            .filter(|r| r.x <= x0 + EPS && r.x + r.width >= x1 - EPS)
+            .map(|r| (r.y, r.y + r.height))
+            .collect();
+        spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut covered = 0.0;
+        let mut reach = f64::NEG_INFINITY;
+        for (start, end) in spans {
+            let from = start.max(reach);
+            if end > from {
+                covered += end - from;
+            }
+            reach = reach.max(end);
+        }
+        area += covered * (x1 - x0);
+    }
+    area
+}
+
+// disjoint pieces of `rect` outside `taken`
+fn subtract(rect: &OffcutRect, taken: &OffcutRect, out: &mut Vec<OffcutRect>) {
+    if !intersects(rect, taken) {
+        out.push(rect.clone());
+        return;
+    }
+    let (right, top) = (rect.x + rect.width, rect.y + rect.height);
+    let left = taken.x.max(rect.x);
+    let cut_right = (taken.x + taken.width).min(right);
+    let pieces = [
+        OffcutRect {
+            x: rect.x,
+            y: rect.y,
+            width: left - rect.x,
+            height: rect.height,
+        },
+        OffcutRect {
+            x: cut_right,
+            y: rect.y,
+            width: right - cut_right,
+            height: rect.height,
+        },
+        OffcutRect {
+            x: left,
+            y: rect.y,
+            width: cut_right - left,
+            height: taken.y - rect.y,
+        },
+        OffcutRect {
+            x: left,
+            y: taken.y + taken.height,
+            width: cut_right - left,
+            height: top - (taken.y + taken.height),
+        },
+    ];
+    out.extend(
+        pieces
+            .into_iter()
+            .filter(|r| r.width > EPS && r.height > EPS),
+    );
+}
+
+// offcuts with every blocked rectangle taken out of them
+pub(crate) fn carve_offcuts(offcuts: Vec<OffcutRect>, blocked: &[OffcutRect]) -> Vec<OffcutRect> {
+    blocked.iter().fold(offcuts, |pending, taken| {
+        let mut rest = Vec::with_capacity(pending.len());
+        for rect in &pending {
+            subtract(rect, taken, &mut rest);
+        }
+        rest
+    })
+}
@@ -0,0 +1,321 @@
+use crate::shape::Point;
+
+const EPS: f64 = 1e-9;
+
+fn cross(o: Point, a: Point, b: Point) -> f64 {
+    (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+}
+
+pub(crate) fn signed_area(ring: &[Point]) -> f64 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+pub(crate) fn perimeter(ring: &[Point]) -> f64 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % n]);
+            (b.x - a.x).hypot(b.y - a.y)
+        })
+        .sum()
+}
+
+// counter-clockwise copy without repeated or collinear vertices
+pub(crate) fn normalize_ring(ring: &[Point]) -> Vec<Point> {
+    let mut points: Vec<Point> = Vec::with_capacity(ring.len());
+    for &p in ring {
+        if points
+            .last()
+            .is_none_or(|q| (q.x - p.x).abs() > EPS || (q.y - p.y).abs() > EPS)
+        {
+            points.push(p);
+        }
+    }
+    while points.len() > 1 {
+        let (first, last) = (points[0], points[points.len() - 1]);
+        if (first.x - last.x).abs() > EPS || (first.y - last.y).abs() > EPS {
+            break;
+        }
+        points.pop();
+    }
+    if signed_area(&points) < 0.0 {
+        points.reverse();
+    }
+    let mut i = 0;
+    while points.len() > 3 && i < points.len() {
+        let n = points.len();
+        let (prev, next) = (points[(i + n - 1) % n], points[(i + 1) % n]);
+        if cross(prev, points[i], next).abs() <= EPS {
+            points.remove(i);
+            i = i.saturating_sub(1);
+        } else {
+            i += 1;
+        }
+    }
+    points
+}
+
+pub(crate) fn rotate(ring: &[Point], degrees: f64) -> Vec<Point> {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    ring.iter()
+        .map(|p| Point {
+            x: p.x * cos - p.y * sin,
+            y: p.x * sin + p.y * cos,
+        })
+        .collect()
+}
+
+pub(crate) fn translate(ring: &[Point], dx: f64, dy: f64) -> Vec<Point> {
+    ring.iter()
+        .map(|p| Point {
+            x: p.x + dx,
+            y: p.y + dy,
+        })
+        .collect()
+}
+
+// (min x, min y, max x, max y)
+pub(crate) fn bounds(ring: &[Point]) -> (f64, f64, f64, f64) {
+    ring.iter().fold(
+        (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+        |(x0, y0, x1, y1), p| (x0.min(p.x), y0.min(p.y), x1.max(p.x), y1.max(p.y)),
+    )
+}
+
+fn inside_triangle(p: Point, a: Point, b: Point, c: Point) -> bool {
+    cross(a, b, p) >= -EPS && cross(b, c, p) >= -EPS && cross(c, a, p) >= -EPS
+}
+
+fn is_convex(ring: &[Point]) -> bool {
+    let n = ring.len();
+    (0..n).all(|i| cross(ring[i], ring[(i + 1) % n], ring[(i + 2) % n]) >= -EPS)
+}
+
+// Ear-clips a counter-clockwise simple ring into triangles, then merges neighbours across
+// shared diagonals while the result stays convex (Hertel-Mehlhorn).
+pub(crate) fn convex_parts(ring: &[Point]) -> Vec<Vec<Point>> {
+    if is_convex(ring) {
+        return vec![ring.to_vec()];
+    }
+    let mut remaining: Vec<usize> = (0..ring.len()).collect();
+    let mut pieces: Vec<Vec<usize>> = Vec::new();
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            );
+            cross(ring[a], ring[b], ring[c]) > EPS
+                && remaining
+                    .iter()
+                    .filter(|&&v| v != a && v != b && v != c)
+                    .all(|&v| !inside_triangle(ring[v], ring[a], ring[b], ring[c]))
+        });
+        // a degenerate ring has no proper ear left; clip the sharpest corner instead
+        let i = ear.unwrap_or(0);
+        pieces.push(vec![
+            remaining[(i + n - 1) % n],
+            remaining[i],
+            remaining[(i + 1) % n],
+        ]);
+        remaining.remove(i);
+    }
+    pieces.push(remaining);
+
+    let mut merged = true;
+    while merged {
+        merged = false;
+        'search: for i in 0..pieces.len() {
+            for j in i + 1..pieces.len() {
+                if let Some(piece) = merge_across_diagonal(&pieces[i], &pieces[j])
+                    && is_convex(&piece.iter().map(|&v| ring[v]).collect::<Vec<_>>())
+                {
+                    pieces[i] = piece;
+                    pieces.swap_remove(j);
+                    merged = true;
+                    break 'search;
+                }
+            }
+        }
+    }
+    pieces
+        .into_iter()
+        .map(|piece| piece.into_iter().map(|v| ring[v]).collect())
+        .collect()
+}
+
+fn merge_across_diagonal(a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    let (na, nb) = (a.len(), b.len());
+    for i in 0..na {
+        let (u, v) = (a[i], a[(i + 1) % na]);
+        if let Some(j) = (0..nb).find(|&j| b[j] == v && b[(j + 1) % nb] == u) {
+            // walk `a` from v round to u, then `b` from u round to v, skipping the shared ends
+            let mut piece: Vec<usize> = (1..=na).map(|k| a[(i + k) % na]).collect();
+            piece.extend((2..nb).map(|k| b[(j + k) % nb]));
+            return Some(piece);
+        }
+    }
+    None
+}
+
+// counter-clockwise hull, Andrew's monotone chain
+pub(crate) fn convex_hull(mut points: Vec<Point>) -> Vec<Point> {
+    points.sort_by(|a, b| {
+        a.x.partial_cmp(&b.x)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    if points.len() < 3 {
+        return points;
+    }
+    let mut hull: Vec<Point> = Vec::with_capacity(points.len() * 2);
+    for &p in &points {
+        while hull.len() >= 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= EPS {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    let lower = hull.len() + 1;
+    for &p in points.iter().rev().skip(1) {
+        while hull.len() >= lower && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= EPS {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    hull.pop();
+    hull
+}
+
+pub(crate) fn minkowski(a: &[Point], b: &[Point]) -> Vec<Point> {
+    let sums = a
+        .iter()
+        .flat_map(|p| {
+            b.iter().map(move |q| Point {
+                x: p.x + q.x,
+                y: p.y + q.y,
+            })
+        })
+        .collect();
+    convex_hull(sums)
+}
+
+pub(crate) fn negate(ring: &[Point]) -> Vec<Point> {
+    ring.iter().map(|p| Point { x: -p.x, y: -p.y }).collect()
+}
+
+// regular polygon around a circle of `radius`, so spacing by it never falls short
+pub(crate) fn disc(radius: f64, sides: usize) -> Vec<Point> {
+    let outer = radius / (std::f64::consts::PI / sides as f64).cos();
+    (0..sides)
+        .map(|k| {
+            let angle = 2.0 * std::f64::consts::PI * k as f64 / sides as f64;
+            Point {
+                x: outer * angle.cos(),
+                y: outer * angle.sin(),
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn strictly_inside_convex(ring: &[Point], p: Point, tolerance: f64) -> bool {
+    let n = ring.len();
+    n >= 3 && (0..n).all(|i| cross(ring[i], ring[(i + 1) % n], p) > tolerance)
+}
+
+pub(crate) fn segment_intersection(a0: Point, a1: Point, b0: Point, b1: Point) -> Option<Point> {
+    let (dx, dy) = (a1.x - a0.x, a1.y - a0.y);
+    let (ex, ey) = (b1.x - b0.x, b1.y - b0.y);
+    let denom = dx * ey - dy * ex;
+    if denom.abs() <= EPS {
+        return None;
+    }
+    let t = ((b0.x - a0.x) * ey - (b0.y - a0.y) * ex) / denom;
+    let u = ((b0.x - a0.x) * dy - (b0.y - a0.y) * dx) / denom;
+    ((-EPS..=1.0 + EPS).contains(&t) && (-EPS..=1.0 + EPS).contains(&u)).then_some(Point {
+        x: a0.x + t * dx,
+        y: a0.y + t * dy,
+    })
+}
+
+// Sutherland-Hodgman step: the part of `ring` on the left of the line through `a` towards `b`
+pub(crate) fn clip_half_plane(ring: &[Point], a: Point, b: Point) -> Vec<Point> {
+    let side = |p: Point| cross(a, b, p);
+    let n = ring.len();
+    let mut out = Vec::with_capacity(n + 1);
+    for i in 0..n {
+        let (p, q) = (ring[i], ring[(i + 1) % n]);
+        let (sp, sq) = (side(p), side(q));
+        if sp >= -EPS {
+            out.push(p);
+        }
+        if (sp < -EPS && sq > EPS) || (sp > EPS && sq < -EPS) {
+            let t = sp / (sp - sq);
+            out.push(Point {
+                x: p.x + t * (q.x - p.x),
+                y: p.y + t * (q.y - p.y),
+            });
+        }
+    }
+    out
+}
+
+// Positions `p` that keep every one of `points` moved by `p` inside the convex `ring`: each
+// edge of the ring pulled in by the point that reaches furthest past it.
+pub(crate) fn inner_fit(ring: &[Point], points: &[Point]) -> Vec<Point> {
+    let (x0, y0, x1, y1) = bounds(ring);
+    let (u0, v0, u1, v1) = bounds(points);
+    let mut region = vec![
+        Point {
+            x: x0 - u1,
+            y: y0 - v1,
+        },
+        Point {
+            x: x1 - u0,
+            y: y0 - v1,
+        },
+        Point {
+            x: x1 - u0,
+            y: y1 - v0,
+        },
+        Point {
+            x: x0 - u1,
+            y: y1 - v0,
+        },
+    ];
+    let n = ring.len();
+    for i in 0..n {
+        let (a, b) = (ring[i], ring[(i + 1) % n]);
+        let Some(v) = points.iter().copied().min_by(|p, q| {
+            let reach = |v: Point| (b.x - a.x) * v.y - (b.y - a.y) * v.x;
+            reach(*p)
+                .partial_cmp(&reach(*q))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        }) else {
+            return region;
+        };
+        region = clip_half_plane(
+            &region,
+            Point {
+                x: a.x - v.x,
+                y: a.y - v.y,
+            },
+            Point {
+                x: b.x - v.x,
+                y: b.y - v.y,
+            },
+        );
+        if region.is_empty() {
+            break;
+        }
+    }
+    region
+}
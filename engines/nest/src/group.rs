@@ -0,0 +1,261 @@
+use crate::cutline::measure_layout;
+use crate::error::{NestError, NestResult};
+use crate::planar::{
+    BlockMembers, FaceGrade, GrainDirection, PlanarNestConfig, PlanarStrategy, RectPart,
+    RectPlacement, SheetLayout, SheetStock, validate_inputs,
+};
+use crate::unplaced::UnplacedReason;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupArrangement {
+    /// side by side, first member on the left
+    Row,
+    /// stacked, first member at the bottom
+    Column,
+}
+
+/// Parts cut next to each other from one sheet in a set order so their grain runs on from one
+/// to the next. Each member names a part and takes one of its quantity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartGroup {
+    pub id: String,
+    pub members: Vec<String>,
+    pub arrangement: GroupArrangement,
+}
+
+struct Member {
+    id: String,
+    width: f64,
+    height: f64,
+    finished_width: f64,
+    finished_height: f64,
+}
+
+// A group packs as one rectangle spanning all its members. Turning it a quarter turns every
+// member with it, so a row comes back as a column and the other way round.
+struct Block {
+    id: String,
+    members: Vec<Member>,
+    arrangement: GroupArrangement,
+}
+
+impl Block {
+    fn part(&self, kerf: f64, grain: GrainDirection, face: FaceGrade) -> RectPart {
+        let gaps = kerf * (self.members.len() - 1) as f64;
+        let (along, across): (f64, f64) = match self.arrangement {
+            GroupArrangement::Row => (
+                self.members.iter().map(|m| m.width).sum::<f64>() + gaps,
+                self.members.iter().map(|m| m.height).fold(0.0, f64::max),
+            ),
+            GroupArrangement::Column => (
+                self.members.iter().map(|m| m.height).sum::<f64>() + gaps,
+                self.members.iter().map(|m| m.width).fold(0.0, f64::max),
+            ),
+        };
+        let (width, height) = match self.arrangement {
+            GroupArrangement::Row => (along, across),
+            GroupArrangement::Column => (across, along),
+        };
+        RectPart {
+            id: self.id.clone(),
+            width,
+            height,
+            quantity: 1,
+            grain,
+            face,
+            ..RectPart::default()
+        }
+    }
+
+    // the members as the guillotine saw parts them
+    fn members(&self) -> BlockMembers {
+        let along_width = self.arrangement == GroupArrangement::Row;
+        let sizes = self
+            .members
+            .iter()
+            .map(|m| {
+                if along_width {
+                    (m.width, m.height)
+                } else {
+                    (m.height, m.width)
+                }
+            })
+            .collect();
+        BlockMembers { along_width, sizes }
+    }
+
+    // the members' placements inside the block's
+    fn expand(&self, block: &RectPlacement, kerf: f64) -> Vec<RectPlacement> {
+        // a row turned a quarter runs up the sheet, a column turned runs across it
+        let across_x = (self.arrangement == GroupArrangement::Row) != block.rotated;
+        let mut placements = Vec::with_capacity(self.members.len());
+        let mut cursor = if across_x { block.x } else { block.y };
+        for member in &self.members {
+            let (width, height, finished_width, finished_height) = if block.rotated {
+                (
+                    member.height,
+                    member.width,
+                    member.finished_height,
+                    member.finished_width,
+                )
+            } else {
+                (
+                    member.width,
+                    member.height,
+                    member.finished_width,
+                    member.finished_height,
+                )
+            };
+            let (x, y) = if across_x {
+                (cursor, block.y)
+            } else {
+                (block.x, cursor)
+            };
+            placements.push(RectPlacement {
+                part_id: member.id.clone(),
+                x,
+                y,
+                width,
+                height,
+                rotated: block.rotated,
+                flipped: block.flipped,
+                finished_width,
+                finished_height,
+                locked: false,
+            });
+            cursor += if across_x { width } else { height } + kerf;
+        }
+        placements
+    }
+}
+
+fn build_blocks(
+    parts: &[RectPart],
+    groups: &[PartGroup],
+) -> NestResult<(Vec<RectPart>, Vec<Block>)> {
+    let mut remaining = parts.to_vec();
+    let mut blocks = Vec::with_capacity(groups.len());
+    for group in groups {
+        if group.members.is_empty() {
+            return Err(NestError::InvalidDimension(
+                "part groups need at least one member",
+            ));
+        }
+        if parts.iter().any(|p| p.id == group.id)
+            || groups.iter().filter(|g| g.id == group.id).count() > 1
+        {
+            return Err(NestError::InvalidDimension(
+                "group ids must differ from each other and from part ids",
+            ));
+        }
+        let mut members = Vec::with_capacity(group.members.len());
+        for id in &group.members {
+            let Some(part) = remaining.iter_mut().find(|p| &p.id == id) else {
+                return Err(NestError::InvalidDimension(
+                    "group member is not a listed part",
+                ));
+            };
+            if part.quantity == 0 {
+                return Err(NestError::InvalidDimension(
+                    "groups take more of a part than its quantity",
+                ));
+            }
+            part.quantity -= 1;
+            let (width, height) = part.cut_size();
+            members.push(Member {
+                id: id.clone(),
+                width,
+                height,
+                finished_width: part.width,
+                finished_height: part.height,
+            });
+        }
+        blocks.push(Block {
+            id: group.id.clone(),
+            members,
+            arrangement: group.arrangement,
+        });
+    }
+    remaining.retain(|p| p.quantity > 0);
+    Ok((remaining, blocks))
+}
+
+// Members share one grain so it can run on through the group; the block needs the best face
+// any member asks for.
+fn block_grain_and_face(
+    parts: &[RectPart],
+    group: &PartGroup,
+) -> NestResult<(GrainDirection, FaceGrade)> {
+    let mut grain = GrainDirection::Either;
+    let mut face = FaceGrade::B;
+    for id in &group.members {
+        let part = parts.iter().find(|p| &p.id == id).expect("member checked");
+        match (grain, part.grain) {
+            (_, GrainDirection::Either) => {}
+            (GrainDirection::Either, member) => grain = member,
+            (current, member) if current != member => {
+                return Err(NestError::InvalidDimension(
+                    "group members must share a grain direction",
+                ));
+            }
+            _ => {}
+        }
+        face = face.min(part.face);
+    }
+    Ok((grain, face))
+}
+
+/// Nests the parts with every group kept together on one sheet, members next to each other in
+/// the declared order. A group no sheet can hold fails with `GroupDoesNotFit`. Under
+/// `Guillotine` the last stage's cuts part the members, so a group its grain keeps from lying
+/// across them fails the same way.
+pub fn grouped_sheets(
+    parts: &[RectPart],
+    groups: &[PartGroup],
+    stock: &[SheetStock],
+    config: &PlanarNestConfig,
+    strategy: PlanarStrategy,
+) -> NestResult<Vec<SheetLayout>> {
+    validate_inputs(parts, stock)?;
+    let (mut packed, blocks) = build_blocks(parts, groups)?;
+    for (group, block) in groups.iter().zip(&blocks) {
+        let (grain, face) = block_grain_and_face(parts, group)?;
+        packed.push(block.part(config.kerf, grain, face));
+    }
+
+    let mut instances = strategy.order(&packed, config.seed)?;
+    for instance in &mut instances {
+        if let Some(block) = blocks.iter().find(|b| b.id == instance.id) {
+            instance.members = Some(block.members());
+        }
+    }
+    let result = strategy.pack(&instances, stock, config)?;
+    if let Some(lost) = result.unplaced.iter().find(|u| {
+        u.reason != UnplacedReason::StockExhausted && blocks.iter().any(|b| b.id == u.part_id)
+    }) {
+        return Err(NestError::GroupDoesNotFit(lost.part_id.clone()));
+    }
+    if !result.unplaced.is_empty() {
+        return Err(NestError::InsufficientStock);
+    }
+
+    let mut layouts = result.layouts;
+    for layout in &mut layouts {
+        let mut placements = Vec::with_capacity(layout.placements.len());
+        for placement in layout.placements.drain(..) {
+            let Some(block) = blocks.iter().find(|b| b.id == placement.part_id) else {
+                placements.push(placement);
+                continue;
+            };
+            placements.extend(block.expand(&placement, config.kerf));
+        }
+        layout.placements = placements;
+        // the block was counted as one part; measure its members and the cuts between them
+        let sheet = stock
+            .iter()
+            .find(|s| s.id == layout.stock_id)
+            .expect("layouts are on listed stock");
+        measure_layout(layout, sheet, config);
+    }
+    Ok(layouts)
+}
@@ -0,0 +1,521 @@
+use crate::cutline::measure_layout;
+use crate::error::{NestError, NestResult};
+use crate::exclusion::{blocked_rects, carve_offcuts, intersects};
+use crate::metrics::{MetricKind, UtilizationBreakdown};
+use crate::planar::{
+    OffcutRect, Orientation, PlanarNestConfig, RectInstance, RectPart, RectPlacement, SheetLayout,
+    SheetPacker, SheetStock, complete_layouts, pack_instances, validate_inputs,
+};
+use crate::util::{cmp_f64_desc, hash_with_seed};
+
+const EPS: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CutAxis {
+    AlongX,
+    AlongY,
+}
+
+impl CutAxis {
+    fn across(self) -> Self {
+        match self {
+            CutAxis::AlongX => CutAxis::AlongY,
+            CutAxis::AlongY => CutAxis::AlongX,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuillotineConfig {
+    pub stages: usize,
+    pub first_cut: CutAxis,
+}
+
+impl Default for GuillotineConfig {
+    fn default() -> Self {
+        Self {
+            stages: 3,
+            first_cut: CutAxis::AlongX,
+        }
+    }
+}
+
+impl GuillotineConfig {
+    pub(crate) fn validate(&self) -> NestResult<()> {
+        if self.stages == 0 {
+            return Err(NestError::InvalidDimension(
+                "guillotine nesting needs at least one stage",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// `position` is where the kerf starts: the y of a cut along X, the x of a cut along Y. Cuts one
+/// stage past the configured count only trim waste off a finished part.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuillotineCut {
+    pub stage: usize,
+    pub axis: CutAxis,
+    pub position: f64,
+    pub start: f64,
+    pub end: f64,
+    pub kerf: f64,
+}
+
+impl GuillotineCut {
+    /// odd stages run with the first cut, even stages cross it
+    pub fn is_rip(&self) -> bool {
+        !self.stage.is_multiple_of(2)
+    }
+}
+
+struct Saw {
+    stages: usize,
+    kerf: f64,
+    // exclusion zones grown by a kerf all round; no part may overlap one
+    blocked: Vec<OffcutRect>,
+    // parts already cut from the sheet; unlike a zone, no cut may run through one either
+    locked: Vec<OffcutRect>,
+}
+
+// whether a saw pass over `strip` cuts into `part`; a pass without kerf is a line
+fn cuts_into(strip: &OffcutRect, part: &OffcutRect) -> bool {
+    strip.x < part.x + part.width - EPS
+        && strip.x + strip.width > part.x + EPS
+        && strip.y < part.y + part.height - EPS
+        && strip.y + strip.height > part.y + EPS
+}
+
+// A piece of the sheet cut at `stage` by lines along `axis` into slices stacked across it.
+// Slices below the last stage are regions themselves; last-stage slices hold one part each, or
+// one group block parted into its members by further last-stage cuts.
+struct Region {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    stage: usize,
+    axis: CutAxis,
+    used: f64,
+    slices: Vec<Slice>,
+}
+
+struct Slice {
+    offset: f64,
+    thickness: f64,
+    content: SliceContent,
+}
+
+enum SliceContent {
+    Region(Region),
+    // the thickness and cross length of each piece the slice is parted into: the part alone, or
+    // the members of a group block in order
+    Part(Vec<(f64, f64)>),
+}
+
+impl Region {
+    // (length the slices stack along, length each slice spans)
+    fn extents(&self) -> (f64, f64) {
+        match self.axis {
+            CutAxis::AlongX => (self.height, self.width),
+            CutAxis::AlongY => (self.width, self.height),
+        }
+    }
+
+    fn thickness(&self, orientation: &Orientation) -> (f64, f64) {
+        match self.axis {
+            CutAxis::AlongX => (orientation.height, orientation.width),
+            CutAxis::AlongY => (orientation.width, orientation.height),
+        }
+    }
+
+    // rectangle `stack..stack + stack_len` along the stacking direction and
+    // `cross..cross + cross_len` across it
+    fn rect(&self, stack: f64, stack_len: f64, cross: f64, cross_len: f64) -> OffcutRect {
+        match self.axis {
+            CutAxis::AlongX => OffcutRect {
+                x: self.x + cross,
+                y: self.y + stack,
+                width: cross_len,
+                height: stack_len,
+            },
+            CutAxis::AlongY => OffcutRect {
+                x: self.x + stack,
+                y: self.y + cross,
+                width: stack_len,
+                height: cross_len,
+            },
+        }
+    }
+
+    // where an exclusion zone overlapping `slot` ends along the stacking direction
+    fn past_zones(&self, saw: &Saw, slot: &OffcutRect) -> Option<f64> {
+        saw.blocked
+            .iter()
+            .filter(|b| intersects(b, slot))
+            .map(|b| match self.axis {
+                CutAxis::AlongX => b.y + b.height - self.y,
+                CutAxis::AlongY => b.x + b.width - self.x,
+            })
+            .reduce(f64::min)
+    }
+
+    // where the next pass would clear the locked parts a pass over `strip` cuts into, along the
+    // stacking direction
+    fn past_locked(&self, saw: &Saw, strip: &OffcutRect) -> Option<f64> {
+        saw.locked
+            .iter()
+            .filter(|l| cuts_into(strip, l))
+            .map(|l| match self.axis {
+                CutAxis::AlongX => l.y + l.height - self.y + saw.kerf,
+                CutAxis::AlongY => l.x + l.width - self.x + saw.kerf,
+            })
+            .reduce(f64::max)
+    }
+
+    // the passes that cut a new slice at `offset` free: before and after it, and for a part the
+    // trim beside it
+    fn passes(&self, saw: &Saw, offset: f64, thick: f64, cross: f64) -> Vec<OffcutRect> {
+        let (stack_len, cross_len) = self.extents();
+        let end = offset + thick;
+        let mut passes = Vec::new();
+        if offset > EPS {
+            let kerf = saw.kerf.min(offset);
+            passes.push(self.rect(offset - kerf, kerf, 0.0, cross_len));
+        }
+        if end + EPS < stack_len {
+            passes.push(self.rect(end, saw.kerf.min(stack_len - end), 0.0, cross_len));
+        }
+        if self.stage == saw.stages && cross + EPS < cross_len {
+            passes.push(self.rect(offset, thick, cross, saw.kerf.min(cross_len - cross)));
+        }
+        passes
+    }
+
+    // Tries the slices already cut first; a new slice is only opened here when `open_slice` is
+    // set, but always inside an existing slice. A new slice that would run into an exclusion
+    // zone, or be cut free through a locked part, is moved on past it.
+    fn insert(
+        &mut self,
+        part: &RectInstance,
+        orientation: Orientation,
+        saw: &Saw,
+        placements: &mut Vec<RectPlacement>,
+        open_slice: bool,
+    ) -> bool {
+        let (thick, cross) = self.thickness(&orientation);
+        let (stack_len, cross_len) = self.extents();
+        if cross > cross_len + EPS || thick > stack_len + EPS {
+            return false;
+        }
+        if self.stage < saw.stages {
+            for slice in &mut self.slices {
+                if let SliceContent::Region(region) = &mut slice.content
+                    && region.insert(part, orientation, saw, placements, true)
+                {
+                    return true;
+                }
+            }
+        }
+        if !open_slice {
+            return false;
+        }
+
+        let mut offset = if self.slices.is_empty() {
+            0.0
+        } else {
+            self.used + saw.kerf
+        };
+        let content = loop {
+            if offset + thick > stack_len + EPS {
+                return false;
+            }
+            if let Some(end) = self
+                .passes(saw, offset, thick, cross)
+                .iter()
+                .filter_map(|pass| self.past_locked(saw, pass))
+                .reduce(f64::max)
+            {
+                offset = end;
+                continue;
+            }
+            let slot = self.rect(offset, thick, 0.0, cross_len);
+            if self.stage < saw.stages {
+                let mut region = Region {
+                    x: slot.x,
+                    y: slot.y,
+                    width: slot.width,
+                    height: slot.height,
+                    stage: self.stage + 1,
+                    axis: self.axis.across(),
+                    used: 0.0,
+                    slices: Vec::new(),
+                };
+                if region.insert(part, orientation, saw, placements, true) {
+                    break SliceContent::Region(region);
+                }
+            } else if self
+                .past_zones(saw, &self.rect(offset, thick, 0.0, cross))
+                .is_none()
+            {
+                let pieces = match &part.members {
+                    Some(members) => members.sizes.clone(),
+                    None => vec![(thick, cross)],
+                };
+                break SliceContent::Part(pieces);
+            }
+            match self.past_zones(saw, &slot) {
+                Some(end) if end > offset + EPS => offset = end,
+                _ => return false,
+            }
+        };
+        if let SliceContent::Part(_) = content {
+            let slot = self.rect(offset, thick, 0.0, cross_len);
+            placements.push(orientation.placement(part, slot.x, slot.y));
+        }
+        self.slices.push(Slice {
+            offset,
+            thickness: thick,
+            content,
+        });
+        self.used = offset + thick;
+        true
+    }
+
+    // Cutting order: each slice is separated from the rest of the region, then broken down
+    // before the next one is cut off.
+    fn emit(&self, saw: &Saw, cuts: &mut Vec<GuillotineCut>, offcuts: &mut Vec<OffcutRect>) {
+        let (stack_len, cross_len) = self.extents();
+        let (stack_start, cross_start) = match self.axis {
+            CutAxis::AlongX => (self.y, self.x),
+            CutAxis::AlongY => (self.x, self.y),
+        };
+        let mut free_from = 0.0;
+        for slice in &self.slices {
+            // a slice moved on past an exclusion zone is cut free of the waste before it
+            if slice.offset > free_from + EPS {
+                let kerf = saw.kerf.min(slice.offset - free_from);
+                cuts.push(GuillotineCut {
+                    stage: self.stage,
+                    axis: self.axis,
+                    position: stack_start + slice.offset - kerf,
+                    start: cross_start,
+                    end: cross_start + cross_len,
+                    kerf,
+                });
+                let waste = slice.offset - kerf - free_from;
+                if waste > EPS {
+                    offcuts.push(self.rect(free_from, waste, 0.0, cross_len));
+                }
+            }
+            let end = slice.offset + slice.thickness;
+            free_from = end + saw.kerf.min(stack_len - end);
+            if end + EPS < stack_len {
+                cuts.push(GuillotineCut {
+                    stage: self.stage,
+                    axis: self.axis,
+                    position: stack_start + end,
+                    start: cross_start,
+                    end: cross_start + cross_len,
+                    kerf: saw.kerf.min(stack_len - end),
+                });
+            }
+            match &slice.content {
+                SliceContent::Region(region) => region.emit(saw, cuts, offcuts),
+                SliceContent::Part(pieces) => {
+                    let mut from = slice.offset;
+                    for (idx, &(thick, part_cross)) in pieces.iter().enumerate() {
+                        // group members are parted at this stage, right across the region
+                        if idx > 0 {
+                            cuts.push(GuillotineCut {
+                                stage: self.stage,
+                                axis: self.axis,
+                                position: stack_start + from - saw.kerf,
+                                start: cross_start,
+                                end: cross_start + cross_len,
+                                kerf: saw.kerf,
+                            });
+                        }
+                        let piece = from;
+                        from += thick + saw.kerf;
+                        if part_cross + EPS >= cross_len {
+                            continue;
+                        }
+                        let kerf = saw.kerf.min(cross_len - part_cross);
+                        cuts.push(GuillotineCut {
+                            stage: self.stage + 1,
+                            axis: self.axis.across(),
+                            position: cross_start + part_cross,
+                            start: stack_start + piece,
+                            end: stack_start + piece + thick,
+                            kerf,
+                        });
+                        let rest = cross_len - part_cross - kerf;
+                        if rest > EPS {
+                            offcuts.push(self.rect(piece, thick, part_cross + kerf, rest));
+                        }
+                    }
+                }
+            }
+        }
+
+        let tail = free_from;
+        if stack_len - tail > EPS {
+            offcuts.push(self.rect(tail, stack_len - tail, 0.0, cross_len));
+        }
+    }
+}
+
+pub(crate) struct GuillotineState {
+    stock: SheetStock,
+    index: usize,
+    config: PlanarNestConfig,
+    saw: Saw,
+    root: Region,
+    placements: Vec<RectPlacement>,
+}
+
+impl SheetPacker for GuillotineState {
+    type Options = GuillotineConfig;
+
+    fn open(
+        stock: &SheetStock,
+        index: usize,
+        config: &PlanarNestConfig,
+        options: GuillotineConfig,
+    ) -> NestResult<Self> {
+        if stock.width <= 2.0 * config.trim || stock.height <= 2.0 * config.trim {
+            return Err(NestError::InvalidDimension(
+                "sheet dimensions smaller than trim allowance",
+            ));
+        }
+        options.validate()?;
+        Ok(Self {
+            stock: stock.clone(),
+            index,
+            config: *config,
+            saw: Saw {
+                stages: options.stages,
+                kerf: config.kerf,
+                blocked: blocked_rects(stock, config.trim, config.kerf),
+                locked: Vec::new(),
+            },
+            root: Region {
+                x: config.trim,
+                y: config.trim,
+                width: stock.width - 2.0 * config.trim,
+                height: stock.height - 2.0 * config.trim,
+                stage: 1,
+                axis: options.first_cut,
+                used: 0.0,
+                slices: Vec::new(),
+            },
+            placements: Vec::new(),
+        })
+    }
+
+    // Existing strips are filled before a new one is cut; a new strip takes the orientation
+    // that keeps it thinnest.
+    fn place(&mut self, part: &RectInstance) -> Option<()> {
+        let mut options = Orientation::options_for(part, &self.stock);
+        if let Some(members) = &part.members {
+            // no stage is left below the last to part members, so they must follow each other
+            // across its cuts
+            let last = if self.saw.stages.is_multiple_of(2) {
+                self.root.axis.across()
+            } else {
+                self.root.axis
+            };
+            options.retain(|o| (members.along_width != o.rotated) == (last == CutAxis::AlongY));
+        }
+        for orientation in &options {
+            if self
+                .root
+                .insert(part, *orientation, &self.saw, &mut self.placements, false)
+            {
+                return Some(());
+            }
+        }
+        options.sort_by(|a, b| {
+            let (ta, _) = self.root.thickness(a);
+            let (tb, _) = self.root.thickness(b);
+            ta.partial_cmp(&tb).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        for orientation in options {
+            if self
+                .root
+                .insert(part, orientation, &self.saw, &mut self.placements, true)
+            {
+                return Some(());
+            }
+        }
+        None
+    }
+
+    fn lock(&mut self, locked: &[OffcutRect]) {
+        self.saw.locked = locked.to_vec();
+    }
+
+    fn finalize(self) -> SheetLayout {
+        let mut cuts = Vec::new();
+        let mut offcuts = Vec::new();
+        self.root.emit(&self.saw, &mut cuts, &mut offcuts);
+        let offcuts = carve_offcuts(offcuts, &blocked_rects(&self.stock, self.config.trim, 0.0));
+
+        let mut layout = SheetLayout {
+            stock_id: self.stock.id.clone(),
+            index: self.index,
+            placements: self.placements,
+            offcuts,
+            metrics: UtilizationBreakdown::new(MetricKind::Area),
+            cost: self.stock.cost,
+            cuts,
+            segments: Vec::new(),
+            source: self.stock.source.clone(),
+        };
+        measure_layout(&mut layout, &self.stock, &self.config);
+        layout
+    }
+}
+
+// thickest first along the first stage so every strip is opened by the part that sizes it
+pub(crate) fn guillotine_order(
+    parts: &[RectPart],
+    guillotine: &GuillotineConfig,
+    seed: u64,
+) -> Vec<RectInstance> {
+    let thickness = |part: &RectInstance| match guillotine.first_cut {
+        CutAxis::AlongX => part.base_height,
+        CutAxis::AlongY => part.base_width,
+    };
+    let mut part_instances: Vec<RectInstance> =
+        parts.iter().flat_map(|part| part.instances()).collect();
+    part_instances.sort_by(|a, b| {
+        cmp_f64_desc(thickness(a), thickness(b))
+            .then(cmp_f64_desc(a.area(), b.area()))
+            .then_with(|| {
+                let ha = hash_with_seed(&a.id, seed ^ a.seq as u64);
+                let hb = hash_with_seed(&b.id, seed ^ b.seq as u64);
+                ha.cmp(&hb)
+            })
+    });
+    part_instances
+}
+
+pub fn guillotine_sheets(
+    parts: &[RectPart],
+    stock: &[SheetStock],
+    config: &PlanarNestConfig,
+    guillotine: &GuillotineConfig,
+) -> NestResult<Vec<SheetLayout>> {
+    validate_inputs(parts, stock)?;
+    guillotine.validate()?;
+    let part_instances = guillotine_order(parts, guillotine, config.seed);
+    complete_layouts(pack_instances::<GuillotineState>(
+        &part_instances,
+        stock,
+        config,
+        *guillotine,
+    )?)
+}
@@ -0,0 +1,167 @@
+use std::cmp::Ordering;
+use std::time::{Duration, Instant};
+
+use crate::error::{NestError, NestResult};
+use crate::pattern::sheet_patterns;
+use crate::planar::{
+    GrainDirection, PlanarNestConfig, PlanarStrategy, RectInstance, RectPart, SheetLayout,
+    SheetStock, summarize_sheet_layouts, validate_inputs,
+};
+use crate::util::SplitMix64;
+
+const START_TEMPERATURE: f64 = 0.3;
+const END_TEMPERATURE: f64 = 0.005;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImproveBudget {
+    pub iterations: usize,
+    /// stops early on the wall clock, so results then depend on machine speed
+    pub time_limit: Option<Duration>,
+}
+
+impl Default for ImproveBudget {
+    fn default() -> Self {
+        Self {
+            iterations: 500,
+            time_limit: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Evaluation {
+    layouts: Vec<SheetLayout>,
+    unplaced: usize,
+    efficiency: f64,
+    // fill of the emptiest sheet; steers the walk towards freeing a whole sheet
+    lowest_fill: f64,
+    // distinct layouts; at equal yield fewer patterns are quicker to cut
+    patterns: usize,
+}
+
+impl Evaluation {
+    fn rank(&self, other: &Evaluation) -> Ordering {
+        self.unplaced
+            .cmp(&other.unplaced)
+            .then(self.layouts.len().cmp(&other.layouts.len()))
+            .then_with(|| {
+                if (self.efficiency - other.efficiency).abs() <= 1e-9 {
+                    Ordering::Equal
+                } else {
+                    other
+                        .efficiency
+                        .partial_cmp(&self.efficiency)
+                        .unwrap_or(Ordering::Equal)
+                }
+            })
+            .then(self.patterns.cmp(&other.patterns))
+    }
+
+    // sheet count dominates; the fractional terms stay below one sheet, and the pattern share
+    // is small enough to nudge the walk without trading yield for it
+    fn energy(&self, parts: usize) -> f64 {
+        let sheets = (self.unplaced * (parts + 1) + self.layouts.len()) as f64;
+        let pattern_share = self.patterns as f64 / self.layouts.len().max(1) as f64;
+        2.0 * sheets + 0.5 * (1.0 - self.efficiency) + 0.5 * self.lowest_fill + 0.05 * pattern_share
+    }
+}
+
+fn evaluate(
+    strategy: &PlanarStrategy,
+    order: &[RectInstance],
+    stock: &[SheetStock],
+    config: &PlanarNestConfig,
+) -> NestResult<Evaluation> {
+    let result = strategy.pack(order, stock, config)?;
+    let efficiency = summarize_sheet_layouts(&result.layouts).efficiency();
+    let lowest_fill = result
+        .layouts
+        .iter()
+        .map(|layout| layout.metrics.efficiency())
+        .fold(1.0, f64::min);
+    Ok(Evaluation {
+        unplaced: result.unplaced.iter().map(|u| u.quantity).sum(),
+        patterns: sheet_patterns(&result.layouts).len(),
+        layouts: result.layouts,
+        efficiency,
+        lowest_fill,
+    })
+}
+
+// swap two parts, move one part, or flip which orientation a free-grain part tries first
+fn perturb(order: &[RectInstance], rng: &mut SplitMix64) -> Vec<RectInstance> {
+    let mut next = order.to_vec();
+    let n = next.len();
+    match rng.below(3) {
+        0 => {
+            let (a, b) = (rng.below(n), rng.below(n));
+            next.swap(a, b);
+        }
+        1 => {
+            let part = next.remove(rng.below(n));
+            next.insert(rng.below(n), part);
+        }
+        _ => {
+            let free: Vec<usize> = (0..n)
+                .filter(|&i| matches!(next[i].grain, GrainDirection::Either))
+                .collect();
+            if free.is_empty() {
+                let (a, b) = (rng.below(n), rng.below(n));
+                next.swap(a, b);
+            } else {
+                let idx = free[rng.below(free.len())];
+                next[idx].rotate_first = !next[idx].rotate_first;
+            }
+        }
+    }
+    next
+}
+
+/// Simulated annealing over part order and orientation preference, starting from the order the
+/// strategy uses on its own, so the result is never worse than calling it directly. The walk is
+/// driven by `config.seed` and repeats exactly for the same seed and iteration budget.
+pub fn improve_sheets(
+    parts: &[RectPart],
+    stock: &[SheetStock],
+    config: &PlanarNestConfig,
+    strategy: PlanarStrategy,
+    budget: &ImproveBudget,
+) -> NestResult<Vec<SheetLayout>> {
+    validate_inputs(parts, stock)?;
+    let mut order = strategy.order(parts, config.seed)?;
+    let mut current = evaluate(&strategy, &order, stock, config)?;
+    let mut best = current.clone();
+
+    let started = Instant::now();
+    let mut rng = SplitMix64::new(config.seed);
+    let n = order.len();
+    for iteration in 0..budget.iterations {
+        if n < 2 && !order.iter().any(|p| p.grain == GrainDirection::Either) {
+            break;
+        }
+        if budget
+            .time_limit
+            .is_some_and(|limit| started.elapsed() >= limit)
+        {
+            break;
+        }
+        let progress = iteration as f64 / budget.iterations as f64;
+        let temperature = START_TEMPERATURE * (END_TEMPERATURE / START_TEMPERATURE).powf(progress);
+
+        let candidate_order = perturb(&order, &mut rng);
+        let candidate = evaluate(&strategy, &candidate_order, stock, config)?;
+        if candidate.rank(&best) == Ordering::Less {
+            best = candidate.clone();
+        }
+        let delta = candidate.energy(n) - current.energy(n);
+        if delta <= 0.0 || rng.next_f64() < (-delta / temperature).exp() {
+            order = candidate_order;
+            current = candidate;
+        }
+    }
+
+    if best.unplaced > 0 {
+        return Err(NestError::InsufficientStock);
+    }
+    Ok(best.layouts)
+}
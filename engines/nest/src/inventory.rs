@@ -0,0 +1,95 @@
+use crate::linear::{LinearNestConfig, LinearNestResult, LinearStock};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OffcutSource {
+    pub stock_id: String,
+    pub board_index: usize,
+    pub job: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OffcutInventory {
+    pub min_length: f64,
+    offcuts: Vec<LinearStock>,
+}
+
+impl OffcutInventory {
+    pub fn new(min_length: f64) -> Self {
+        Self {
+            min_length,
+            offcuts: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.offcuts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offcuts.is_empty()
+    }
+
+    pub fn stock(&self) -> &[LinearStock] {
+        &self.offcuts
+    }
+
+    /// offcuts first so a reader of the list sees the rack before the yard
+    pub fn stock_with(&self, fresh: &[LinearStock]) -> Vec<LinearStock> {
+        self.offcuts.iter().chain(fresh).cloned().collect()
+    }
+
+    /// Drops offcuts the run consumed and racks every new offcut at least `min_length` long
+    /// once the kerf that cuts it free of the last part is taken off. Returns how many offcuts
+    /// were added.
+    pub fn record(
+        &mut self,
+        job: &str,
+        result: &LinearNestResult,
+        config: &LinearNestConfig,
+    ) -> usize {
+        self.offcuts.retain(|offcut| {
+            !result
+                .boards
+                .iter()
+                .any(|board| board.source.is_some() && board.stock_id == offcut.id)
+        });
+
+        let before = self.offcuts.len();
+        for board in &result.boards {
+            // the leading trim is listed as an offcut of a fresh board
+            let leading = if board.source.is_some() {
+                0.0
+            } else {
+                config.trim_leading
+            };
+            let usable = board
+                .offcuts
+                .iter()
+                .filter(|o| o.start >= leading - 1e-9)
+                .map(|o| {
+                    let after_part = board
+                        .cuts
+                        .iter()
+                        .any(|c| (c.start + c.length - o.start).abs() <= 1e-9);
+                    let kerf = if after_part { config.kerf } else { 0.0 };
+                    (o.start + kerf, o.length - kerf)
+                })
+                .filter(|(_, length)| *length + 1e-9 >= self.min_length);
+            for (start, length) in usable {
+                self.offcuts.push(LinearStock {
+                    id: format!("{job}/{}/{}@{:.1}", board.stock_id, board.index, start),
+                    length,
+                    quantity: 1,
+                    cost: 0.0,
+                    defects: Vec::new(),
+                    source: Some(OffcutSource {
+                        stock_id: board.stock_id.clone(),
+                        board_index: board.index,
+                        job: job.to_string(),
+                    }),
+                });
+            }
+        }
+        self.offcuts.len() - before
+    }
+}
@@ -1,16 +1,63 @@
+mod book;
+mod cutline;
+mod edit;
 mod error;
+mod exclusion;
+mod geometry;
+mod group;
+mod guillotine;
+mod improve;
+mod inventory;
 mod linear;
+mod linear_exact;
+mod lumber;
+mod material;
+mod maxrects;
 mod metrics;
+mod objective;
+mod pattern;
 mod planar;
+mod remnant;
+mod renest;
+mod schedule;
+mod shape;
+mod unplaced;
 mod util;
+mod verify;
 
+pub use book::{BookConfig, BookNestResult, BookPattern, OverproducedPart, book_sheets};
+pub use cutline::CutSegment;
+pub use edit::{LayoutEdit, edit_layout};
 pub use error::{NestError, NestResult};
+pub use exclusion::ExclusionZone;
+pub use group::{GroupArrangement, PartGroup, grouped_sheets};
+pub use guillotine::{CutAxis, GuillotineConfig, GuillotineCut, guillotine_sheets};
+pub use improve::{ImproveBudget, improve_sheets};
+pub use inventory::{OffcutInventory, OffcutSource};
 pub use linear::{
-    LinearBoard, LinearNestConfig, LinearNestResult, LinearOffcut, LinearPart, LinearStock,
-    first_fit_boards,
+    LinearBoard, LinearDefect, LinearNestConfig, LinearNestResult, LinearOffcut, LinearPart,
+    LinearStock, OptimalityBound, first_fit_boards, partial_boards,
 };
+pub use linear_exact::{SearchBudget, optimal_boards};
+pub use lumber::{
+    LumberLayout, LumberNestConfig, LumberNestResult, LumberPart, RipStrip, RoughBoard,
+    rip_first_boards,
+};
+pub use material::{MaterialLayouts, MaterialNestResult, material_sheets};
+pub use maxrects::{MaxRectsHeuristic, max_rects_sheets};
 pub use metrics::{MetricKind, UtilizationBreakdown};
+pub use objective::NestObjective;
+pub use pattern::{SheetPattern, sheet_patterns};
 pub use planar::{
-    GrainDirection, PlanarNestConfig, RectPart, SheetLayout, SheetStock, best_fit_sheets,
-    skyline_sheets, summarize_sheet_layouts,
+    FaceGrade, GrainDirection, OffcutRect, PartEdges, PlanarNestConfig, PlanarNestResult,
+    PlanarStrategy, RectPart, RectPlacement, SheetLayout, SheetStock, best_fit_sheets,
+    partial_sheets, skyline_sheets, summarize_sheet_layouts, total_sheet_cost,
+};
+pub use remnant::{SheetInventory, extract_remnants};
+pub use renest::renest_sheets;
+pub use schedule::{BoardEnd, CutAction, CutSchedule, CutStep, StopSetup, cut_schedule};
+pub use shape::{
+    Point, Polygon, ShapeLayout, ShapeNestConfig, ShapePart, ShapePlacement, shape_sheets,
 };
+pub use unplaced::{UnplacedPart, UnplacedReason};
+pub use verify::{LayoutViolation, Violation, verify_boards, verify_shapes, verify_sheets};
@@ -1,5 +1,10 @@
+use std::cmp::Ordering;
+
 use crate::error::{NestError, NestResult};
+use crate::inventory::OffcutSource;
 use crate::metrics::{MetricKind, UtilizationBreakdown};
+use crate::objective::{NestObjective, StockTrial};
+use crate::unplaced::{UnplacedPart, UnplacedReason, record_unplaced};
 use crate::util::{cmp_f64_desc, hash_with_seed};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -20,10 +25,19 @@ impl LinearPart {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+pub struct LinearDefect {
+    pub start: f64,
+    pub length: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct LinearStock {
     pub id: String,
     pub length: f64,
     pub quantity: usize,
+    pub cost: f64,
+    pub defects: Vec<LinearDefect>,
+    pub source: Option<OffcutSource>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -32,6 +46,7 @@ pub struct LinearNestConfig {
     pub trim_leading: f64,
     pub trim_trailing: f64,
     pub seed: u64,
+    pub objective: NestObjective,
 }
 
 impl Default for LinearNestConfig {
@@ -41,6 +56,7 @@ impl Default for LinearNestConfig {
             trim_leading: 0.0,
             trim_trailing: 0.0,
             seed: 0,
+            objective: NestObjective::MinCount,
         }
     }
 }
@@ -65,19 +81,32 @@ pub struct LinearBoard {
     pub cuts: Vec<LinearCut>,
     pub offcuts: Vec<LinearOffcut>,
     pub metrics: UtilizationBreakdown,
+    pub cost: f64,
+    pub source: Option<OffcutSource>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OptimalityBound {
+    pub lower_bound: f64,
+    pub gap: f64,
+    pub proven_optimal: bool,
+    pub nodes: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct LinearNestResult {
     pub boards: Vec<LinearBoard>,
     pub metrics: UtilizationBreakdown,
+    pub cost: f64,
+    pub bound: Option<OptimalityBound>,
+    pub unplaced: Vec<UnplacedPart>,
 }
 
 #[derive(Debug, Clone)]
-struct LinearPartInstance {
-    id: String,
-    length: f64,
-    seq: usize,
+pub(crate) struct LinearPartInstance {
+    pub(crate) id: String,
+    pub(crate) length: f64,
+    pub(crate) seq: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -86,78 +115,123 @@ struct StockSupply {
     used: usize,
 }
 
-struct BoardState {
+// a stretch of board between the trims and any defects that parts may be cut from
+#[derive(Debug, Clone)]
+struct ClearSegment {
+    end: f64,
+    cursor: f64,
+    used: bool,
+}
+
+impl ClearSegment {
+    fn can_place(&self, part_len: f64, kerf: f64) -> bool {
+        let available = self.end - self.cursor;
+        if self.used {
+            part_len + kerf <= available + 1e-9
+        } else {
+            part_len <= available + 1e-9
+        }
+    }
+}
+
+pub(crate) struct BoardState {
     stock: LinearStock,
     index: usize,
-    cursor: f64,
+    segments: Vec<ClearSegment>,
     cuts: Vec<LinearCut>,
-    trim_trailing: f64,
+    defect_loss: f64,
     config: LinearNestConfig,
 }
 
 impl BoardState {
-    fn new(
+    pub(crate) fn new(
         stock: &LinearStock,
         index: usize,
         config: &LinearNestConfig,
     ) -> Result<Self, NestError> {
-        if stock.length <= config.trim_leading + config.trim_trailing {
+        let (leading, trailing) = end_trims(stock, config);
+        if stock.length <= leading + trailing {
             return Err(NestError::InvalidDimension(
                 "stock length smaller than trim allowance",
             ));
         }
+        let bounds = clear_segments(stock, config);
+        let clear: f64 = bounds.iter().map(|(start, end)| end - start).sum();
         Ok(Self {
             stock: stock.clone(),
             index,
-            cursor: config.trim_leading,
+            segments: bounds
+                .into_iter()
+                .map(|(start, end)| ClearSegment {
+                    end,
+                    cursor: start,
+                    used: false,
+                })
+                .collect(),
             cuts: Vec::new(),
-            trim_trailing: config.trim_trailing,
+            defect_loss: stock.length - leading - trailing - clear,
             config: config.clone(),
         })
     }
 
-    fn available(&self) -> f64 {
-        self.stock.length - self.cursor - self.trim_trailing
-    }
-
-    fn can_place(&self, part_len: f64) -> bool {
+    pub(crate) fn can_place(&self, part_len: f64) -> bool {
         if part_len <= 0.0 {
             return false;
         }
-        if self.cuts.is_empty() {
-            part_len <= self.available() + 1e-9
-        } else {
-            part_len + self.config.kerf <= self.available() + 1e-9
-        }
+        self.segments
+            .iter()
+            .any(|segment| segment.can_place(part_len, self.config.kerf))
+    }
+
+    pub(crate) fn place(&mut self, part: &LinearPartInstance) -> Result<(), NestError> {
+        let segment = self
+            .segments
+            .iter()
+            .position(|segment| segment.can_place(part.length, self.config.kerf))
+            .ok_or(NestError::InsufficientStock)?;
+        self.place_in_segment(segment, part)
     }
 
-    fn place(&mut self, part: &LinearPartInstance) -> Result<(), NestError> {
-        if !self.can_place(part.length) {
+    pub(crate) fn place_in_segment(
+        &mut self,
+        segment: usize,
+        part: &LinearPartInstance,
+    ) -> Result<(), NestError> {
+        let kerf = self.config.kerf;
+        let segment = &mut self.segments[segment];
+        if part.length <= 0.0 || !segment.can_place(part.length, kerf) {
             return Err(NestError::InsufficientStock);
         }
-        if !self.cuts.is_empty() {
-            self.cursor += self.config.kerf;
+        if segment.used {
+            segment.cursor += kerf;
         }
-        let start = self.cursor;
         self.cuts.push(LinearCut {
             part_id: part.id.clone(),
-            start,
+            start: segment.cursor,
             length: part.length,
         });
-        self.cursor += part.length;
+        segment.cursor += part.length;
+        segment.used = true;
         Ok(())
     }
 
-    fn finalize(self) -> LinearBoard {
+    pub(crate) fn finalize(mut self) -> LinearBoard {
+        self.cuts
+            .sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(Ordering::Equal));
+
+        let used_segments = self.segments.iter().filter(|s| s.used).count();
         let mut metrics = UtilizationBreakdown::new(MetricKind::Linear);
         metrics.stock_total = self.stock.length;
-        metrics.trim_loss = self.config.trim_leading + self.trim_trailing;
-        metrics.kerf_loss = self.cuts.len().saturating_sub(1) as f64 * self.config.kerf;
+        let (leading, trailing) = end_trims(&self.stock, &self.config);
+        metrics.trim_loss = leading + trailing;
+        metrics.defect_loss = self.defect_loss.max(0.0);
+        metrics.kerf_loss = (self.cuts.len() - used_segments) as f64 * self.config.kerf;
         metrics.utilized = self.cuts.iter().map(|cut| cut.length).sum();
-        let utilized_and_losses = metrics.utilized + metrics.kerf_loss + metrics.trim_loss;
+        let utilized_and_losses =
+            metrics.utilized + metrics.kerf_loss + metrics.trim_loss + metrics.defect_loss;
         metrics.offcut_loss = (metrics.stock_total - utilized_and_losses).max(0.0);
 
-        let offcuts = compute_offcuts(&self, metrics.offcut_loss);
+        let offcuts = compute_offcuts(&self);
 
         LinearBoard {
             stock_id: self.stock.id.clone(),
@@ -165,33 +239,28 @@ impl BoardState {
             cuts: self.cuts,
             offcuts,
             metrics,
+            cost: self.stock.cost,
+            source: self.stock.source.clone(),
         }
     }
 }
 
-fn compute_offcuts(board: &BoardState, tail_offcut: f64) -> Vec<LinearOffcut> {
+fn compute_offcuts(board: &BoardState) -> Vec<LinearOffcut> {
     let mut offcuts = Vec::new();
 
-    if board.config.trim_leading > 0.0 {
+    let (leading, _) = end_trims(&board.stock, &board.config);
+    if leading > 0.0 {
         offcuts.push(LinearOffcut {
             start: 0.0,
-            length: board.config.trim_leading,
+            length: leading,
         });
     }
 
-    if let Some(last_cut) = board.cuts.last() {
-        if tail_offcut > 1e-9 {
-            offcuts.push(LinearOffcut {
-                start: last_cut.start + last_cut.length,
-                length: tail_offcut,
-            });
-        }
-    } else {
-        let remaining =
-            board.stock.length - (board.config.trim_leading + board.config.trim_trailing);
+    for segment in &board.segments {
+        let remaining = segment.end - segment.cursor;
         if remaining > 1e-9 {
             offcuts.push(LinearOffcut {
-                start: board.config.trim_leading,
+                start: segment.cursor,
                 length: remaining,
             });
         }
@@ -200,24 +269,78 @@ fn compute_offcuts(board: &BoardState, tail_offcut: f64) -> Vec<LinearOffcut> {
     offcuts
 }
 
+// The rough ends taken off a board. An offcut from the rack was squared when it was cut free,
+// so it is used to its ends.
+pub(crate) fn end_trims(stock: &LinearStock, config: &LinearNestConfig) -> (f64, f64) {
+    if stock.source.is_some() {
+        (0.0, 0.0)
+    } else {
+        (config.trim_leading, config.trim_trailing)
+    }
+}
+
+// Usable stretches of a board once the trims and each defect (plus a kerf either side of it)
+// are taken out.
+pub(crate) fn clear_segments(stock: &LinearStock, config: &LinearNestConfig) -> Vec<(f64, f64)> {
+    let (leading, trailing) = end_trims(stock, config);
+    let usable_start = leading;
+    let usable_end = stock.length - trailing;
+
+    let mut blocked: Vec<(f64, f64)> = stock
+        .defects
+        .iter()
+        .map(|d| {
+            (
+                (d.start - config.kerf).max(usable_start),
+                (d.start + d.length + config.kerf).min(usable_end),
+            )
+        })
+        .filter(|(start, end)| end > start)
+        .collect();
+    blocked.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+    let mut segments = Vec::new();
+    let mut cursor = usable_start;
+    for (start, end) in blocked {
+        if start > cursor + 1e-9 {
+            segments.push((cursor, start));
+        }
+        cursor = cursor.max(end);
+    }
+    if usable_end > cursor + 1e-9 {
+        segments.push((cursor, usable_end));
+    }
+    segments
+}
+
 pub fn first_fit_boards(
     parts: &[LinearPart],
     stock: &[LinearStock],
     config: &LinearNestConfig,
 ) -> NestResult<LinearNestResult> {
-    validate_inputs(parts, stock)?;
+    first_fit(parts, stock, config, false)
+}
 
-    let mut part_instances: Vec<LinearPartInstance> =
-        parts.iter().flat_map(|part| part.instances()).collect();
+/// Cuts what the stock allows first-fit and lists every part left over with the reason it
+/// could not be cut, as `partial_sheets` does for sheets.
+pub fn partial_boards(
+    parts: &[LinearPart],
+    stock: &[LinearStock],
+    config: &LinearNestConfig,
+) -> NestResult<LinearNestResult> {
+    first_fit(parts, stock, config, true)
+}
+
+fn first_fit(
+    parts: &[LinearPart],
+    stock: &[LinearStock],
+    config: &LinearNestConfig,
+    partial: bool,
+) -> NestResult<LinearNestResult> {
+    validate_inputs(parts, stock)?;
 
     // first-fit decreasing with deterministic tie-breaking
-    part_instances.sort_by(|a, b| {
-        cmp_f64_desc(a.length, b.length).then_with(|| {
-            let ha = hash_with_seed(&a.id, config.seed ^ a.seq as u64);
-            let hb = hash_with_seed(&b.id, config.seed ^ b.seq as u64);
-            ha.cmp(&hb)
-        })
-    });
+    let part_instances = sorted_instances(parts, config.seed);
 
     let mut supplies: Vec<StockSupply> = stock
         .iter()
@@ -228,8 +351,9 @@ pub fn first_fit_boards(
         .collect();
 
     let mut active_boards: Vec<BoardState> = Vec::new();
+    let mut unplaced = Vec::new();
 
-    for part in &part_instances {
+    for (pos, part) in part_instances.iter().enumerate() {
         let mut placed = false;
         for board in &mut active_boards {
             if board.can_place(part.length) {
@@ -240,10 +364,19 @@ pub fn first_fit_boards(
         }
 
         if !placed {
-            let supply = supplies
-                .iter_mut()
-                .find(|s| s.used < s.stock.quantity)
-                .ok_or(NestError::InsufficientStock)?;
+            let Some(choice) = choose_supply(&supplies, &part_instances[pos..], config)? else {
+                if !partial {
+                    return Err(NestError::InsufficientStock);
+                }
+                let reason = if fits_any_stock(stock, part, config) {
+                    UnplacedReason::StockExhausted
+                } else {
+                    UnplacedReason::LargerThanAnyStock
+                };
+                record_unplaced(&mut unplaced, &part.id, reason);
+                continue;
+            };
+            let supply = &mut supplies[choice];
 
             let mut board_state = BoardState::new(&supply.stock, supply.used, config)?;
             supply.used += 1;
@@ -252,31 +385,180 @@ pub fn first_fit_boards(
         }
     }
 
-    let mut boards: Vec<LinearBoard> = active_boards.into_iter().map(|b| b.finalize()).collect();
+    let mut result = assemble_result(
+        active_boards.into_iter().map(|b| b.finalize()).collect(),
+        None,
+    );
+    result.unplaced = unplaced;
+    Ok(result)
+}
+
+// whether a fresh board of any listed stock, ignoring quantities, has room for the part
+fn fits_any_stock(
+    stock: &[LinearStock],
+    part: &LinearPartInstance,
+    config: &LinearNestConfig,
+) -> bool {
+    stock
+        .iter()
+        .any(|s| BoardState::new(s, 0, config).is_ok_and(|board| board.can_place(part.length)))
+}
+
+// Opens a trial board of every available stock, fills it first-fit with the parts still to
+// place and keeps the stock the objective scores best. Racked offcuts that can take the part
+// always win over fresh boards. As for sheets, the choice is greedy, board by board, and under
+// `MinCost` looks one step ahead by pricing what each trial leaves on every stock type alone.
+fn choose_supply(
+    supplies: &[StockSupply],
+    remaining: &[LinearPartInstance],
+    config: &LinearNestConfig,
+) -> NestResult<Option<usize>> {
+    let mut best: Option<(usize, StockTrial)> = None;
+    let mut open_error = None;
+    for (idx, supply) in supplies.iter().enumerate() {
+        if supply.used >= supply.stock.quantity {
+            continue;
+        }
+        let mut trial = match BoardState::new(&supply.stock, supply.used, config) {
+            Ok(trial) => trial,
+            Err(err) => {
+                open_error.get_or_insert(err);
+                continue;
+            }
+        };
+        if !trial.can_place(remaining[0].length) {
+            continue;
+        }
+        let mut packed = 0.0;
+        let mut left = Vec::new();
+        for part in remaining {
+            if trial.can_place(part.length) {
+                trial.place(part)?;
+                packed += part.length;
+            } else {
+                left.push(part.clone());
+            }
+        }
+        let run_cost = if config.objective == NestObjective::MinCost {
+            let finish = (0..supplies.len())
+                .filter_map(|other| {
+                    let used = supplies[other].used + usize::from(other == idx);
+                    finish_cost(&supplies[other].stock, used, &left, config)
+                })
+                .fold(f64::INFINITY, f64::min);
+            supply.stock.cost + finish
+        } else {
+            0.0
+        };
+        let score = StockTrial {
+            packed,
+            total: supply.stock.length,
+            cost: supply.stock.cost,
+            run_cost,
+        };
+        let reclaimed = supply.stock.source.is_some();
+        let better = best.as_ref().is_none_or(|(current_idx, current)| {
+            let current_reclaimed = supplies[*current_idx].stock.source.is_some();
+            reclaimed && !current_reclaimed
+                || reclaimed == current_reclaimed
+                    && config.objective.compare(&score, current) == Ordering::Less
+        });
+        if better {
+            best = Some((idx, score));
+        }
+    }
+    match (best, open_error) {
+        (None, Some(err)) => Err(err),
+        (best, _) => Ok(best.map(|(idx, _)| idx)),
+    }
+}
+
+// What cutting `parts` first-fit from fresh boards of `stock` alone costs, or `None` when the
+// boards left of it cannot take them all.
+fn finish_cost(
+    stock: &LinearStock,
+    mut used: usize,
+    parts: &[LinearPartInstance],
+    config: &LinearNestConfig,
+) -> Option<f64> {
+    let mut boards: Vec<BoardState> = Vec::new();
+    for part in parts {
+        let board = match boards.iter_mut().find(|b| b.can_place(part.length)) {
+            Some(board) => board,
+            None => {
+                if used >= stock.quantity {
+                    return None;
+                }
+                let board = BoardState::new(stock, used, config).ok()?;
+                used += 1;
+                if !board.can_place(part.length) {
+                    return None;
+                }
+                boards.push(board);
+                boards.last_mut().expect("just pushed")
+            }
+        };
+        board.place(part).ok()?;
+    }
+    Some(stock.cost * boards.len() as f64)
+}
+
+pub(crate) fn sorted_instances(parts: &[LinearPart], seed: u64) -> Vec<LinearPartInstance> {
+    let mut part_instances: Vec<LinearPartInstance> =
+        parts.iter().flat_map(|part| part.instances()).collect();
+    part_instances.sort_by(|a, b| {
+        cmp_f64_desc(a.length, b.length).then_with(|| {
+            let ha = hash_with_seed(&a.id, seed ^ a.seq as u64);
+            let hb = hash_with_seed(&b.id, seed ^ b.seq as u64);
+            ha.cmp(&hb)
+        })
+    });
+    part_instances
+}
+
+pub(crate) fn assemble_result(
+    mut boards: Vec<LinearBoard>,
+    bound: Option<OptimalityBound>,
+) -> LinearNestResult {
     boards.sort_by(|a, b| a.stock_id.cmp(&b.stock_id).then(a.index.cmp(&b.index)));
 
     let mut agg = UtilizationBreakdown::new(MetricKind::Linear);
+    let mut cost = 0.0;
     for board in &boards {
+        cost += board.cost;
         agg.stock_total += board.metrics.stock_total;
         agg.trim_loss += board.metrics.trim_loss;
         agg.kerf_loss += board.metrics.kerf_loss;
         agg.offcut_loss += board.metrics.offcut_loss;
+        agg.defect_loss += board.metrics.defect_loss;
         agg.utilized += board.metrics.utilized;
     }
 
-    Ok(LinearNestResult {
+    LinearNestResult {
         boards,
         metrics: agg,
-    })
+        cost,
+        bound,
+        unplaced: Vec::new(),
+    }
 }
 
-fn validate_inputs(parts: &[LinearPart], stock: &[LinearStock]) -> NestResult<()> {
+pub(crate) fn validate_inputs(parts: &[LinearPart], stock: &[LinearStock]) -> NestResult<()> {
     if parts.iter().any(|p| p.length <= 0.0) {
         return Err(NestError::InvalidDimension("part length must be positive"));
     }
     if stock.iter().any(|s| s.length <= 0.0) {
         return Err(NestError::InvalidDimension("stock length must be positive"));
     }
+    if stock.iter().any(|s| {
+        s.defects
+            .iter()
+            .any(|d| d.length <= 0.0 || d.start < 0.0 || d.start + d.length > s.length + 1e-9)
+    }) {
+        return Err(NestError::InvalidDimension(
+            "defects must have positive length and lie on the board",
+        ));
+    }
     Ok(())
 }
 
@@ -302,12 +584,12 @@ mod tests {
             id: "board".into(),
             length: 5.3,
             quantity: 1,
+            ..LinearStock::default()
         }];
         let config = LinearNestConfig {
             kerf: 0.1,
-            trim_leading: 0.0,
-            trim_trailing: 0.0,
             seed: 42,
+            ..LinearNestConfig::default()
         };
 
         let result = first_fit_boards(&parts, &stock, &config).unwrap();
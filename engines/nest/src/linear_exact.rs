@@ -0,0 +1,418 @@
+use std::time::{Duration, Instant};
+
+use crate::error::{NestError, NestResult};
+use crate::linear::{
+    BoardState, LinearNestConfig, LinearNestResult, LinearPart, LinearStock, OptimalityBound,
+    assemble_result, clear_segments, end_trims, first_fit_boards, sorted_instances,
+    validate_inputs,
+};
+use crate::objective::NestObjective;
+use crate::util::cmp_f64_desc;
+
+const EPS: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchBudget {
+    pub max_nodes: usize,
+    pub time_limit: Option<Duration>,
+}
+
+impl Default for SearchBudget {
+    fn default() -> Self {
+        Self {
+            max_nodes: 200_000,
+            time_limit: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct OpenBin {
+    stock: usize,
+    residuals: Vec<f64>,
+    items: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Totals {
+    boards: f64,
+    length: f64,
+    cost: f64,
+}
+
+impl Totals {
+    // primary and tie-breaking objective values, both minimised
+    fn key(&self, objective: NestObjective) -> (f64, f64) {
+        match objective {
+            NestObjective::MinCount => (self.boards, self.length),
+            NestObjective::MinCost => (self.cost, self.boards),
+            NestObjective::MinWaste => (self.length, self.boards),
+        }
+    }
+
+    fn add(&mut self, stock: &LinearStock) {
+        self.boards += board_weight(stock);
+        self.length += stock.length;
+        self.cost += stock.cost;
+    }
+
+    fn remove(&mut self, stock: &LinearStock) {
+        self.boards -= board_weight(stock);
+        self.length -= stock.length;
+        self.cost -= stock.cost;
+    }
+}
+
+// racked offcuts are free to use, so only fresh boards count towards the board total
+fn board_weight(stock: &LinearStock) -> f64 {
+    if stock.source.is_some() { 0.0 } else { 1.0 }
+}
+
+fn improves_on(candidate: (f64, f64), best: (f64, f64)) -> bool {
+    candidate.0 < best.0 - EPS || (candidate.0 <= best.0 + EPS && candidate.1 < best.1 - EPS)
+}
+
+struct Search<'a> {
+    sizes: Vec<f64>,
+    same_as_prev: Vec<bool>,
+    suffix: Vec<f64>,
+    stock: &'a [LinearStock],
+    segments: Vec<Vec<f64>>,
+    capacities: Vec<f64>,
+    objective: NestObjective,
+    remaining: Vec<usize>,
+    bins: Vec<OpenBin>,
+    totals: Totals,
+    best: Option<((f64, f64), Vec<OpenBin>)>,
+    nodes: usize,
+    budget: SearchBudget,
+    started: Instant,
+    exhausted: bool,
+}
+
+impl Search<'_> {
+    fn out_of_budget(&mut self) -> bool {
+        if self.nodes >= self.budget.max_nodes {
+            self.exhausted = true;
+        } else if let Some(limit) = self.budget.time_limit {
+            // checking the clock on every node dominates small searches
+            if self.nodes.is_multiple_of(1024) && self.started.elapsed() >= limit {
+                self.exhausted = true;
+            }
+        }
+        self.exhausted
+    }
+
+    // cheapest fractional set of unopened boards whose capacity covers the deficit
+    fn cover(&self, deficit: f64, weight: impl Fn(&LinearStock) -> f64) -> Option<f64> {
+        if deficit <= EPS {
+            return Some(0.0);
+        }
+        let mut order: Vec<usize> = (0..self.stock.len())
+            .filter(|&s| self.remaining[s] > 0 && self.capacities[s] > EPS)
+            .collect();
+        order.sort_by(|&a, &b| {
+            let ra = weight(&self.stock[a]) / self.capacities[a];
+            let rb = weight(&self.stock[b]) / self.capacities[b];
+            ra.partial_cmp(&rb).unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let mut left = deficit;
+        let mut total = 0.0;
+        for s in order {
+            let take = (left / self.capacities[s]).min(self.remaining[s] as f64);
+            total += take * weight(&self.stock[s]);
+            left -= take * self.capacities[s];
+            if left <= EPS {
+                return Some(total);
+            }
+        }
+        None
+    }
+
+    fn extra_totals(&self, deficit: f64) -> Option<Totals> {
+        let boards = self.cover(deficit, board_weight)?;
+        Some(Totals {
+            boards: (boards - EPS).ceil().max(0.0),
+            length: self.cover(deficit, |s| s.length)?,
+            cost: self.cover(deficit, |s| s.cost)?,
+        })
+    }
+
+    fn pruned(&self, item: usize) -> bool {
+        let residual: f64 = self.bins.iter().flat_map(|b| &b.residuals).sum();
+        let Some(extra) = self.extra_totals(self.suffix[item] - residual) else {
+            return true;
+        };
+        let Some((best, _)) = &self.best else {
+            return false;
+        };
+        let bound = Totals {
+            boards: self.totals.boards + extra.boards,
+            length: self.totals.length + extra.length,
+            cost: self.totals.cost + extra.cost,
+        }
+        .key(self.objective);
+        !improves_on(bound, *best)
+    }
+
+    fn dfs(&mut self, item: usize, prev_bin: usize) {
+        if self.out_of_budget() {
+            return;
+        }
+        self.nodes += 1;
+
+        if item == self.sizes.len() {
+            let key = self.totals.key(self.objective);
+            if self.best.as_ref().is_none_or(|(b, _)| improves_on(key, *b)) {
+                self.best = Some((key, self.bins.clone()));
+            }
+            return;
+        }
+        if self.pruned(item) {
+            return;
+        }
+
+        let size = self.sizes[item];
+        // identical parts never go to an earlier board than their predecessor
+        let min_bin = if self.same_as_prev[item] { prev_bin } else { 0 };
+
+        // best fit into open boards, skipping segments that look identical to one already tried
+        let mut candidates: Vec<(usize, usize)> = (min_bin..self.bins.len())
+            .flat_map(|b| (0..self.bins[b].residuals.len()).map(move |seg| (b, seg)))
+            .filter(|&(b, seg)| self.bins[b].residuals[seg] + EPS >= size)
+            .collect();
+        candidates.sort_by(|&(a, sa), &(b, sb)| {
+            self.bins[a].residuals[sa]
+                .partial_cmp(&self.bins[b].residuals[sb])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let mut tried: Vec<(usize, Vec<f64>, f64)> = Vec::new();
+        for (bin, seg) in candidates {
+            let open = &self.bins[bin];
+            let residual = open.residuals[seg];
+            let seen = tried.iter().any(|(stock, residuals, r)| {
+                *stock == open.stock
+                    && (r - residual).abs() < EPS
+                    && residuals
+                        .iter()
+                        .zip(&open.residuals)
+                        .all(|(a, b)| (a - b).abs() < EPS)
+            });
+            if seen {
+                continue;
+            }
+            tried.push((open.stock, open.residuals.clone(), residual));
+
+            self.bins[bin].residuals[seg] -= size;
+            self.bins[bin].items.push((seg, item));
+            self.dfs(item + 1, bin);
+            self.bins[bin].items.pop();
+            self.bins[bin].residuals[seg] += size;
+            if self.exhausted {
+                return;
+            }
+        }
+
+        let mut openings: Vec<(usize, usize)> = (0..self.stock.len())
+            .filter(|&s| self.remaining[s] > 0)
+            .flat_map(|s| (0..self.segments[s].len()).map(move |seg| (s, seg)))
+            .filter(|&(s, seg)| self.segments[s][seg] + EPS >= size)
+            .collect();
+        let rate = |s: usize| {
+            let weight = match self.objective {
+                NestObjective::MinCount => board_weight(&self.stock[s]),
+                NestObjective::MinCost => self.stock[s].cost,
+                NestObjective::MinWaste => self.stock[s].length,
+            };
+            weight / self.capacities[s]
+        };
+        openings.sort_by(|&(a, sa), &(b, sb)| {
+            rate(a)
+                .partial_cmp(&rate(b))
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(cmp_f64_desc(self.capacities[a], self.capacities[b]))
+                .then(a.cmp(&b))
+                .then(cmp_f64_desc(self.segments[b][sb], self.segments[a][sa]))
+        });
+        openings.dedup_by(|(b, sb), (a, sa)| {
+            self.interchangeable(*a, *b)
+                && (self.segments[*a][*sa] - self.segments[*b][*sb]).abs() < EPS
+        });
+        for (stock, seg) in openings {
+            self.remaining[stock] -= 1;
+            self.totals.add(&self.stock[stock]);
+            let mut residuals = self.segments[stock].clone();
+            residuals[seg] -= size;
+            self.bins.push(OpenBin {
+                stock,
+                residuals,
+                items: vec![(seg, item)],
+            });
+            let bin = self.bins.len() - 1;
+            self.dfs(item + 1, bin);
+            self.bins.pop();
+            self.totals.remove(&self.stock[stock]);
+            self.remaining[stock] += 1;
+            if self.exhausted {
+                return;
+            }
+        }
+    }
+
+    fn interchangeable(&self, a: usize, b: usize) -> bool {
+        let (sa, sb) = (&self.stock[a], &self.stock[b]);
+        (sa.length - sb.length).abs() < EPS
+            && (sa.cost - sb.cost).abs() < EPS
+            && sa.source.is_some() == sb.source.is_some()
+            && self.segments[a].len() == self.segments[b].len()
+            && self.segments[a]
+                .iter()
+                .zip(&self.segments[b])
+                .all(|(x, y)| (x - y).abs() < EPS)
+    }
+}
+
+/// Searches complete assignments only: without one it fails with `InsufficientStock`, and
+/// `partial_boards` lists what cannot be cut. If the budget runs out before the first complete
+/// assignment the first-fit nest comes back, its bound marked as not proven.
+pub fn optimal_boards(
+    parts: &[LinearPart],
+    stock: &[LinearStock],
+    config: &LinearNestConfig,
+    budget: &SearchBudget,
+) -> NestResult<LinearNestResult> {
+    validate_inputs(parts, stock)?;
+    if stock.iter().any(|s| {
+        let (leading, trailing) = end_trims(s, config);
+        s.length <= leading + trailing
+    }) {
+        return Err(NestError::InvalidDimension(
+            "stock length smaller than trim allowance",
+        ));
+    }
+    let instances = sorted_instances(parts, config.seed);
+
+    // n cuts need n - 1 kerfs, so charge every part one kerf and give every segment one back
+    let sizes: Vec<f64> = instances.iter().map(|p| p.length + config.kerf).collect();
+    let segments: Vec<Vec<f64>> = stock
+        .iter()
+        .map(|s| {
+            clear_segments(s, config)
+                .into_iter()
+                .map(|(start, end)| end - start + config.kerf)
+                .collect()
+        })
+        .collect();
+    let capacities: Vec<f64> = segments.iter().map(|segs| segs.iter().sum()).collect();
+    let same_as_prev = (0..instances.len())
+        .map(|i| {
+            i > 0
+                && instances[i].id == instances[i - 1].id
+                && instances[i].length == instances[i - 1].length
+        })
+        .collect();
+    let mut suffix = vec![0.0; sizes.len() + 1];
+    for i in (0..sizes.len()).rev() {
+        suffix[i] = suffix[i + 1] + sizes[i];
+    }
+
+    let mut search = Search {
+        sizes,
+        same_as_prev,
+        suffix,
+        stock,
+        segments,
+        capacities,
+        objective: config.objective,
+        remaining: stock.iter().map(|s| s.quantity).collect(),
+        bins: Vec::new(),
+        totals: Totals::default(),
+        best: None,
+        nodes: 0,
+        budget: *budget,
+        started: Instant::now(),
+        exhausted: false,
+    };
+
+    let root_bound = root_lower_bound(&search).ok_or(NestError::InsufficientStock)?;
+    search.dfs(0, 0);
+
+    let boards = match search.best.take() {
+        Some((_, bins)) => {
+            let mut used = vec![0usize; stock.len()];
+            let mut boards = Vec::with_capacity(bins.len());
+            for bin in bins {
+                let mut state = BoardState::new(&stock[bin.stock], used[bin.stock], config)?;
+                used[bin.stock] += 1;
+                for (seg, item) in bin.items {
+                    state.place_in_segment(seg, &instances[item])?;
+                }
+                boards.push(state.finalize());
+            }
+            boards
+        }
+        // the budget ran out before the first complete assignment
+        None if search.exhausted => first_fit_boards(parts, stock, config)?.boards,
+        None => return Err(NestError::InsufficientStock),
+    };
+
+    let achieved = boards.iter().fold(Totals::default(), |mut totals, board| {
+        if board.source.is_none() {
+            totals.boards += 1.0;
+        }
+        totals.length += board.metrics.stock_total;
+        totals.cost += board.cost;
+        totals
+    });
+    let value = achieved.key(config.objective).0;
+    let lower_bound = if search.exhausted {
+        root_bound.min(value)
+    } else {
+        value
+    };
+    let gap = if value <= EPS {
+        0.0
+    } else {
+        (value - lower_bound) / value
+    };
+
+    Ok(assemble_result(
+        boards,
+        Some(OptimalityBound {
+            lower_bound,
+            gap,
+            proven_optimal: !search.exhausted,
+            nodes: search.nodes,
+        }),
+    ))
+}
+
+fn root_lower_bound(search: &Search<'_>) -> Option<f64> {
+    let bound = search
+        .extra_totals(search.suffix[0])?
+        .key(search.objective)
+        .0;
+    if search.objective != NestObjective::MinCount {
+        return Some(bound);
+    }
+    let available = || {
+        (0..search.stock.len())
+            .filter(|&s| search.remaining[s] > 0)
+            .map(|s| &search.segments[s])
+    };
+    let widest = available().flatten().copied().fold(0.0, f64::max);
+    let most_segments = available().map(Vec::len).max().unwrap_or(1).max(1);
+    let reclaimed_slots: usize = (0..search.stock.len())
+        .filter(|&s| search.stock[s].source.is_some())
+        .map(|s| search.remaining[s] * search.segments[s].len())
+        .sum();
+    // no two parts longer than half the longest clear segment can share one
+    let long_parts = search
+        .sizes
+        .iter()
+        .filter(|size| **size > widest * 0.5 + EPS)
+        .count()
+        .saturating_sub(reclaimed_slots);
+    Some(bound.max(long_parts.div_ceil(most_segments) as f64))
+}
@@ -0,0 +1,290 @@
+use crate::error::{NestError, NestResult};
+use crate::linear::{BoardState, LinearBoard, LinearNestConfig, LinearPartInstance, LinearStock};
+use crate::metrics::{MetricKind, UtilizationBreakdown};
+use crate::util::{cmp_f64_desc, hash_with_seed};
+
+const EPS: f64 = 1e-9;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoughBoard {
+    pub id: String,
+    pub width: f64,
+    pub length: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LumberPart {
+    pub id: String,
+    pub width: f64,
+    pub length: f64,
+    pub quantity: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LumberNestConfig {
+    pub rip_kerf: f64,
+    pub crosscut_kerf: f64,
+    pub edge_trim: f64,
+    pub trim_leading: f64,
+    pub trim_trailing: f64,
+    pub seed: u64,
+}
+
+impl Default for LumberNestConfig {
+    fn default() -> Self {
+        Self {
+            rip_kerf: 0.0,
+            crosscut_kerf: 0.0,
+            edge_trim: 0.0,
+            trim_leading: 0.0,
+            trim_trailing: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RipStrip {
+    pub offset: f64,
+    pub width: f64,
+    pub board: LinearBoard,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LumberLayout {
+    pub board_id: String,
+    pub strips: Vec<RipStrip>,
+    pub metrics: UtilizationBreakdown,
+}
+
+#[derive(Debug, Clone)]
+pub struct LumberNestResult {
+    pub layouts: Vec<LumberLayout>,
+    pub metrics: UtilizationBreakdown,
+}
+
+#[derive(Debug, Clone)]
+struct LumberInstance {
+    width: f64,
+    part: LinearPartInstance,
+}
+
+/// Rips each board into strips, widest parts first, picking for every strip the width whose
+/// crosscut fill gives the best yield. Parts keep their length along the board so the grain
+/// always runs with the stock.
+pub fn rip_first_boards(
+    parts: &[LumberPart],
+    boards: &[RoughBoard],
+    config: &LumberNestConfig,
+) -> NestResult<LumberNestResult> {
+    validate_inputs(parts, boards, config)?;
+
+    let mut pending: Vec<LumberInstance> = parts
+        .iter()
+        .flat_map(|part| {
+            (0..part.quantity).map(move |seq| LumberInstance {
+                width: part.width,
+                part: LinearPartInstance {
+                    id: part.id.clone(),
+                    length: part.length,
+                    seq,
+                },
+            })
+        })
+        .collect();
+    pending.sort_by(|a, b| {
+        cmp_f64_desc(a.width, b.width)
+            .then(cmp_f64_desc(a.part.length, b.part.length))
+            .then_with(|| {
+                let ha = hash_with_seed(&a.part.id, config.seed ^ a.part.seq as u64);
+                let hb = hash_with_seed(&b.part.id, config.seed ^ b.part.seq as u64);
+                ha.cmp(&hb)
+            })
+    });
+
+    let crosscut = LinearNestConfig {
+        kerf: config.crosscut_kerf,
+        trim_leading: config.trim_leading,
+        trim_trailing: config.trim_trailing,
+        seed: config.seed,
+        ..LinearNestConfig::default()
+    };
+
+    let mut layouts = Vec::new();
+    for board in boards {
+        if pending.is_empty() {
+            break;
+        }
+        let stock = LinearStock {
+            id: board.id.clone(),
+            length: board.length,
+            quantity: 1,
+            ..LinearStock::default()
+        };
+        let edge = board.width - config.edge_trim;
+        let mut offset = config.edge_trim;
+        let mut strips = Vec::new();
+        let mut utilized = 0.0;
+        // kerf of the second rips that take narrower parts down to width
+        let mut second_rips = 0.0;
+
+        loop {
+            let mut widths: Vec<f64> = pending
+                .iter()
+                .map(|p| p.width)
+                .filter(|w| offset + w <= edge + EPS)
+                .collect();
+            widths.dedup_by(|a, b| (*a - *b).abs() < EPS);
+
+            let mut best: Option<(f64, f64, Vec<usize>)> = None;
+            for width in widths {
+                let picked = fill_strip(
+                    &pending,
+                    width,
+                    &stock,
+                    &crosscut,
+                    config.rip_kerf,
+                    strips.len(),
+                )?;
+                if picked.is_empty() {
+                    continue;
+                }
+                let area: f64 = picked
+                    .iter()
+                    .map(|&i| pending[i].width * pending[i].part.length)
+                    .sum();
+                let strip_yield = area / (width * board.length);
+                if best.as_ref().is_none_or(|(y, _, _)| strip_yield > y + EPS) {
+                    best = Some((strip_yield, width, picked));
+                }
+            }
+            let Some((_, width, picked)) = best else {
+                break;
+            };
+
+            let mut state = BoardState::new(&stock, strips.len(), &crosscut)?;
+            for &i in &picked {
+                state.place(&pending[i].part)?;
+                utilized += pending[i].width * pending[i].part.length;
+                if pending[i].width < width - EPS {
+                    second_rips += config.rip_kerf * pending[i].part.length;
+                }
+            }
+            strips.push(RipStrip {
+                offset,
+                width,
+                board: state.finalize(),
+            });
+            for i in picked.into_iter().rev() {
+                pending.remove(i);
+            }
+            offset += width + config.rip_kerf;
+        }
+
+        if !strips.is_empty() {
+            layouts.push(finalize_layout(
+                board,
+                strips,
+                utilized,
+                second_rips,
+                config,
+            ));
+        }
+    }
+
+    if !pending.is_empty() {
+        return Err(NestError::InsufficientStock);
+    }
+
+    let mut agg = UtilizationBreakdown::new(MetricKind::Area);
+    for layout in &layouts {
+        agg.stock_total += layout.metrics.stock_total;
+        agg.utilized += layout.metrics.utilized;
+        agg.kerf_loss += layout.metrics.kerf_loss;
+        agg.trim_loss += layout.metrics.trim_loss;
+        agg.offcut_loss += layout.metrics.offcut_loss;
+    }
+    Ok(LumberNestResult {
+        layouts,
+        metrics: agg,
+    })
+}
+
+// A part fits a strip of its own width, or a wider one that leaves room for the rip that
+// takes it down to width; the sliver beyond that rip is offcut.
+fn fits_strip(part_width: f64, width: f64, rip_kerf: f64) -> bool {
+    (part_width - width).abs() <= EPS || part_width + rip_kerf <= width + EPS
+}
+
+// First-fit crosscut fill of one strip; `pending` is widest first, so parts of exactly this
+// width are tried before narrower ones that need a second rip.
+fn fill_strip(
+    pending: &[LumberInstance],
+    width: f64,
+    stock: &LinearStock,
+    crosscut: &LinearNestConfig,
+    rip_kerf: f64,
+    index: usize,
+) -> NestResult<Vec<usize>> {
+    let mut trial = BoardState::new(stock, index, crosscut)?;
+    let mut picked = Vec::new();
+    for (i, instance) in pending.iter().enumerate() {
+        if fits_strip(instance.width, width, rip_kerf) && trial.can_place(instance.part.length) {
+            trial.place(&instance.part)?;
+            picked.push(i);
+        }
+    }
+    Ok(picked)
+}
+
+fn finalize_layout(
+    board: &RoughBoard,
+    strips: Vec<RipStrip>,
+    utilized: f64,
+    second_rips: f64,
+    config: &LumberNestConfig,
+) -> LumberLayout {
+    let edge = board.width - config.edge_trim;
+    let mut metrics = UtilizationBreakdown::new(MetricKind::Area);
+    metrics.stock_total = board.width * board.length;
+    metrics.utilized = utilized;
+    metrics.trim_loss = 2.0 * config.edge_trim * board.length;
+    metrics.kerf_loss = second_rips;
+    for strip in &strips {
+        metrics.trim_loss += strip.board.metrics.trim_loss * strip.width;
+        metrics.kerf_loss += strip.board.metrics.kerf_loss * strip.width;
+        let rip = config
+            .rip_kerf
+            .min(edge - strip.offset - strip.width)
+            .max(0.0);
+        metrics.kerf_loss += rip * board.length;
+    }
+    let losses = metrics.utilized + metrics.trim_loss + metrics.kerf_loss;
+    metrics.offcut_loss = (metrics.stock_total - losses).max(0.0);
+
+    LumberLayout {
+        board_id: board.id.clone(),
+        strips,
+        metrics,
+    }
+}
+
+fn validate_inputs(
+    parts: &[LumberPart],
+    boards: &[RoughBoard],
+    config: &LumberNestConfig,
+) -> NestResult<()> {
+    if parts.iter().any(|p| p.width <= 0.0 || p.length <= 0.0) {
+        return Err(NestError::InvalidDimension(
+            "part dimensions must be positive",
+        ));
+    }
+    if boards.iter().any(|b| {
+        b.width <= 2.0 * config.edge_trim || b.length <= config.trim_leading + config.trim_trailing
+    }) {
+        return Err(NestError::InvalidDimension(
+            "board dimensions smaller than trim allowance",
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use crate::error::{NestError, NestResult};
+use crate::metrics::UtilizationBreakdown;
+use crate::planar::{
+    PlanarNestConfig, PlanarStrategy, RectPart, SheetLayout, SheetStock, summarize_sheet_layouts,
+    validate_inputs,
+};
+use crate::unplaced::{UnplacedPart, UnplacedReason};
+
+const EPS: f64 = 1e-6;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialLayouts {
+    pub material: String,
+    pub thickness: f64,
+    pub layouts: Vec<SheetLayout>,
+    pub unplaced: Vec<UnplacedPart>,
+    pub metrics: UtilizationBreakdown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialNestResult {
+    pub groups: Vec<MaterialLayouts>,
+    /// parts no stock shares a material and thickness with
+    pub unmatched: Vec<UnplacedPart>,
+}
+
+fn same_material(material: &str, thickness: f64, other: &str, other_thickness: f64) -> bool {
+    material == other && (thickness - other_thickness).abs() <= EPS
+}
+
+/// Nests a mixed cutlist one material at a time, each group of parts against the stock with
+/// the same material and thickness, in the order the groups first appear in `parts`. Groups
+/// pack as `partial_sheets` does, so a short group does not fail the others.
+pub fn material_sheets(
+    parts: &[RectPart],
+    stock: &[SheetStock],
+    config: &PlanarNestConfig,
+    strategy: PlanarStrategy,
+) -> NestResult<MaterialNestResult> {
+    validate_inputs(parts, stock)?;
+    if parts.iter().any(|p| p.thickness < 0.0) || stock.iter().any(|s| s.thickness < 0.0) {
+        return Err(NestError::InvalidDimension(
+            "thickness must not be negative",
+        ));
+    }
+
+    let mut keys: Vec<(&str, f64)> = Vec::new();
+    for part in parts {
+        if !keys
+            .iter()
+            .any(|(m, t)| same_material(m, *t, &part.material, part.thickness))
+        {
+            keys.push((&part.material, part.thickness));
+        }
+    }
+
+    let mut groups = Vec::new();
+    let mut unmatched = Vec::new();
+    for (material, thickness) in keys {
+        let members: Vec<RectPart> = parts
+            .iter()
+            .filter(|p| same_material(material, thickness, &p.material, p.thickness))
+            .cloned()
+            .collect();
+        let matching: Vec<SheetStock> = stock
+            .iter()
+            .filter(|s| same_material(material, thickness, &s.material, s.thickness))
+            .cloned()
+            .collect();
+        if matching.is_empty() {
+            unmatched.extend(members.iter().map(|p| UnplacedPart {
+                part_id: p.id.clone(),
+                quantity: p.quantity,
+                reason: UnplacedReason::NoMatchingStock,
+            }));
+            continue;
+        }
+        let instances = strategy.order(&members, config.seed)?;
+        let result = strategy.pack(&instances, &matching, config)?;
+        groups.push(MaterialLayouts {
+            material: material.to_string(),
+            thickness,
+            metrics: summarize_sheet_layouts(&result.layouts),
+            layouts: result.layouts,
+            unplaced: result.unplaced,
+        });
+    }
+    Ok(MaterialNestResult { groups, unmatched })
+}
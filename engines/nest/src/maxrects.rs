@@ -0,0 +1,399 @@
+use crate::cutline::measure_layout;
+use crate::error::{NestError, NestResult};
+use crate::exclusion::blocked_rects;
+use crate::metrics::{MetricKind, UtilizationBreakdown};
+use crate::planar::{
+    OffcutRect, Orientation, PlanarNestConfig, RectInstance, RectPart, RectPlacement, SheetLayout,
+    SheetPacker, SheetStock, complete_layouts, pack_instances, validate_inputs,
+};
+use crate::util::{cmp_f64_desc, hash_with_seed};
+
+const EPS: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaxRectsHeuristic {
+    BestShortSideFit,
+    BestLongSideFit,
+    BestAreaFit,
+    BottomLeft,
+    ContactPoint,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Rect {
+    pub(crate) x: f64,
+    pub(crate) y: f64,
+    pub(crate) width: f64,
+    pub(crate) height: f64,
+}
+
+impl Rect {
+    pub(crate) fn right(&self) -> f64 {
+        self.x + self.width
+    }
+
+    pub(crate) fn top(&self) -> f64 {
+        self.y + self.height
+    }
+
+    pub(crate) fn area(&self) -> f64 {
+        self.width * self.height
+    }
+
+    pub(crate) fn intersects(&self, other: &Rect) -> bool {
+        self.x < other.right() - EPS
+            && other.x < self.right() - EPS
+            && self.y < other.top() - EPS
+            && other.y < self.top() - EPS
+    }
+
+    fn contains(&self, other: &Rect) -> bool {
+        other.x >= self.x - EPS
+            && other.y >= self.y - EPS
+            && other.right() <= self.right() + EPS
+            && other.top() <= self.top() + EPS
+    }
+
+    pub(crate) fn clip(&self, bounds: &Rect) -> Option<Rect> {
+        let x = self.x.max(bounds.x);
+        let y = self.y.max(bounds.y);
+        let width = self.right().min(bounds.right()) - x;
+        let height = self.top().min(bounds.top()) - y;
+        (width > EPS && height > EPS).then_some(Rect {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+
+    // maximal rectangles of `self` left free around `used`
+    pub(crate) fn split_around(&self, used: &Rect, out: &mut Vec<Rect>) {
+        if used.x > self.x + EPS {
+            out.push(Rect {
+                width: used.x - self.x,
+                ..*self
+            });
+        }
+        if used.right() < self.right() - EPS {
+            out.push(Rect {
+                x: used.right(),
+                width: self.right() - used.right(),
+                ..*self
+            });
+        }
+        if used.y > self.y + EPS {
+            out.push(Rect {
+                height: used.y - self.y,
+                ..*self
+            });
+        }
+        if used.top() < self.top() - EPS {
+            out.push(Rect {
+                y: used.top(),
+                height: self.top() - used.top(),
+                ..*self
+            });
+        }
+    }
+
+    // disjoint pieces of `self` outside `taken`
+    fn subtract(&self, taken: &Rect, out: &mut Vec<Rect>) {
+        if !self.intersects(taken) {
+            out.push(*self);
+            return;
+        }
+        let left = taken.x.max(self.x);
+        let right = taken.right().min(self.right());
+        let pieces = [
+            Rect {
+                width: left - self.x,
+                ..*self
+            },
+            Rect {
+                x: right,
+                width: self.right() - right,
+                ..*self
+            },
+            Rect {
+                x: left,
+                width: right - left,
+                height: taken.y - self.y,
+                ..*self
+            },
+            Rect {
+                x: left,
+                y: taken.top(),
+                width: right - left,
+                height: self.top() - taken.top(),
+            },
+        ];
+        out.extend(
+            pieces
+                .into_iter()
+                .filter(|r| r.width > EPS && r.height > EPS),
+        );
+    }
+}
+
+pub(crate) struct MaxRectsState {
+    stock: SheetStock,
+    index: usize,
+    config: PlanarNestConfig,
+    heuristic: MaxRectsHeuristic,
+    // usable area grown by one kerf on the far edges, so every part can carry its kerf to the
+    // right and above it
+    bin: Rect,
+    usable: Rect,
+    free: Vec<Rect>,
+    used: Vec<Rect>,
+    placements: Vec<RectPlacement>,
+}
+
+impl MaxRectsState {
+    // lower is better
+    fn score(&self, free: &Rect, width: f64, height: f64) -> (f64, f64) {
+        let leftover_w = free.width - width;
+        let leftover_h = free.height - height;
+        match self.heuristic {
+            MaxRectsHeuristic::BestShortSideFit => {
+                (leftover_w.min(leftover_h), leftover_w.max(leftover_h))
+            }
+            MaxRectsHeuristic::BestLongSideFit => {
+                (leftover_w.max(leftover_h), leftover_w.min(leftover_h))
+            }
+            MaxRectsHeuristic::BestAreaFit => {
+                (free.area() - width * height, leftover_w.min(leftover_h))
+            }
+            MaxRectsHeuristic::BottomLeft => (free.y + height, free.x),
+            MaxRectsHeuristic::ContactPoint => {
+                let candidate = Rect {
+                    width,
+                    height,
+                    ..*free
+                };
+                (-self.contact(&candidate), free.y + height)
+            }
+        }
+    }
+
+    fn contact(&self, rect: &Rect) -> f64 {
+        let overlap = |a0: f64, a1: f64, b0: f64, b1: f64| (a1.min(b1) - a0.max(b0)).max(0.0);
+        let mut total = 0.0;
+        if (rect.x - self.bin.x).abs() < EPS || (rect.right() - self.bin.right()).abs() < EPS {
+            total += rect.height;
+        }
+        if (rect.y - self.bin.y).abs() < EPS || (rect.top() - self.bin.top()).abs() < EPS {
+            total += rect.width;
+        }
+        for used in &self.used {
+            if (used.x - rect.right()).abs() < EPS || (used.right() - rect.x).abs() < EPS {
+                total += overlap(used.y, used.top(), rect.y, rect.top());
+            }
+            if (used.y - rect.top()).abs() < EPS || (used.top() - rect.y).abs() < EPS {
+                total += overlap(used.x, used.right(), rect.x, rect.right());
+            }
+        }
+        total
+    }
+
+    fn commit(&mut self, footprint: Rect) {
+        self.block(footprint);
+        self.used.push(footprint);
+    }
+
+    fn block(&mut self, footprint: Rect) {
+        let mut next = Vec::with_capacity(self.free.len() + 4);
+        for free in &self.free {
+            if free.intersects(&footprint) {
+                free.split_around(&footprint, &mut next);
+            } else {
+                next.push(*free);
+            }
+        }
+        self.free = prune(next);
+    }
+}
+
+pub(crate) fn prune(mut rects: Vec<Rect>) -> Vec<Rect> {
+    rects.retain(|r| r.width > EPS && r.height > EPS);
+    let mut keep = vec![true; rects.len()];
+    for i in 0..rects.len() {
+        for j in 0..rects.len() {
+            if i != j && keep[j] && rects[j].contains(&rects[i]) {
+                // of two identical rectangles keep the first
+                if !rects[i].contains(&rects[j]) || j < i {
+                    keep[i] = false;
+                    break;
+                }
+            }
+        }
+    }
+    rects
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(r, k)| k.then_some(r))
+        .collect()
+}
+
+impl SheetPacker for MaxRectsState {
+    type Options = MaxRectsHeuristic;
+
+    fn open(
+        stock: &SheetStock,
+        index: usize,
+        config: &PlanarNestConfig,
+        options: MaxRectsHeuristic,
+    ) -> NestResult<Self> {
+        if stock.width <= 2.0 * config.trim || stock.height <= 2.0 * config.trim {
+            return Err(NestError::InvalidDimension(
+                "sheet dimensions smaller than trim allowance",
+            ));
+        }
+        let usable = Rect {
+            x: config.trim,
+            y: config.trim,
+            width: stock.width - 2.0 * config.trim,
+            height: stock.height - 2.0 * config.trim,
+        };
+        let bin = Rect {
+            width: usable.width + config.kerf,
+            height: usable.height + config.kerf,
+            ..usable
+        };
+        let mut state = Self {
+            stock: stock.clone(),
+            index,
+            config: *config,
+            heuristic: options,
+            bin,
+            usable,
+            free: vec![bin],
+            used: Vec::new(),
+            placements: Vec::new(),
+        };
+        // zones carry a kerf above and to the right like the parts, which keeps parts on every
+        // side of them a kerf clear
+        for zone in blocked_rects(stock, config.trim, 0.0) {
+            let footprint = Rect {
+                x: zone.x,
+                y: zone.y,
+                width: zone.width + config.kerf,
+                height: zone.height + config.kerf,
+            };
+            if let Some(footprint) = footprint.clip(&bin) {
+                state.block(footprint);
+            }
+        }
+        Ok(state)
+    }
+
+    fn place(&mut self, part: &RectInstance) -> Option<()> {
+        let kerf = self.config.kerf;
+        let mut best: Option<((f64, f64), Rect, Orientation)> = None;
+        for orientation in Orientation::options_for(part, &self.stock) {
+            let (width, height) = (orientation.width + kerf, orientation.height + kerf);
+            for free in &self.free {
+                if width > free.width + EPS || height > free.height + EPS {
+                    continue;
+                }
+                let score = self.score(free, width, height);
+                let better = best.as_ref().is_none_or(|(current, _, _)| {
+                    score.0 < current.0 - EPS
+                        || (score.0 - current.0).abs() <= EPS && score.1 < current.1 - EPS
+                });
+                if better {
+                    let footprint = Rect {
+                        width,
+                        height,
+                        ..*free
+                    };
+                    best = Some((score, footprint, orientation));
+                }
+            }
+        }
+
+        let (_, footprint, orientation) = best?;
+        self.placements
+            .push(orientation.placement(part, footprint.x, footprint.y));
+        self.commit(footprint);
+        Some(())
+    }
+
+    fn finalize(self) -> SheetLayout {
+        // free rectangles overlap; hand out the largest first and cut it from the rest
+        let mut pending: Vec<Rect> = self
+            .free
+            .iter()
+            .filter_map(|r| r.clip(&self.usable))
+            .collect();
+        let mut offcuts = Vec::new();
+        while !pending.is_empty() {
+            let (idx, _) = pending
+                .iter()
+                .enumerate()
+                .max_by(|a, b| cmp_f64_desc(b.1.area(), a.1.area()))
+                .expect("pending offcut");
+            let taken = pending.swap_remove(idx);
+            let mut rest = Vec::with_capacity(pending.len());
+            for rect in &pending {
+                rect.subtract(&taken, &mut rest);
+            }
+            pending = rest;
+            offcuts.push(OffcutRect {
+                x: taken.x,
+                y: taken.y,
+                width: taken.width,
+                height: taken.height,
+            });
+        }
+
+        let mut layout = SheetLayout {
+            stock_id: self.stock.id.clone(),
+            index: self.index,
+            placements: self.placements,
+            offcuts,
+            metrics: UtilizationBreakdown::new(MetricKind::Area),
+            cost: self.stock.cost,
+            cuts: Vec::new(),
+            segments: Vec::new(),
+            source: self.stock.source.clone(),
+        };
+        measure_layout(&mut layout, &self.stock, &self.config);
+        layout
+    }
+}
+
+pub(crate) fn max_rects_order(parts: &[RectPart], seed: u64) -> Vec<RectInstance> {
+    let mut part_instances: Vec<RectInstance> =
+        parts.iter().flat_map(|part| part.instances()).collect();
+    part_instances.sort_by(|a, b| {
+        cmp_f64_desc(a.area(), b.area())
+            .then(cmp_f64_desc(
+                a.base_width.max(a.base_height),
+                b.base_width.max(b.base_height),
+            ))
+            .then_with(|| {
+                let ha = hash_with_seed(&a.id, seed ^ a.seq as u64);
+                let hb = hash_with_seed(&b.id, seed ^ b.seq as u64);
+                ha.cmp(&hb)
+            })
+    });
+    part_instances
+}
+
+pub fn max_rects_sheets(
+    parts: &[RectPart],
+    stock: &[SheetStock],
+    config: &PlanarNestConfig,
+    heuristic: MaxRectsHeuristic,
+) -> NestResult<Vec<SheetLayout>> {
+    validate_inputs(parts, stock)?;
+    let part_instances = max_rects_order(parts, config.seed);
+    complete_layouts(pack_instances::<MaxRectsState>(
+        &part_instances,
+        stock,
+        config,
+        heuristic,
+    )?)
+}
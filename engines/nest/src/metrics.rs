@@ -11,6 +11,7 @@ pub struct UtilizationBreakdown {
     pub kerf_loss: f64,
     pub trim_loss: f64,
     pub offcut_loss: f64,
+    pub defect_loss: f64,
     pub stock_total: f64,
 }
 
@@ -22,6 +23,7 @@ impl UtilizationBreakdown {
             kerf_loss: 0.0,
             trim_loss: 0.0,
             offcut_loss: 0.0,
+            defect_loss: 0.0,
             stock_total: 0.0,
         }
     }
@@ -0,0 +1,49 @@
+use std::cmp::Ordering;
+
+/// Scores the stock to open next. The solvers choose sheet by sheet (or board by board), so
+/// each objective is met greedily rather than over the whole run; `MinCost` looks ahead to the
+/// end of the run before each choice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NestObjective {
+    #[default]
+    MinCount,
+    /// least total cost: each stock is priced with the parts it leaves packed onto fresh stock
+    /// of the one type that takes them all most cheaply
+    MinCost,
+    MinWaste,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct StockTrial {
+    pub(crate) packed: f64,
+    pub(crate) total: f64,
+    pub(crate) cost: f64,
+    // the cost plus the cheapest finish found for the parts left over; only `MinCost` prices it
+    pub(crate) run_cost: f64,
+}
+
+impl NestObjective {
+    // Less means `a` is the better stock to open next
+    pub(crate) fn compare(self, a: &StockTrial, b: &StockTrial) -> Ordering {
+        let more_packed = cmp_f64(b.packed, a.packed);
+        match self {
+            NestObjective::MinCount => more_packed
+                .then(cmp_f64(a.cost, b.cost))
+                .then(cmp_f64(a.total, b.total)),
+            NestObjective::MinCost => cmp_f64(a.run_cost, b.run_cost)
+                .then(cmp_f64(a.cost / a.packed, b.cost / b.packed))
+                .then(more_packed),
+            NestObjective::MinWaste => {
+                cmp_f64(a.total - a.packed, b.total - b.packed).then(more_packed)
+            }
+        }
+    }
+}
+
+fn cmp_f64(lhs: f64, rhs: f64) -> Ordering {
+    if (lhs - rhs).abs() <= 1e-9 {
+        Ordering::Equal
+    } else {
+        lhs.partial_cmp(&rhs).unwrap_or(Ordering::Equal)
+    }
+}
@@ -0,0 +1,60 @@
+use crate::planar::{RectPlacement, SheetLayout};
+use crate::util::cmp_f64_desc;
+
+const EPS: f64 = 1e-6;
+
+/// One layout cut `count` times over. `indices` are the sheet indices of the repeats, in the
+/// order they were given.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetPattern {
+    pub layout: SheetLayout,
+    pub count: usize,
+    pub indices: Vec<usize>,
+}
+
+// placements by position, so the order a packer listed them in does not matter
+fn by_position(layout: &SheetLayout) -> Vec<&RectPlacement> {
+    let mut placements: Vec<&RectPlacement> = layout.placements.iter().collect();
+    placements.sort_by(|a, b| cmp_f64_desc(b.y, a.y).then(cmp_f64_desc(b.x, a.x)));
+    placements
+}
+
+fn same_pattern(a: &SheetLayout, b: &SheetLayout) -> bool {
+    if a.stock_id != b.stock_id || a.source != b.source || a.placements.len() != b.placements.len()
+    {
+        return false;
+    }
+    by_position(a).iter().zip(by_position(b)).all(|(p, q)| {
+        p.part_id == q.part_id
+            && (p.x - q.x).abs() <= EPS
+            && (p.y - q.y).abs() <= EPS
+            && (p.width - q.width).abs() <= EPS
+            && (p.height - q.height).abs() <= EPS
+            && p.rotated == q.rotated
+            && p.flipped == q.flipped
+    })
+}
+
+/// Groups layouts that cut the same parts in the same places on the same stock, in the order
+/// each pattern first appears. The first layout of a group stands for the pattern. Only the
+/// improver weighs the pattern count; the packers place parts without regard to it.
+pub fn sheet_patterns(layouts: &[SheetLayout]) -> Vec<SheetPattern> {
+    let mut patterns: Vec<SheetPattern> = Vec::new();
+    for layout in layouts {
+        match patterns
+            .iter_mut()
+            .find(|pattern| same_pattern(&pattern.layout, layout))
+        {
+            Some(pattern) => {
+                pattern.count += 1;
+                pattern.indices.push(layout.index);
+            }
+            None => patterns.push(SheetPattern {
+                layout: layout.clone(),
+                count: 1,
+                indices: vec![layout.index],
+            }),
+        }
+    }
+    patterns
+}
@@ -1,41 +1,121 @@
+use crate::cutline::{CutSegment, measure_layout};
 use crate::error::{NestError, NestResult};
+use crate::exclusion::{ExclusionZone, blocked_rects, carve_offcuts, intersects, zones_overlap};
+use crate::guillotine::{GuillotineConfig, GuillotineCut, GuillotineState, guillotine_order};
+use crate::inventory::OffcutSource;
+use crate::maxrects::{MaxRectsHeuristic, MaxRectsState, max_rects_order};
 use crate::metrics::{MetricKind, UtilizationBreakdown};
+use crate::objective::{NestObjective, StockTrial};
+use crate::unplaced::{UnplacedPart, UnplacedReason, record_unplaced};
 use crate::util::{cmp_f64_desc, hash_with_seed};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// On a part, the axis its grain must run along. On a sheet, the axis its grain runs along;
+/// `Either` there means no declared grain, and grain-bound parts keep the way they are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum GrainDirection {
     AlongX,
     AlongY,
+    #[default]
     Either,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// A is the better face. A part's grade is the worst face its show side may land on; sheets
+/// are taken as good on both faces unless graded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum FaceGrade {
+    #[default]
+    A,
+    B,
+}
+
+/// One amount per edge of a part as drawn: left and right run along its height, bottom and
+/// top along its width.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PartEdges {
+    pub left: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub top: f64,
+}
+
+impl PartEdges {
+    fn is_valid(&self) -> bool {
+        [self.left, self.right, self.bottom, self.top]
+            .iter()
+            .all(|v| *v >= 0.0)
+    }
+}
+
+/// `width` and `height` are the finished size. The saw cuts it less the banding glued on
+/// afterwards and plus the allowance milled off.
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct RectPart {
     pub id: String,
     pub width: f64,
     pub height: f64,
     pub quantity: usize,
     pub grain: GrainDirection,
+    pub face: FaceGrade,
+    pub material: String,
+    pub thickness: f64,
+    pub banding: PartEdges,
+    pub allowance: PartEdges,
 }
 
 impl RectPart {
-    fn instances(&self) -> impl Iterator<Item = RectInstance> + '_ {
+    pub(crate) fn cut_size(&self) -> (f64, f64) {
+        let (band, extra) = (&self.banding, &self.allowance);
+        (
+            self.width - band.left - band.right + extra.left + extra.right,
+            self.height - band.bottom - band.top + extra.bottom + extra.top,
+        )
+    }
+
+    pub(crate) fn instances(&self) -> impl Iterator<Item = RectInstance> + '_ {
+        let (cut_width, cut_height) = self.cut_size();
         (0..self.quantity).map(move |seq| RectInstance {
             id: self.id.clone(),
-            base_width: self.width,
-            base_height: self.height,
+            base_width: cut_width,
+            base_height: cut_height,
+            finished_width: self.width,
+            finished_height: self.height,
             grain: self.grain,
+            face: self.face,
             seq,
+            rotate_first: false,
+            members: None,
         })
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct SheetStock {
     pub id: String,
     pub width: f64,
     pub height: f64,
     pub quantity: usize,
+    pub cost: f64,
+    pub exclusions: Vec<ExclusionZone>,
+    pub grain: GrainDirection,
+    pub top_face: FaceGrade,
+    pub bottom_face: FaceGrade,
+    pub source: Option<OffcutSource>,
+    pub material: String,
+    pub thickness: f64,
+}
+
+impl SheetStock {
+    // whether a part's show face goes up (`Some(false)`) or down (`Some(true)`), or `None` when
+    // neither face of the sheet is good enough for it
+    pub(crate) fn face_side(&self, face: FaceGrade) -> Option<bool> {
+        if self.top_face <= face {
+            Some(false)
+        } else if self.bottom_face <= face {
+            Some(true)
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -43,6 +123,7 @@ pub struct PlanarNestConfig {
     pub kerf: f64,
     pub trim: f64,
     pub seed: u64,
+    pub objective: NestObjective,
 }
 
 impl Default for PlanarNestConfig {
@@ -51,6 +132,7 @@ impl Default for PlanarNestConfig {
             kerf: 0.0,
             trim: 0.0,
             seed: 0,
+            objective: NestObjective::MinCount,
         }
     }
 }
@@ -60,9 +142,17 @@ pub struct RectPlacement {
     pub part_id: String,
     pub x: f64,
     pub y: f64,
+    /// the size cut, on the sheet's axes
     pub width: f64,
     pub height: f64,
     pub rotated: bool,
+    /// turned over so the show face is on the underside of the sheet
+    pub flipped: bool,
+    /// the size once banded and milled, on the sheet's axes too
+    pub finished_width: f64,
+    pub finished_height: f64,
+    /// held in place when the sheet is nested again
+    pub locked: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -80,15 +170,55 @@ pub struct SheetLayout {
     pub placements: Vec<RectPlacement>,
     pub offcuts: Vec<OffcutRect>,
     pub metrics: UtilizationBreakdown,
+    pub cost: f64,
+    pub cuts: Vec<GuillotineCut>,
+    /// every pass that cuts the parts free, guillotine cuts included
+    pub segments: Vec<CutSegment>,
+    pub source: Option<OffcutSource>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanarNestResult {
+    pub layouts: Vec<SheetLayout>,
+    pub unplaced: Vec<UnplacedPart>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanarStrategy {
+    BestFit,
+    Skyline,
+    Guillotine(GuillotineConfig),
+    MaxRects(MaxRectsHeuristic),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct RectInstance {
+    pub(crate) id: String,
+    pub(crate) base_width: f64,
+    pub(crate) base_height: f64,
+    pub(crate) finished_width: f64,
+    pub(crate) finished_height: f64,
+    pub(crate) grain: GrainDirection,
+    pub(crate) face: FaceGrade,
+    pub(crate) seq: usize,
+    // try the rotated orientation before the base one
+    pub(crate) rotate_first: bool,
+    // set on a group block, whose members the guillotine saw parts inside its cut tree
+    pub(crate) members: Option<BlockMembers>,
 }
 
 #[derive(Debug, Clone)]
-struct RectInstance {
-    id: String,
-    base_width: f64,
-    base_height: f64,
-    grain: GrainDirection,
-    seq: usize,
+pub(crate) struct BlockMembers {
+    // whether the members follow each other along the block's drawn width
+    pub(crate) along_width: bool,
+    // each member's cut length along that line and breadth across it, in order
+    pub(crate) sizes: Vec<(f64, f64)>,
+}
+
+impl RectInstance {
+    pub(crate) fn area(&self) -> f64 {
+        self.base_width * self.base_height
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -97,6 +227,210 @@ struct SheetSupply {
     used: usize,
 }
 
+pub(crate) trait SheetPacker: Sized {
+    // strategy settings that do not belong in `PlanarNestConfig`
+    type Options: Copy;
+
+    fn open(
+        stock: &SheetStock,
+        index: usize,
+        config: &PlanarNestConfig,
+        options: Self::Options,
+    ) -> NestResult<Self>;
+    fn place(&mut self, part: &RectInstance) -> Option<()>;
+    fn finalize(self) -> SheetLayout;
+    // Parts already cut from the sheet, which it also lists as zones. Only a packer that plans
+    // its own cuts has to keep those cuts out of them.
+    fn lock(&mut self, _locked: &[OffcutRect]) {}
+}
+
+pub(crate) fn pack_instances<P: SheetPacker>(
+    instances: &[RectInstance],
+    stock: &[SheetStock],
+    config: &PlanarNestConfig,
+    options: P::Options,
+) -> NestResult<PlanarNestResult> {
+    pack_onto::<P>(&[], instances, stock, config, options)
+}
+
+// Like `pack_instances`, with sheets already in use opened first. Each comes as the stock to
+// open, zones included, its index and the parts locked on it; fresh sheets of that stock are
+// numbered after it.
+pub(crate) fn pack_onto<P: SheetPacker>(
+    opened: &[(SheetStock, usize, Vec<OffcutRect>)],
+    instances: &[RectInstance],
+    stock: &[SheetStock],
+    config: &PlanarNestConfig,
+    options: P::Options,
+) -> NestResult<PlanarNestResult> {
+    let mut supplies: Vec<SheetSupply> = stock
+        .iter()
+        .map(|sheet| SheetSupply {
+            stock: sheet.clone(),
+            used: opened
+                .iter()
+                .filter(|(open, _, _)| open.id == sheet.id)
+                .map(|(_, index, _)| index + 1)
+                .max()
+                .unwrap_or(0),
+        })
+        .collect();
+
+    let mut sheets: Vec<P> = opened
+        .iter()
+        .map(|(open, index, locked)| {
+            let mut sheet = P::open(open, *index, config, options)?;
+            sheet.lock(locked);
+            Ok(sheet)
+        })
+        .collect::<NestResult<_>>()?;
+    let mut unplaced = Vec::new();
+
+    for (pos, part) in instances.iter().enumerate() {
+        if sheets.iter_mut().any(|sheet| sheet.place(part).is_some()) {
+            continue;
+        }
+        let Some(choice) = choose_supply::<P>(&supplies, &instances[pos..], config, options)?
+        else {
+            let reason = if !stock.iter().any(|s| s.face_side(part.face).is_some()) {
+                UnplacedReason::NoSuitableFace
+            } else if fits_any_stock::<P>(stock, part, config, options) {
+                UnplacedReason::StockExhausted
+            } else {
+                UnplacedReason::LargerThanAnyStock
+            };
+            record_unplaced(&mut unplaced, &part.id, reason);
+            continue;
+        };
+        let supply = &mut supplies[choice];
+
+        let mut sheet = P::open(&supply.stock, supply.used, config, options)?;
+        supply.used += 1;
+        if sheet.place(part).is_none() {
+            return Err(NestError::InsufficientStock);
+        }
+        sheets.push(sheet);
+    }
+
+    let mut layouts: Vec<SheetLayout> = sheets.into_iter().map(|s| s.finalize()).collect();
+    layouts.sort_by(|a, b| a.stock_id.cmp(&b.stock_id).then(a.index.cmp(&b.index)));
+    Ok(PlanarNestResult { layouts, unplaced })
+}
+
+// whether a fresh sheet of any listed stock, ignoring quantities, has room for the part
+// What packing `parts` first-fit onto fresh sheets of `stock` alone costs, or `None` when the
+// sheets left of it cannot take them all.
+fn finish_cost<P: SheetPacker>(
+    stock: &SheetStock,
+    mut used: usize,
+    parts: &[RectInstance],
+    config: &PlanarNestConfig,
+    options: P::Options,
+) -> Option<f64> {
+    let mut sheets: Vec<P> = Vec::new();
+    for part in parts {
+        if sheets.iter_mut().any(|sheet| sheet.place(part).is_some()) {
+            continue;
+        }
+        if used >= stock.quantity {
+            return None;
+        }
+        let mut sheet = P::open(stock, used, config, options).ok()?;
+        used += 1;
+        sheet.place(part)?;
+        sheets.push(sheet);
+    }
+    Some(stock.cost * sheets.len() as f64)
+}
+
+fn fits_any_stock<P: SheetPacker>(
+    stock: &[SheetStock],
+    part: &RectInstance,
+    config: &PlanarNestConfig,
+    options: P::Options,
+) -> bool {
+    stock.iter().any(|sheet| {
+        P::open(sheet, 0, config, options).is_ok_and(|mut trial| trial.place(part).is_some())
+    })
+}
+
+pub(crate) fn complete_layouts(result: PlanarNestResult) -> NestResult<Vec<SheetLayout>> {
+    if result.unplaced.is_empty() {
+        Ok(result.layouts)
+    } else {
+        Err(NestError::InsufficientStock)
+    }
+}
+
+// Packs a trial sheet of every available stock with the parts still to place and keeps the
+// stock the objective scores best. The choice is greedy, one sheet at a time, at the price of
+// a trial pack per stock type for every sheet opened. Under `MinCost` each trial is priced with
+// what it leaves repacked onto every stock type in turn as well, so the mix a run ends with is
+// searched one step ahead but still not as a whole.
+fn choose_supply<P: SheetPacker>(
+    supplies: &[SheetSupply],
+    remaining: &[RectInstance],
+    config: &PlanarNestConfig,
+    options: P::Options,
+) -> NestResult<Option<usize>> {
+    let mut best: Option<(usize, StockTrial)> = None;
+    let mut open_error = None;
+    for (idx, supply) in supplies.iter().enumerate() {
+        if supply.used >= supply.stock.quantity {
+            continue;
+        }
+        let mut trial = match P::open(&supply.stock, supply.used, config, options) {
+            Ok(trial) => trial,
+            Err(err) => {
+                open_error.get_or_insert(err);
+                continue;
+            }
+        };
+        if trial.place(&remaining[0]).is_none() {
+            continue;
+        }
+        let mut packed = remaining[0].area();
+        let mut left = Vec::new();
+        for part in &remaining[1..] {
+            match trial.place(part) {
+                Some(()) => packed += part.area(),
+                None => left.push(part.clone()),
+            }
+        }
+        let run_cost = if config.objective == NestObjective::MinCost {
+            let finish = (0..supplies.len())
+                .filter_map(|other| {
+                    let used = supplies[other].used + usize::from(other == idx);
+                    finish_cost::<P>(&supplies[other].stock, used, &left, config, options)
+                })
+                .fold(f64::INFINITY, f64::min);
+            supply.stock.cost + finish
+        } else {
+            0.0
+        };
+        let score = StockTrial {
+            packed,
+            total: supply.stock.width * supply.stock.height,
+            cost: supply.stock.cost,
+            run_cost,
+        };
+        let reclaimed = supply.stock.source.is_some();
+        let better = best.as_ref().is_none_or(|(current_idx, current)| {
+            let current_reclaimed = supplies[*current_idx].stock.source.is_some();
+            reclaimed && !current_reclaimed
+                || reclaimed == current_reclaimed
+                    && config.objective.compare(&score, current) == std::cmp::Ordering::Less
+        });
+        if better {
+            best = Some((idx, score));
+        }
+    }
+    match (best, open_error) {
+        (None, Some(err)) => Err(err),
+        (best, _) => Ok(best.map(|(idx, _)| idx)),
+    }
+}
+
 #[derive(Debug, Clone)]
 struct FreeRect {
     x: f64,
@@ -130,16 +464,27 @@ impl SheetState {
                 "sheet dimensions smaller than trim allowance",
             ));
         }
-        let usable = FreeRect {
+        let usable = OffcutRect {
             x: config.trim,
             y: config.trim,
             width: stock.width - 2.0 * config.trim,
             height: stock.height - 2.0 * config.trim,
         };
+        // a kerf all round each zone keeps parts in neighbouring free rects clear of it
+        let blocked = blocked_rects(stock, config.trim, config.kerf);
+        let free_rects = carve_offcuts(vec![usable], &blocked)
+            .into_iter()
+            .map(|r| FreeRect {
+                x: r.x,
+                y: r.y,
+                width: r.width,
+                height: r.height,
+            })
+            .collect();
         Ok(Self {
             stock: stock.clone(),
             index,
-            free_rects: vec![usable],
+            free_rects,
             placements: Vec::new(),
             config: *config,
         })
@@ -148,7 +493,7 @@ impl SheetState {
     fn place_best_fit(&mut self, part: &RectInstance) -> Option<()> {
         let mut best_choice: Option<(usize, Orientation, (f64, f64))> = None;
         for (idx, rect) in self.free_rects.iter().enumerate() {
-            for orientation in Orientation::options_for(part) {
+            for orientation in Orientation::options_for(part, &self.stock) {
                 let (pw, ph) = (orientation.width, orientation.height);
                 if !rect.can_fit(pw, ph) {
                     continue;
@@ -182,17 +527,38 @@ impl SheetState {
     ) -> Option<()> {
         let rect = self.free_rects.swap_remove(rect_idx);
         let (pw, ph) = (orientation.width, orientation.height);
-        let placement = RectPlacement {
-            part_id: part.id.clone(),
-            x: rect.x,
-            y: rect.y,
-            width: pw,
-            height: ph,
-            rotated: orientation.rotated,
-        };
-        self.placements.push(placement);
+        self.placements
+            .push(orientation.placement(part, rect.x, rect.y));
 
         let kerf = self.config.kerf;
+        // the pieces carved around a zone touch, so a part flush with one edge of its rect can
+        // border the next rect: keep every other rect a kerf clear of the part
+        let clear = OffcutRect {
+            x: rect.x - kerf,
+            y: rect.y - kerf,
+            width: pw + 2.0 * kerf,
+            height: ph + 2.0 * kerf,
+        };
+        let others = self
+            .free_rects
+            .drain(..)
+            .map(|r| OffcutRect {
+                x: r.x,
+                y: r.y,
+                width: r.width,
+                height: r.height,
+            })
+            .collect();
+        self.free_rects = carve_offcuts(others, std::slice::from_ref(&clear))
+            .into_iter()
+            .map(|r| FreeRect {
+                x: r.x,
+                y: r.y,
+                width: r.width,
+                height: r.height,
+            })
+            .collect();
+
         let right_width = rect.width - pw - kerf;
         if right_width > 1e-9 {
             self.free_rects.push(FreeRect {
@@ -208,7 +574,7 @@ impl SheetState {
             self.free_rects.push(FreeRect {
                 x: rect.x,
                 y: rect.y + ph + kerf,
-                width: (pw + kerf).min(rect.width),
+                width: pw,
                 height: top_height,
             });
         }
@@ -240,24 +606,6 @@ impl SheetState {
     fn finalize(mut self) -> SheetLayout {
         self.prune_free_rects();
 
-        let mut metrics = UtilizationBreakdown::new(MetricKind::Area);
-        metrics.stock_total = self.stock.width * self.stock.height;
-        let trim_area = (self.stock.width * self.config.trim * 2.0)
-            + (self.stock.height * self.config.trim * 2.0)
-            - (4.0 * self.config.trim * self.config.trim);
-        metrics.trim_loss = trim_area.max(0.0);
-        metrics.kerf_loss = self
-            .placements
-            .iter()
-            .map(|p| {
-                let perimeter = 2.0 * (p.width + p.height);
-                perimeter * self.config.kerf * 0.5
-            })
-            .sum();
-        metrics.utilized = self.placements.iter().map(|p| p.width * p.height).sum();
-        let occupied = metrics.utilized + metrics.kerf_loss + metrics.trim_loss;
-        metrics.offcut_loss = (metrics.stock_total - occupied).max(0.0);
-
         let offcuts = self
             .free_rects
             .into_iter()
@@ -269,39 +617,100 @@ impl SheetState {
             })
             .collect();
 
-        SheetLayout {
+        let mut layout = SheetLayout {
             stock_id: self.stock.id.clone(),
             index: self.index,
             placements: self.placements,
             offcuts,
-            metrics,
-        }
+            metrics: UtilizationBreakdown::new(MetricKind::Area),
+            cost: self.stock.cost,
+            cuts: Vec::new(),
+            segments: Vec::new(),
+            source: self.stock.source.clone(),
+        };
+        measure_layout(&mut layout, &self.stock, &self.config);
+        layout
+    }
+}
+
+impl SheetPacker for SheetState {
+    type Options = ();
+
+    fn open(
+        stock: &SheetStock,
+        index: usize,
+        config: &PlanarNestConfig,
+        _options: (),
+    ) -> NestResult<Self> {
+        SheetState::new(stock, index, config)
+    }
+
+    fn place(&mut self, part: &RectInstance) -> Option<()> {
+        self.place_best_fit(part)
+    }
+
+    fn finalize(self) -> SheetLayout {
+        SheetState::finalize(self)
     }
 }
 
 #[derive(Clone, Copy)]
-struct Orientation {
-    width: f64,
-    height: f64,
-    rotated: bool,
+pub(crate) struct Orientation {
+    pub(crate) width: f64,
+    pub(crate) height: f64,
+    pub(crate) rotated: bool,
+    pub(crate) flipped: bool,
 }
 
 impl Orientation {
-    fn options_for(part: &RectInstance) -> Vec<Orientation> {
-        let mut options = Vec::with_capacity(2);
-        options.push(Orientation {
-            width: part.base_width,
-            height: part.base_height,
-            rotated: false,
-        });
-        if matches!(part.grain, GrainDirection::Either) {
-            options.push(Orientation {
-                width: part.base_height,
-                height: part.base_width,
-                rotated: true,
-            });
+    // the ways the part may lie on a sheet of `stock`; none when no face of it is good enough
+    pub(crate) fn options_for(part: &RectInstance, stock: &SheetStock) -> Vec<Orientation> {
+        let Some(flipped) = stock.face_side(part.face) else {
+            return Vec::new();
+        };
+        let turned = match (part.grain, stock.grain) {
+            (GrainDirection::Either, _) => None,
+            (_, GrainDirection::Either) => Some(false),
+            (part_grain, sheet_grain) => Some(part_grain != sheet_grain),
+        };
+        let orientation = |rotated: bool| {
+            let (width, height) = if rotated {
+                (part.base_height, part.base_width)
+            } else {
+                (part.base_width, part.base_height)
+            };
+            Orientation {
+                width,
+                height,
+                rotated,
+                flipped,
+            }
+        };
+        match turned {
+            Some(rotated) => vec![orientation(rotated)],
+            None if part.rotate_first => vec![orientation(true), orientation(false)],
+            None => vec![orientation(false), orientation(true)],
+        }
+    }
+
+    pub(crate) fn placement(&self, part: &RectInstance, x: f64, y: f64) -> RectPlacement {
+        let (finished_width, finished_height) = if self.rotated {
+            (part.finished_height, part.finished_width)
+        } else {
+            (part.finished_width, part.finished_height)
+        };
+        RectPlacement {
+            part_id: part.id.clone(),
+            x,
+            y,
+            width: self.width,
+            height: self.height,
+            rotated: self.rotated,
+            flipped: self.flipped,
+            finished_width,
+            finished_height,
+            locked: false,
         }
-        options
     }
 }
 
@@ -318,79 +727,53 @@ pub fn best_fit_sheets(
     config: &PlanarNestConfig,
 ) -> NestResult<Vec<SheetLayout>> {
     validate_inputs(parts, stock)?;
+    let part_instances = best_fit_order(parts, config.seed);
+    complete_layouts(pack_instances::<SheetState>(
+        &part_instances,
+        stock,
+        config,
+        (),
+    )?)
+}
 
+fn best_fit_order(parts: &[RectPart], seed: u64) -> Vec<RectInstance> {
     let mut part_instances: Vec<RectInstance> =
         parts.iter().flat_map(|part| part.instances()).collect();
     part_instances.sort_by(|a, b| {
         cmp_f64_desc(a.base_width * a.base_height, b.base_width * b.base_height).then_with(|| {
-            let ha = hash_with_seed(&a.id, config.seed ^ a.seq as u64);
-            let hb = hash_with_seed(&b.id, config.seed ^ b.seq as u64);
+            let ha = hash_with_seed(&a.id, seed ^ a.seq as u64);
+            let hb = hash_with_seed(&b.id, seed ^ b.seq as u64);
             ha.cmp(&hb)
         })
     });
-
-    let mut supplies: Vec<SheetSupply> = stock
-        .iter()
-        .map(|sheet| SheetSupply {
-            stock: sheet.clone(),
-            used: 0,
-        })
-        .collect();
-
-    let mut sheets: Vec<SheetState> = Vec::new();
-
-    for part in &part_instances {
-        let mut placed = false;
-        for sheet in &mut sheets {
-            if sheet.place_best_fit(part).is_some() {
-                placed = true;
-                break;
-            }
-        }
-        if !placed {
-            let supply = supplies
-                .iter_mut()
-                .find(|s| s.used < s.stock.quantity)
-                .ok_or(NestError::InsufficientStock)?;
-
-            let mut sheet_state = SheetState::new(&supply.stock, supply.used, config)?;
-            supply.used += 1;
-            if sheet_state.place_best_fit(part).is_none() {
-                return Err(NestError::InsufficientStock);
-            }
-            sheets.push(sheet_state);
-        }
-    }
-
-    let mut results: Vec<SheetLayout> = sheets.into_iter().map(|s| s.finalize()).collect();
-    results.sort_by(|a, b| a.stock_id.cmp(&b.stock_id).then(a.index.cmp(&b.index)));
-    Ok(results)
+    part_instances
 }
 
-struct SkylineShelf {
+// A stretch of the skyline: the sheet is free above `y` across [x, x + width).
+#[derive(Clone, Copy)]
+struct SkylineSegment {
+    x: f64,
     y: f64,
-    height: f64,
-    cursor_x: f64,
-    width_remaining: f64,
+    width: f64,
 }
 
-impl SkylineShelf {
-    fn new(y: f64, height: f64, start_x: f64, available_width: f64) -> Self {
-        Self {
-            y,
-            height,
-            cursor_x: start_x,
-            width_remaining: available_width,
-        }
-    }
+struct SkylineFit {
+    x: f64,
+    y: f64,
+    orientation: Orientation,
+    waste: f64,
 }
 
 struct SkylineState {
     sheet: SheetStock,
     index: usize,
-    shelves: Vec<SkylineShelf>,
+    skyline: Vec<SkylineSegment>,
+    // free rects left under the skyline where a part was set over lower segments; each keeps
+    // a kerf from whatever stands above and to the right of it
+    waste: Vec<OffcutRect>,
     placements: Vec<RectPlacement>,
     config: PlanarNestConfig,
+    blocked: Vec<OffcutRect>,
 }
 
 impl SkylineState {
@@ -403,184 +786,262 @@ impl SkylineState {
         Ok(Self {
             sheet: sheet.clone(),
             index,
-            shelves: Vec::new(),
+            skyline: vec![SkylineSegment {
+                x: config.trim,
+                y: config.trim,
+                width: sheet.width - 2.0 * config.trim,
+            }],
+            waste: Vec::new(),
             placements: Vec::new(),
             config: *config,
+            blocked: blocked_rects(sheet, config.trim, config.kerf),
         })
     }
 
-    fn place(&mut self, part: &RectInstance) -> Option<()> {
-        let mut best_idx: Option<(usize, Orientation)> = None;
-        let mut best_score = f64::MAX;
+    fn right(&self) -> f64 {
+        self.sheet.width - self.config.trim
+    }
 
-        for (idx, shelf) in self.shelves.iter().enumerate() {
-            for orientation in Orientation::options_for(part) {
-                let (pw, ph) = (orientation.width, orientation.height);
-                let mut needed_width = pw;
-                if shelf.cursor_x > self.config.trim {
-                    needed_width += self.config.kerf;
-                }
-                if needed_width <= shelf.width_remaining + 1e-9 && ph <= shelf.height + 1e-9 {
-                    let score = shelf.width_remaining - needed_width;
-                    if score < best_score {
-                        best_score = score;
-                        best_idx = Some((idx, orientation));
-                    }
-                }
-            }
-        }
+    fn top(&self) -> f64 {
+        self.sheet.height - self.config.trim
+    }
 
-        if let Some((idx, orient)) = best_idx {
-            self.place_on_shelf(idx, part, orient)
-        } else {
-            self.create_shelf(part)
-        }
+    // a part of `width` at `x` takes the skyline up across itself and the kerf to its right
+    fn span_end(&self, x: f64, width: f64) -> f64 {
+        (x + width + self.config.kerf).min(self.right())
     }
 
-    fn place_on_shelf(
-        &mut self,
-        shelf_idx: usize,
-        part: &RectInstance,
-        preferred: Orientation,
-    ) -> Option<()> {
-        let shelf = &mut self.shelves[shelf_idx];
-        let mut best_orientation: Option<Orientation> = None;
-        for orientation in Orientation::options_for(part) {
-            let (pw, ph) = (orientation.width, orientation.height);
-            let mut needed_width = pw;
-            if shelf.cursor_x > self.config.trim {
-                needed_width += self.config.kerf;
+    // lowest y a part of `width` by `height` can sit at with its left side at `x`, lifted over
+    // any exclusion zone in the way, and the area it would leave under it
+    fn fit_at(&self, x: f64, width: f64, height: f64) -> Option<(f64, f64)> {
+        if x < self.config.trim - 1e-9 || x + width > self.right() + 1e-9 {
+            return None;
+        }
+        let end = self.span_end(x, width);
+        let under: Vec<&SkylineSegment> = self
+            .skyline
+            .iter()
+            .filter(|s| s.x < end - 1e-9 && s.x + s.width > x + 1e-9)
+            .collect();
+        let mut y = under.iter().map(|s| s.y).reduce(f64::max)?;
+        loop {
+            if y + height > self.top() + 1e-9 {
+                return None;
             }
-            if needed_width <= shelf.width_remaining + 1e-9 && ph <= shelf.height + 1e-9 {
-                match best_orientation {
-                    None => best_orientation = Some(orientation),
-                    Some(prev) => {
-                        let candidate_is_preferred = orientation.width == preferred.width
-                            && orientation.height == preferred.height;
-                        let prev_is_preferred =
-                            prev.width == preferred.width && prev.height == preferred.height;
-                        let prev_needed = if shelf.cursor_x > self.config.trim {
-                            prev.width + self.config.kerf
-                        } else {
-                            prev.width
-                        };
-                        let candidate_score = shelf.width_remaining - needed_width;
-                        let prev_score = shelf.width_remaining - prev_needed;
-
-                        let take_candidate = if candidate_is_preferred && !prev_is_preferred {
-                            true
-                        } else if prev_is_preferred && !candidate_is_preferred {
-                            false
-                        } else {
-                            candidate_score + 1e-9 < prev_score
-                        };
+            let rect = OffcutRect {
+                x,
+                y,
+                width,
+                height,
+            };
+            match self
+                .blocked
+                .iter()
+                .filter(|b| intersects(b, &rect))
+                .map(|b| b.y + b.height)
+                .reduce(f64::max)
+            {
+                Some(above) => y = above,
+                None => break,
+            }
+        }
+        let waste = under
+            .iter()
+            .map(|s| (y - s.y) * ((s.x + s.width).min(end) - s.x.max(x)))
+            .sum();
+        Some((y, waste))
+    }
 
-                        if take_candidate {
-                            best_orientation = Some(orientation);
+    // Tries the part at the start of every segment and past every zone, keeping the spot that
+    // leaves the least waste under it, then the lowest top, then the leftmost.
+    fn skyline_fit(&self, part: &RectInstance) -> Option<SkylineFit> {
+        let starts: Vec<f64> = self
+            .skyline
+            .iter()
+            .map(|s| s.x)
+            .chain(self.blocked.iter().map(|b| b.x + b.width))
+            .collect();
+        let mut best: Option<SkylineFit> = None;
+        for orientation in Orientation::options_for(part, &self.sheet) {
+            for &x in &starts {
+                let Some((y, waste)) = self.fit_at(x, orientation.width, orientation.height) else {
+                    continue;
+                };
+                let better = match &best {
+                    None => true,
+                    Some(b) => {
+                        let (top, best_top) = (y + orientation.height, b.y + b.orientation.height);
+                        if (waste - b.waste).abs() > 1e-9 {
+                            waste < b.waste
+                        } else if (top - best_top).abs() > 1e-9 {
+                            top < best_top
+                        } else {
+                            x < b.x - 1e-9
                         }
                     }
+                };
+                if better {
+                    best = Some(SkylineFit {
+                        x,
+                        y,
+                        orientation,
+                        waste,
+                    });
                 }
             }
         }
-        let orientation = best_orientation?;
-        let (pw, ph) = (orientation.width, orientation.height);
-        if shelf.cursor_x > self.config.trim {
-            shelf.cursor_x += self.config.kerf;
-            shelf.width_remaining -= self.config.kerf;
+        best
+    }
+
+    // the waste rect the part fills most closely, by the shorter side left over
+    fn waste_fit(&self, part: &RectInstance) -> Option<(usize, Orientation)> {
+        let mut best = None;
+        let mut best_score = f64::MAX;
+        for (idx, rect) in self.waste.iter().enumerate() {
+            for orientation in Orientation::options_for(part, &self.sheet) {
+                let (spare_w, spare_h) = (
+                    rect.width - orientation.width,
+                    rect.height - orientation.height,
+                );
+                if spare_w < -1e-9 || spare_h < -1e-9 {
+                    continue;
+                }
+                let score = spare_w.min(spare_h);
+                if score < best_score {
+                    best_score = score;
+                    best = Some((idx, orientation));
+                }
+            }
         }
-        let placement = RectPlacement {
-            part_id: part.id.clone(),
-            x: shelf.cursor_x,
-            y: shelf.y,
-            width: pw,
-            height: ph,
-            rotated: orientation.rotated,
-        };
-        self.placements.push(placement);
-        shelf.cursor_x += pw;
-        shelf.width_remaining -= pw;
-        Some(())
+        best
     }
 
-    fn create_shelf(&mut self, part: &RectInstance) -> Option<()> {
-        for orientation in Orientation::options_for(part) {
+    fn place(&mut self, part: &RectInstance) -> Option<()> {
+        let kerf = self.config.kerf;
+        if let Some((idx, orientation)) = self.waste_fit(part) {
+            let rect = self.waste.remove(idx);
             let (pw, ph) = (orientation.width, orientation.height);
-            let usable_width = self.sheet.width - 2.0 * self.config.trim;
-            let usable_height = self.sheet.height - 2.0 * self.config.trim;
-            let next_y = if let Some(last) = self.shelves.last() {
-                last.y + last.height + self.config.kerf
-            } else {
-                self.config.trim
-            };
-            if pw <= usable_width + 1e-9 && next_y + ph <= self.config.trim + usable_height + 1e-9 {
-                let mut shelf = SkylineShelf::new(next_y, ph, self.config.trim, usable_width);
-                shelf.cursor_x = self.config.trim;
-                shelf.width_remaining = usable_width;
-                self.shelves.push(shelf);
-                let idx = self.shelves.len() - 1;
-                return self.place_on_shelf(idx, part, orientation);
-            }
+            self.placements
+                .push(orientation.placement(part, rect.x, rect.y));
+            // what is left of the rect, split along the part's right side
+            let rest = [
+                OffcutRect {
+                    x: rect.x + pw + kerf,
+                    y: rect.y,
+                    width: rect.width - pw - kerf,
+                    height: rect.height,
+                },
+                OffcutRect {
+                    x: rect.x,
+                    y: rect.y + ph + kerf,
+                    width: pw,
+                    height: rect.height - ph - kerf,
+                },
+            ];
+            self.waste.extend(
+                rest.into_iter()
+                    .filter(|r| r.width > 1e-9 && r.height > 1e-9),
+            );
+            return Some(());
         }
-        None
+
+        let fit = self.skyline_fit(part)?;
+        self.raise(fit.x, fit.y, fit.orientation.width, fit.orientation.height);
+        self.placements
+            .push(fit.orientation.placement(part, fit.x, fit.y));
+        Some(())
     }
 
-    fn finalize(self) -> SheetLayout {
-        let mut metrics = UtilizationBreakdown::new(MetricKind::Area);
-        metrics.stock_total = self.sheet.width * self.sheet.height;
-        metrics.utilized = self.placements.iter().map(|p| p.width * p.height).sum();
-        metrics.kerf_loss = self
-            .shelves
-            .iter()
-            .filter(|shelf| shelf.cursor_x > self.config.trim)
-            .map(|shelf| (shelf.height * self.config.kerf).max(0.0))
-            .sum();
-        metrics.trim_loss = 2.0 * self.sheet.width * self.config.trim
-            + 2.0 * self.sheet.height * self.config.trim
-            - 4.0 * self.config.trim * self.config.trim;
-
-        let mut offcuts = Vec::new();
-        for shelf in &self.shelves {
-            if shelf.width_remaining > 1e-9 {
-                offcuts.push(OffcutRect {
-                    x: shelf.cursor_x,
-                    y: shelf.y,
-                    width: shelf.width_remaining,
-                    height: shelf.height,
+    // Lifts the skyline over a part set at (x, y), with a kerf above and to the right of it.
+    // What lay between the old skyline and the part goes to the waste map.
+    fn raise(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        let kerf = self.config.kerf;
+        let (right, top) = (self.right(), self.top());
+        let end = self.span_end(x, width);
+        let mut skyline = Vec::with_capacity(self.skyline.len() + 2);
+        let mut waste = Vec::new();
+        for s in &self.skyline {
+            let s_end = s.x + s.width;
+            if s_end <= x + 1e-9 || s.x >= end - 1e-9 {
+                skyline.push(*s);
+                continue;
+            }
+            if s.x < x {
+                skyline.push(SkylineSegment {
+                    x: s.x,
+                    y: s.y,
+                    width: x - s.x,
                 });
             }
-        }
-
-        let usable_height = self.sheet.height - 2.0 * self.config.trim;
-        let used_height = self
-            .shelves
-            .last()
-            .map(|s| (s.y + s.height) - self.config.trim)
-            .unwrap_or(0.0);
-        if usable_height - used_height > 1e-9 {
-            offcuts.push(OffcutRect {
-                x: self.config.trim,
-                y: self.config.trim + used_height,
-                width: self.sheet.width - 2.0 * self.config.trim,
-                height: (usable_height - used_height).max(0.0),
+            let (from, to) = (s.x.max(x), s_end.min(end));
+            let to = if to < right - 1e-9 { to - kerf } else { to };
+            waste.push(OffcutRect {
+                x: from,
+                y: s.y,
+                width: to - from,
+                height: y - kerf - s.y,
             });
+            if s_end > end {
+                skyline.push(SkylineSegment {
+                    x: end,
+                    y: s.y,
+                    width: s_end - end,
+                });
+            }
         }
+        let at = skyline
+            .iter()
+            .position(|s| s.x > x)
+            .unwrap_or(skyline.len());
+        skyline.insert(
+            at,
+            SkylineSegment {
+                x,
+                y: (y + height + kerf).min(top),
+                width: end - x,
+            },
+        );
+        skyline.dedup_by(|next, prev| {
+            let level = (next.y - prev.y).abs() <= 1e-9;
+            if level {
+                prev.width = next.x + next.width - prev.x;
+            }
+            level
+        });
+        self.skyline = skyline;
+        waste.retain(|r| r.width > 1e-9 && r.height > 1e-9);
+        self.waste.extend(carve_offcuts(waste, &self.blocked));
+    }
 
-        metrics.offcut_loss = offcuts.iter().map(|o| o.width * o.height).sum();
-        let occupied =
-            metrics.utilized + metrics.kerf_loss + metrics.trim_loss + metrics.offcut_loss;
-        if occupied > metrics.stock_total + 1e-6 {
-            metrics.offcut_loss = (metrics.stock_total
-                - (metrics.utilized + metrics.kerf_loss + metrics.trim_loss))
-                .max(0.0);
-        }
-
-        SheetLayout {
+    fn finalize(self) -> SheetLayout {
+        let top = self.top();
+        let mut offcuts = self.waste;
+        offcuts.extend(
+            self.skyline
+                .iter()
+                .filter(|s| top - s.y > 1e-9)
+                .map(|s| OffcutRect {
+                    x: s.x,
+                    y: s.y,
+                    width: s.width,
+                    height: top - s.y,
+                }),
+        );
+        let offcuts = carve_offcuts(offcuts, &blocked_rects(&self.sheet, self.config.trim, 0.0));
+
+        let mut layout = SheetLayout {
             stock_id: self.sheet.id.clone(),
             index: self.index,
             placements: self.placements,
             offcuts,
-            metrics,
-        }
+            metrics: UtilizationBreakdown::new(MetricKind::Area),
+            cost: self.sheet.cost,
+            cuts: Vec::new(),
+            segments: Vec::new(),
+            source: self.sheet.source.clone(),
+        };
+        measure_layout(&mut layout, &self.sheet, &self.config);
+        layout
     }
 }
 
@@ -590,53 +1051,107 @@ pub fn skyline_sheets(
     config: &PlanarNestConfig,
 ) -> NestResult<Vec<SheetLayout>> {
     validate_inputs(parts, stock)?;
+    let part_instances = skyline_order(parts, config.seed);
+    complete_layouts(pack_instances::<SkylineState>(
+        &part_instances,
+        stock,
+        config,
+        (),
+    )?)
+}
 
+fn skyline_order(parts: &[RectPart], seed: u64) -> Vec<RectInstance> {
     let mut part_instances: Vec<RectInstance> =
         parts.iter().flat_map(|part| part.instances()).collect();
     part_instances.sort_by(|a, b| {
         cmp_f64_desc(a.base_height, b.base_height).then_with(|| {
-            let ha = hash_with_seed(&a.id, config.seed ^ a.seq as u64);
-            let hb = hash_with_seed(&b.id, config.seed ^ b.seq as u64);
+            let ha = hash_with_seed(&a.id, seed ^ a.seq as u64);
+            let hb = hash_with_seed(&b.id, seed ^ b.seq as u64);
             ha.cmp(&hb)
         })
     });
+    part_instances
+}
 
-    let mut supplies: Vec<SheetSupply> = stock
-        .iter()
-        .map(|sheet| SheetSupply {
-            stock: sheet.clone(),
-            used: 0,
+/// Packs what the stock allows with the given strategy and lists every part left over with the
+/// reason it could not be placed.
+pub fn partial_sheets(
+    parts: &[RectPart],
+    stock: &[SheetStock],
+    config: &PlanarNestConfig,
+    strategy: PlanarStrategy,
+) -> NestResult<PlanarNestResult> {
+    validate_inputs(parts, stock)?;
+    let instances = strategy.order(parts, config.seed)?;
+    strategy.pack(&instances, stock, config)
+}
+
+impl PlanarStrategy {
+    // the part order the strategy packs in without any search
+    pub(crate) fn order(&self, parts: &[RectPart], seed: u64) -> NestResult<Vec<RectInstance>> {
+        Ok(match self {
+            PlanarStrategy::BestFit => best_fit_order(parts, seed),
+            PlanarStrategy::Skyline => skyline_order(parts, seed),
+            PlanarStrategy::Guillotine(guillotine) => {
+                guillotine.validate()?;
+                guillotine_order(parts, guillotine, seed)
+            }
+            PlanarStrategy::MaxRects(_) => max_rects_order(parts, seed),
         })
-        .collect();
+    }
 
-    let mut layouts: Vec<SkylineState> = Vec::new();
+    pub(crate) fn pack(
+        &self,
+        instances: &[RectInstance],
+        stock: &[SheetStock],
+        config: &PlanarNestConfig,
+    ) -> NestResult<PlanarNestResult> {
+        self.pack_onto(&[], instances, stock, config)
+    }
 
-    for part in &part_instances {
-        let mut placed = false;
-        for layout in &mut layouts {
-            if layout.place(part).is_some() {
-                placed = true;
-                break;
+    pub(crate) fn pack_onto(
+        &self,
+        opened: &[(SheetStock, usize, Vec<OffcutRect>)],
+        instances: &[RectInstance],
+        stock: &[SheetStock],
+        config: &PlanarNestConfig,
+    ) -> NestResult<PlanarNestResult> {
+        match *self {
+            PlanarStrategy::BestFit => {
+                pack_onto::<SheetState>(opened, instances, stock, config, ())
             }
-        }
-        if !placed {
-            let supply = supplies
-                .iter_mut()
-                .find(|s| s.used < s.stock.quantity)
-                .ok_or(NestError::InsufficientStock)?;
-
-            let mut state = SkylineState::new(&supply.stock, supply.used, config)?;
-            supply.used += 1;
-            if state.place(part).is_none() {
-                return Err(NestError::InsufficientStock);
+            PlanarStrategy::Skyline => {
+                pack_onto::<SkylineState>(opened, instances, stock, config, ())
+            }
+            PlanarStrategy::Guillotine(guillotine) => {
+                pack_onto::<GuillotineState>(opened, instances, stock, config, guillotine)
+            }
+            PlanarStrategy::MaxRects(heuristic) => {
+                pack_onto::<MaxRectsState>(opened, instances, stock, config, heuristic)
             }
-            layouts.push(state);
         }
     }
+}
+
+impl SheetPacker for SkylineState {
+    type Options = ();
 
-    let mut results: Vec<SheetLayout> = layouts.into_iter().map(|s| s.finalize()).collect();
-    results.sort_by(|a, b| a.stock_id.cmp(&b.stock_id).then(a.index.cmp(&b.index)));
-    Ok(results)
+    fn open(
+        stock: &SheetStock,
+        index: usize,
+        config: &PlanarNestConfig,
+        _options: (),
+    ) -> NestResult<Self> {
+        SkylineState::new(stock, index, config)
+    }
+
+    fn place(&mut self, part: &RectInstance) -> Option<()> {
+        SkylineState::place(self, part)
+    }
+
+    fn finalize(self) -> SheetLayout {
+        SkylineState::finalize(self)
+    }
 }
 
 pub fn summarize_sheet_layouts(layouts: &[SheetLayout]) -> UtilizationBreakdown {
@@ -647,11 +1162,16 @@ pub fn summarize_sheet_layouts(layouts: &[SheetLayout]) -> UtilizationBreakdown
         agg.kerf_loss += layout.metrics.kerf_loss;
         agg.trim_loss += layout.metrics.trim_loss;
         agg.offcut_loss += layout.metrics.offcut_loss;
+        agg.defect_loss += layout.metrics.defect_loss;
     }
     agg
 }
 
-fn validate_inputs(parts: &[RectPart], stock: &[SheetStock]) -> NestResult<()> {
+pub fn total_sheet_cost(layouts: &[SheetLayout]) -> f64 {
+    layouts.iter().map(|layout| layout.cost).sum()
+}
+
+pub(crate) fn validate_inputs(parts: &[RectPart], stock: &[SheetStock]) -> NestResult<()> {
     if parts
         .iter()
         .any(|p| p.width <= 0.0 || p.height <= 0.0 || p.quantity == 0)
@@ -660,6 +1180,14 @@ fn validate_inputs(parts: &[RectPart], stock: &[SheetStock]) -> NestResult<()> {
             "part dimensions must be positive",
         ));
     }
+    if parts.iter().any(|p| {
+        let (cut_width, cut_height) = p.cut_size();
+        !p.banding.is_valid() || !p.allowance.is_valid() || cut_width <= 0.0 || cut_height <= 0.0
+    }) {
+        return Err(NestError::InvalidDimension(
+            "banding and allowances must be non-negative and leave a part to cut",
+        ));
+    }
     if stock
         .iter()
         .any(|s| s.width <= 0.0 || s.height <= 0.0 || s.quantity == 0)
@@ -668,6 +1196,23 @@ fn validate_inputs(parts: &[RectPart], stock: &[SheetStock]) -> NestResult<()> {
             "stock dimensions must be positive",
         ));
     }
+    validate_exclusions(stock)
+}
+
+pub(crate) fn validate_exclusions(stock: &[SheetStock]) -> NestResult<()> {
+    if stock
+        .iter()
+        .any(|s| s.exclusions.iter().any(|zone| !zone.is_valid()))
+    {
+        return Err(NestError::InvalidDimension(
+            "exclusion zones must enclose a positive area",
+        ));
+    }
+    if stock.iter().any(zones_overlap) {
+        return Err(NestError::InvalidDimension(
+            "exclusion zones must not overlap",
+        ));
+    }
     Ok(())
 }
 
@@ -683,17 +1228,19 @@ mod tests {
             height: 1.0,
             quantity: 2,
             grain: GrainDirection::AlongX,
+            ..RectPart::default()
         }];
         let stock = vec![SheetStock {
             id: "sheet".into(),
             width: 5.0,
             height: 3.0,
             quantity: 1,
+            ..SheetStock::default()
         }];
         let config = PlanarNestConfig {
             kerf: 0.1,
-            trim: 0.0,
             seed: 7,
+            ..PlanarNestConfig::default()
         };
         let layouts = best_fit_sheets(&parts, &stock, &config).unwrap();
         let placement = &layouts[0].placements[0];
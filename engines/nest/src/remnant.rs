@@ -0,0 +1,226 @@
+use crate::exclusion::blocked_rects;
+use crate::inventory::OffcutSource;
+use crate::maxrects::{Rect, prune};
+use crate::planar::{OffcutRect, PlanarNestConfig, SheetLayout, SheetStock};
+use crate::util::cmp_f64_desc;
+
+const EPS: f64 = 1e-9;
+
+fn qualifies(rect: &Rect, min_width: f64, min_height: f64) -> bool {
+    let (short, long) = (rect.width.min(rect.height), rect.width.max(rect.height));
+    short + 1e-9 >= min_width.min(min_height) && long + 1e-9 >= min_width.max(min_height)
+}
+
+// whether guillotine cuts through the kerf gaps free every remnant from the parts and from
+// each other; the parts themselves are cut as the layout says
+fn separable(items: &[(Rect, bool)]) -> bool {
+    if items.len() <= 1 || !items.iter().any(|(_, remnant)| *remnant) {
+        return true;
+    }
+    for (rect, _) in items {
+        for (at, vertical) in [(rect.right(), true), (rect.top(), false)] {
+            let (before, after): (Vec<_>, Vec<_>) = items.iter().partition(|(r, _)| {
+                if vertical {
+                    r.right() <= at + EPS
+                } else {
+                    r.top() <= at + EPS
+                }
+            });
+            let crossed = after.iter().any(|(r, _)| {
+                if vertical {
+                    r.x < at - EPS
+                } else {
+                    r.y < at - EPS
+                }
+            });
+            if !crossed && !after.is_empty() {
+                return separable(&before) && separable(&after);
+            }
+        }
+    }
+    false
+}
+
+/// Leftover space on a laid-out sheet as rectangles that do not overlap and stand a kerf clear
+/// of the parts, the zones and each other, largest first. A remnant must take a `min_width` by
+/// `min_height` piece either way round, and guillotine cuts must free every remnant without
+/// crossing a part or another remnant.
+pub fn extract_remnants(
+    layout: &SheetLayout,
+    sheet: &SheetStock,
+    config: &PlanarNestConfig,
+    min_width: f64,
+    min_height: f64,
+) -> Vec<OffcutRect> {
+    let kerf = config.kerf;
+    let usable = Rect {
+        x: config.trim,
+        y: config.trim,
+        width: sheet.width - 2.0 * config.trim,
+        height: sheet.height - 2.0 * config.trim,
+    };
+    if usable.width <= 0.0 || usable.height <= 0.0 {
+        return Vec::new();
+    }
+    let mut obstacles: Vec<Rect> = layout
+        .placements
+        .iter()
+        .map(|p| Rect {
+            x: p.x - kerf,
+            y: p.y - kerf,
+            width: p.width + 2.0 * kerf,
+            height: p.height + 2.0 * kerf,
+        })
+        .collect();
+    obstacles.extend(
+        blocked_rects(sheet, config.trim, kerf)
+            .into_iter()
+            .map(|b| Rect {
+                x: b.x,
+                y: b.y,
+                width: b.width,
+                height: b.height,
+            }),
+    );
+
+    // maximal free rectangles, as MaxRects keeps them
+    let mut free = vec![usable];
+    let carve = |free: &[Rect], taken: &Rect| {
+        let mut next = Vec::with_capacity(free.len() + 4);
+        for rect in free {
+            if rect.intersects(taken) {
+                rect.split_around(taken, &mut next);
+            } else {
+                next.push(*rect);
+            }
+        }
+        prune(next)
+    };
+    for obstacle in &obstacles {
+        free = carve(&free, obstacle);
+    }
+
+    let mut items: Vec<(Rect, bool)> = layout
+        .placements
+        .iter()
+        .map(|p| {
+            let rect = Rect {
+                x: p.x,
+                y: p.y,
+                width: p.width,
+                height: p.height,
+            };
+            (rect, false)
+        })
+        .collect();
+    let mut remnants = Vec::new();
+    while let Some(at) = (0..free.len())
+        .filter(|&i| qualifies(&free[i], min_width, min_height))
+        .max_by(|&a, &b| cmp_f64_desc(free[b].area(), free[a].area()))
+    {
+        let best = free[at];
+        items.push((best, true));
+        if !separable(&items) {
+            // a pinwheel with what is already taken; leave the space as waste
+            items.pop();
+            free.swap_remove(at);
+            continue;
+        }
+        remnants.push(OffcutRect {
+            x: best.x,
+            y: best.y,
+            width: best.width,
+            height: best.height,
+        });
+        let clearance = Rect {
+            x: best.x - kerf,
+            y: best.y - kerf,
+            width: best.width + 2.0 * kerf,
+            height: best.height + 2.0 * kerf,
+        };
+        free = carve(&free, &clearance);
+    }
+    remnants
+}
+
+/// Remnants racked between planar runs. They come back as single sheets at no cost with the
+/// parent's grain and faces, and runs open them before any fresh sheet.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SheetInventory {
+    pub min_width: f64,
+    pub min_height: f64,
+    remnants: Vec<SheetStock>,
+}
+
+impl SheetInventory {
+    pub fn new(min_width: f64, min_height: f64) -> Self {
+        Self {
+            min_width,
+            min_height,
+            remnants: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.remnants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remnants.is_empty()
+    }
+
+    pub fn stock(&self) -> &[SheetStock] {
+        &self.remnants
+    }
+
+    pub fn stock_with(&self, fresh: &[SheetStock]) -> Vec<SheetStock> {
+        self.remnants.iter().chain(fresh).cloned().collect()
+    }
+
+    /// Drops remnants the run consumed and racks the remnants of every layout. `stock` is the
+    /// list the run was given, remnants included. Returns how many remnants were added.
+    pub fn record(
+        &mut self,
+        job: &str,
+        layouts: &[SheetLayout],
+        stock: &[SheetStock],
+        config: &PlanarNestConfig,
+    ) -> usize {
+        self.remnants.retain(|remnant| {
+            !layouts
+                .iter()
+                .any(|layout| layout.source.is_some() && layout.stock_id == remnant.id)
+        });
+
+        let before = self.remnants.len();
+        for layout in layouts {
+            let Some(sheet) = stock.iter().find(|s| s.id == layout.stock_id) else {
+                continue;
+            };
+            for rect in extract_remnants(layout, sheet, config, self.min_width, self.min_height) {
+                self.remnants.push(SheetStock {
+                    id: format!(
+                        "{job}/{}/{}@{:.1},{:.1}",
+                        layout.stock_id, layout.index, rect.x, rect.y
+                    ),
+                    width: rect.width,
+                    height: rect.height,
+                    quantity: 1,
+                    cost: 0.0,
+                    exclusions: Vec::new(),
+                    grain: sheet.grain,
+                    top_face: sheet.top_face,
+                    bottom_face: sheet.bottom_face,
+                    source: Some(OffcutSource {
+                        stock_id: layout.stock_id.clone(),
+                        board_index: layout.index,
+                        job: job.to_string(),
+                    }),
+                    material: sheet.material.clone(),
+                    thickness: sheet.thickness,
+                });
+            }
+        }
+        self.remnants.len() - before
+    }
+}
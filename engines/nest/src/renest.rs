@@ -0,0 +1,87 @@
+use crate::cutline::measure_layout;
+use crate::error::{NestError, NestResult};
+use crate::exclusion::ExclusionZone;
+use crate::planar::{
+    OffcutRect, PlanarNestConfig, PlanarNestResult, PlanarStrategy, RectPart, SheetLayout,
+    SheetStock, validate_inputs,
+};
+
+/// Nests `parts` again around the locked placements of sheets already in use. Those sheets are
+/// filled first and fresh stock opens only for what does not fit on them. Unlocked placements
+/// are dropped, so `parts` lists everything still to be cut, changed parts included. Locked
+/// placements come back first on their sheet, exactly where they were.
+pub fn renest_sheets(
+    existing: &[SheetLayout],
+    parts: &[RectPart],
+    stock: &[SheetStock],
+    config: &PlanarNestConfig,
+    strategy: PlanarStrategy,
+) -> NestResult<PlanarNestResult> {
+    validate_inputs(parts, stock)?;
+    let mut opened = Vec::with_capacity(existing.len());
+    for layout in existing {
+        let Some(sheet) = stock.iter().find(|s| s.id == layout.stock_id) else {
+            return Err(NestError::InvalidDimension(
+                "existing layouts must be on listed stock",
+            ));
+        };
+        if existing
+            .iter()
+            .filter(|other| other.stock_id == layout.stock_id && other.index == layout.index)
+            .count()
+            > 1
+        {
+            return Err(NestError::InvalidDimension(
+                "existing layouts must not share a sheet",
+            ));
+        }
+        let locked: Vec<OffcutRect> = layout
+            .placements
+            .iter()
+            .filter(|p| p.locked)
+            .map(|p| OffcutRect {
+                x: p.x,
+                y: p.y,
+                width: p.width,
+                height: p.height,
+            })
+            .collect();
+        // the packers keep clear of locked parts as they do of zones, and the guillotine saw
+        // keeps its cuts out of them as well
+        let mut occupied = sheet.clone();
+        occupied
+            .exclusions
+            .extend(locked.iter().map(|r| ExclusionZone::Rect {
+                x: r.x,
+                y: r.y,
+                width: r.width,
+                height: r.height,
+            }));
+        opened.push((occupied, layout.index, locked));
+    }
+
+    let instances = strategy.order(parts, config.seed)?;
+    let mut result = strategy.pack_onto(&opened, &instances, stock, config)?;
+    for layout in &mut result.layouts {
+        let Some(before) = existing
+            .iter()
+            .find(|e| e.stock_id == layout.stock_id && e.index == layout.index)
+        else {
+            continue;
+        };
+        let locked: Vec<_> = before
+            .placements
+            .iter()
+            .filter(|p| p.locked)
+            .cloned()
+            .collect();
+        layout.placements.splice(0..0, locked);
+        // the packer saw the locked parts as zones; measure them as parts
+        let sheet = stock
+            .iter()
+            .find(|s| s.id == layout.stock_id)
+            .expect("existing layouts checked");
+        measure_layout(layout, sheet, config);
+    }
+    Ok(result)
+}
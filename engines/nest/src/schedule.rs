@@ -0,0 +1,239 @@
+use std::fmt::Write;
+
+use crate::linear::{LinearBoard, LinearNestConfig, LinearNestResult};
+use crate::util::cmp_f64_desc;
+
+const LENGTH_TOLERANCE: f64 = 1e-6;
+
+// pusher-feeder columns, separated by one space: line, part id, length, quantity
+const FEEDER_LINE_WIDTH: usize = 5;
+const FEEDER_PART_WIDTH: usize = 20;
+const FEEDER_LENGTH_WIDTH: usize = 10;
+const FEEDER_QTY_WIDTH: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardEnd {
+    Leading,
+    Trailing,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CutAction {
+    TrimEnd { end: BoardEnd, length: f64 },
+    Flip,
+    Cut { part_id: String, length: f64 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CutStep {
+    pub stock_id: String,
+    pub board_index: usize,
+    pub action: CutAction,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StopSetup {
+    pub stop: f64,
+    pub steps: Vec<CutStep>,
+}
+
+impl StopSetup {
+    pub fn cut_count(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|step| matches!(step.action, CutAction::Cut { .. }))
+            .count()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CutSchedule {
+    pub setups: Vec<StopSetup>,
+}
+
+struct BoardProgress<'a> {
+    board: &'a LinearBoard,
+    pending: Vec<usize>,
+    facing: BoardEnd,
+    front: f64,
+    back: f64,
+}
+
+impl BoardProgress<'_> {
+    fn cut(&mut self, end: BoardEnd, kerf: f64, steps: &mut Vec<CutStep>) {
+        let board = self.board;
+        let step = |action| CutStep {
+            stock_id: board.stock_id.clone(),
+            board_index: board.index,
+            action,
+        };
+        if self.facing != end {
+            steps.push(step(CutAction::Flip));
+            self.facing = end;
+        }
+        let idx = match end {
+            BoardEnd::Leading => self.pending.remove(0),
+            BoardEnd::Trailing => self.pending.pop().expect("pending cut"),
+        };
+        let cut = &board.cuts[idx];
+        let waste = match end {
+            BoardEnd::Leading => cut.start - self.front,
+            BoardEnd::Trailing => self.back - (cut.start + cut.length),
+        };
+        if waste > LENGTH_TOLERANCE {
+            steps.push(step(CutAction::TrimEnd { end, length: waste }));
+        }
+        steps.push(step(CutAction::Cut {
+            part_id: cut.part_id.clone(),
+            length: cut.length,
+        }));
+        match end {
+            BoardEnd::Leading => self.front = cut.start + cut.length + kerf,
+            BoardEnd::Trailing => self.back = cut.start - kerf,
+        }
+    }
+
+    // the end a cut of this length can be taken from, preferring the one facing the stop
+    fn reachable(&self, stop: f64) -> Option<BoardEnd> {
+        let matches = |idx: Option<&usize>| {
+            idx.is_some_and(|&i| (self.board.cuts[i].length - stop).abs() <= LENGTH_TOLERANCE)
+        };
+        let leading = matches(self.pending.first());
+        let trailing = matches(self.pending.last());
+        match (self.facing, leading, trailing) {
+            (BoardEnd::Trailing, _, true) => Some(BoardEnd::Trailing),
+            (_, true, _) => Some(BoardEnd::Leading),
+            (_, false, true) => Some(BoardEnd::Trailing),
+            _ => None,
+        }
+    }
+}
+
+/// Orders the cuts of a nest so that every stop-block setting, longest first, takes all the
+/// pieces of that length it can reach before the block moves. Pieces buried behind another
+/// length wait for a later pass over the settings.
+pub fn cut_schedule(result: &LinearNestResult, config: &LinearNestConfig) -> CutSchedule {
+    let mut stops: Vec<f64> = result
+        .boards
+        .iter()
+        .flat_map(|board| board.cuts.iter().map(|cut| cut.length))
+        .collect();
+    stops.sort_by(|a, b| cmp_f64_desc(*a, *b));
+    stops.dedup_by(|a, b| (*a - *b).abs() <= LENGTH_TOLERANCE);
+
+    let mut boards: Vec<BoardProgress> = result
+        .boards
+        .iter()
+        .map(|board| {
+            let mut pending: Vec<usize> = (0..board.cuts.len()).collect();
+            pending.sort_by(|&a, &b| {
+                board.cuts[a]
+                    .start
+                    .partial_cmp(&board.cuts[b].start)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+            BoardProgress {
+                board,
+                pending,
+                facing: BoardEnd::Leading,
+                front: 0.0,
+                back: board.metrics.stock_total,
+            }
+        })
+        .collect();
+
+    let mut schedule = CutSchedule::default();
+    while boards.iter().any(|b| !b.pending.is_empty()) {
+        for &stop in &stops {
+            let mut steps = Vec::new();
+            for board in &mut boards {
+                while let Some(end) = board.reachable(stop) {
+                    board.cut(end, config.kerf, &mut steps);
+                }
+            }
+            if steps.is_empty() {
+                continue;
+            }
+            match schedule.setups.last_mut() {
+                Some(last) if (last.stop - stop).abs() <= LENGTH_TOLERANCE => {
+                    last.steps.extend(steps)
+                }
+                _ => schedule.setups.push(StopSetup { stop, steps }),
+            }
+        }
+    }
+    schedule
+}
+
+impl CutSchedule {
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("seq,stop,stock_id,board_index,action,end,part_id,length\n");
+        let mut seq = 0;
+        for setup in &self.setups {
+            for step in &setup.steps {
+                seq += 1;
+                let (action, end, part_id, length) = match &step.action {
+                    CutAction::TrimEnd { end, length } => ("trim", end_label(*end), "", *length),
+                    CutAction::Flip => ("flip", "", "", 0.0),
+                    CutAction::Cut { part_id, length } => ("cut", "", part_id.as_str(), *length),
+                };
+                let _ = writeln!(
+                    out,
+                    "{seq},{:.2},{},{},{action},{end},{},{length:.2}",
+                    setup.stop,
+                    csv_field(&step.stock_id),
+                    step.board_index,
+                    csv_field(part_id),
+                );
+            }
+        }
+        out
+    }
+
+    /// One line per part and stop setting: line number, part id, length and quantity in
+    /// fixed-width columns. Part ids longer than their column are truncated.
+    pub fn to_feeder_list(&self) -> String {
+        let mut out = String::new();
+        let mut line = 0;
+        for setup in &self.setups {
+            let mut parts: Vec<(&str, usize)> = Vec::new();
+            for step in &setup.steps {
+                if let CutAction::Cut { part_id, .. } = &step.action {
+                    match parts.iter_mut().find(|(id, _)| id == part_id) {
+                        Some((_, qty)) => *qty += 1,
+                        None => parts.push((part_id, 1)),
+                    }
+                }
+            }
+            for (part_id, qty) in parts {
+                line += 1;
+                let label: String = part_id.chars().take(FEEDER_PART_WIDTH).collect();
+                let _ = writeln!(
+                    out,
+                    "{line:>lw$} {label:<pw$} {stop:>sw$.2} {qty:>qw$}",
+                    stop = setup.stop,
+                    lw = FEEDER_LINE_WIDTH,
+                    pw = FEEDER_PART_WIDTH,
+                    sw = FEEDER_LENGTH_WIDTH,
+                    qw = FEEDER_QTY_WIDTH,
+                );
+            }
+        }
+        out
+    }
+}
+
+fn end_label(end: BoardEnd) -> &'static str {
+    match end {
+        BoardEnd::Leading => "leading",
+        BoardEnd::Trailing => "trailing",
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
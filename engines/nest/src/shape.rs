@@ -0,0 +1,602 @@
+use std::collections::HashMap;
+
+use crate::error::{NestError, NestResult};
+use crate::exclusion::excluded_area;
+use crate::geometry::{
+    bounds, convex_parts, disc, inner_fit, minkowski, negate, normalize_ring, perimeter, rotate,
+    segment_intersection, signed_area, strictly_inside_convex, translate,
+};
+use crate::metrics::{MetricKind, UtilizationBreakdown};
+use crate::planar::{GrainDirection, SheetStock, validate_exclusions};
+use crate::util::{cmp_f64_desc, hash_with_seed};
+
+const EPS: f64 = 1e-6;
+// sides of the polygon standing in for the router bit when spacing parts
+const TOOL_SIDES: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    pub outer: Vec<Point>,
+    pub holes: Vec<Vec<Point>>,
+}
+
+impl Polygon {
+    pub fn area(&self) -> f64 {
+        signed_area(&self.outer).abs()
+            - self
+                .holes
+                .iter()
+                .map(|hole| signed_area(hole).abs())
+                .sum::<f64>()
+    }
+
+    pub fn perimeter(&self) -> f64 {
+        perimeter(&self.outer) + self.holes.iter().map(|hole| perimeter(hole)).sum::<f64>()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapePart {
+    pub id: String,
+    pub shape: Polygon,
+    pub quantity: usize,
+    pub grain: GrainDirection,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeNestConfig {
+    pub tool_diameter: f64,
+    pub trim: f64,
+    /// degrees, counter-clockwise; parts with a grain direction only take the ones that keep
+    /// their grain on the same axis
+    pub rotations: Vec<f64>,
+    pub seed: u64,
+}
+
+impl Default for ShapeNestConfig {
+    fn default() -> Self {
+        Self {
+            tool_diameter: 0.0,
+            trim: 0.0,
+            rotations: vec![0.0, 90.0, 180.0, 270.0],
+            seed: 0,
+        }
+    }
+}
+
+/// the part is rotated about its own origin by `rotation` degrees, then moved by (x, y);
+/// `parent` is the index of the placement whose cutout it sits in
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapePlacement {
+    pub part_id: String,
+    pub rotation: f64,
+    pub x: f64,
+    pub y: f64,
+    pub parent: Option<usize>,
+}
+
+impl ShapePlacement {
+    pub fn transform(&self, point: Point) -> Point {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        Point {
+            x: point.x * cos - point.y * sin + self.x,
+            y: point.x * sin + point.y * cos + self.y,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeLayout {
+    pub stock_id: String,
+    pub index: usize,
+    pub placements: Vec<ShapePlacement>,
+    pub metrics: UtilizationBreakdown,
+    pub cost: f64,
+}
+
+impl ShapeLayout {
+    /// placement indices with every part ahead of the one it is nested in, so nothing is cut
+    /// free of the sheet before the parts inside it
+    pub fn cut_order(&self) -> Vec<usize> {
+        let depth = |mut idx: usize| {
+            let mut depth = 0;
+            while let Some(parent) = self.placements[idx].parent {
+                depth += 1;
+                idx = parent;
+            }
+            depth
+        };
+        let mut order: Vec<usize> = (0..self.placements.len()).collect();
+        order.sort_by_key(|&idx| std::cmp::Reverse(depth(idx)));
+        order
+    }
+}
+
+// one allowed rotation of a part, moved so its bounding box starts at the origin
+struct Orientation {
+    part: usize,
+    rotation: f64,
+    // translation from the rotated part's own origin to the normalised outline
+    shift: Point,
+    width: f64,
+    height: f64,
+    pieces: Vec<Vec<Point>>,
+    // the convex pieces grown by the tool, for the part already on the sheet
+    spaced: Vec<Vec<Point>>,
+    // convex pieces of the cutouts, each a free region once the part is placed
+    hollows: Vec<Vec<Point>>,
+}
+
+struct Placed {
+    orientation: usize,
+    at: Point,
+    parent: Option<usize>,
+}
+
+struct ShapeSheet {
+    stock: SheetStock,
+    index: usize,
+    placed: Vec<Placed>,
+    // convex pieces of the exclusion zones, grown by the tool
+    zones: Vec<Vec<Point>>,
+}
+
+struct Nester<'a> {
+    parts: &'a [ShapePart],
+    config: &'a ShapeNestConfig,
+    tool: Vec<Point>,
+    orientations: Vec<Orientation>,
+    by_part: Vec<Vec<usize>>,
+    // no-fit pieces of a candidate orientation around a placed one, keyed (placed, candidate)
+    nfp_cache: HashMap<(usize, usize), Vec<Vec<Point>>>,
+}
+
+impl<'a> Nester<'a> {
+    fn new(parts: &'a [ShapePart], config: &'a ShapeNestConfig) -> NestResult<Self> {
+        let tool = disc(config.tool_diameter, TOOL_SIDES);
+        let mut orientations = Vec::new();
+        let mut by_part = Vec::with_capacity(parts.len());
+        for (idx, part) in parts.iter().enumerate() {
+            let outline = normalize_ring(&part.shape.outer);
+            let mut options = Vec::new();
+            let mut angles: Vec<f64> = config
+                .rotations
+                .iter()
+                .copied()
+                .filter(|angle| {
+                    keeps_grain(part.grain, GrainDirection::AlongX, *angle)
+                        || keeps_grain(part.grain, GrainDirection::AlongY, *angle)
+                })
+                .collect();
+            angles.dedup_by(|a, b| (*a - *b).abs() < EPS);
+            for rotation in angles {
+                let rotated = rotate(&outline, rotation);
+                let (x0, y0, x1, y1) = bounds(&rotated);
+                let ring = translate(&rotated, -x0, -y0);
+                let pieces = convex_parts(&ring);
+                let hollows = part
+                    .shape
+                    .holes
+                    .iter()
+                    .map(|hole| normalize_ring(hole))
+                    .filter(|hole| hole.len() >= 3)
+                    .flat_map(|hole| convex_parts(&translate(&rotate(&hole, rotation), -x0, -y0)))
+                    .collect();
+                let spaced = if config.tool_diameter > 0.0 {
+                    pieces.iter().map(|piece| minkowski(piece, &tool)).collect()
+                } else {
+                    pieces.clone()
+                };
+                options.push(orientations.len());
+                orientations.push(Orientation {
+                    part: idx,
+                    rotation,
+                    shift: Point { x: -x0, y: -y0 },
+                    width: x1 - x0,
+                    height: y1 - y0,
+                    pieces,
+                    spaced,
+                    hollows,
+                });
+            }
+            if options.is_empty() {
+                return Err(NestError::InvalidDimension(
+                    "no allowed rotation keeps the part's grain",
+                ));
+            }
+            by_part.push(options);
+        }
+        Ok(Self {
+            parts,
+            config,
+            tool,
+            orientations,
+            by_part,
+            nfp_cache: HashMap::new(),
+        })
+    }
+
+    fn open(&self, stock: &SheetStock, index: usize) -> ShapeSheet {
+        let zones = stock
+            .exclusions
+            .iter()
+            .map(|zone| normalize_ring(&zone.outline()))
+            .filter(|ring| ring.len() >= 3)
+            .flat_map(|ring| convex_parts(&ring))
+            .map(|piece| {
+                if self.config.tool_diameter > 0.0 {
+                    minkowski(&piece, &self.tool)
+                } else {
+                    piece
+                }
+            })
+            .collect();
+        ShapeSheet {
+            stock: stock.clone(),
+            index,
+            placed: Vec::new(),
+            zones,
+        }
+    }
+
+    fn no_fit(&mut self, placed: usize, candidate: usize) -> &[Vec<Point>] {
+        let orientations = &self.orientations;
+        self.nfp_cache
+            .entry((placed, candidate))
+            .or_insert_with(|| {
+                let mut pieces = Vec::new();
+                for a in &orientations[placed].spaced {
+                    for b in &orientations[candidate].pieces {
+                        pieces.push(minkowski(a, &negate(b)));
+                    }
+                }
+                pieces
+            })
+    }
+
+    // no-fit pieces around every part on the sheet, tagged with the placement they belong to,
+    // and around the exclusion zones, tagged with no placement at all
+    fn obstacles(&mut self, sheet: &ShapeSheet, candidate: usize) -> Vec<(usize, Vec<Point>)> {
+        let mut obstacles = Vec::new();
+        for zone in &sheet.zones {
+            for piece in &self.orientations[candidate].pieces {
+                obstacles.push((usize::MAX, minkowski(zone, &negate(piece))));
+            }
+        }
+        for (idx, placed) in sheet.placed.iter().enumerate() {
+            let at = placed.at;
+            let pieces = self.no_fit(placed.orientation, candidate);
+            obstacles.extend(
+                pieces
+                    .iter()
+                    .map(|piece| (idx, translate(piece, at.x, at.y))),
+            );
+        }
+        obstacles
+    }
+
+    // Bottom-left position inside the convex `frame` of allowed positions: the lowest, then the
+    // leftmost, among the frame's corners and the vertices and crossings of the no-fit pieces.
+    fn search(frame: &[Point], obstacles: &[&Vec<Point>]) -> Option<Point> {
+        let edges = |ring: &[Point]| {
+            let n = ring.len();
+            (0..n)
+                .map(|i| (ring[i], ring[(i + 1) % n]))
+                .collect::<Vec<_>>()
+        };
+        let mut candidates: Vec<Point> = frame.to_vec();
+        let frame_edges = edges(frame);
+        let obstacle_edges: Vec<Vec<(Point, Point)>> =
+            obstacles.iter().map(|piece| edges(piece)).collect();
+        let boxes: Vec<(f64, f64, f64, f64)> =
+            obstacles.iter().map(|piece| bounds(piece)).collect();
+        for (i, piece_edges) in obstacle_edges.iter().enumerate() {
+            candidates.extend(piece_edges.iter().map(|(a, _)| *a));
+            for (a, b) in piece_edges {
+                for (c, d) in &frame_edges {
+                    candidates.extend(segment_intersection(*a, *b, *c, *d));
+                }
+            }
+            for (j, other_edges) in obstacle_edges.iter().enumerate().skip(i + 1) {
+                let (bi, bj) = (boxes[i], boxes[j]);
+                if bi.2 < bj.0 || bj.2 < bi.0 || bi.3 < bj.1 || bj.3 < bi.1 {
+                    continue;
+                }
+                for (a, b) in piece_edges {
+                    for (c, d) in other_edges {
+                        candidates.extend(segment_intersection(*a, *b, *c, *d));
+                    }
+                }
+            }
+        }
+
+        let (x0, y0, x1, y1) = bounds(frame);
+        candidates.retain(|p| {
+            p.x >= x0 - EPS
+                && p.x <= x1 + EPS
+                && p.y >= y0 - EPS
+                && p.y <= y1 + EPS
+                && (frame.len() < 3 || strictly_inside_convex(frame, *p, -EPS))
+        });
+        candidates.sort_by(|a, b| {
+            a.y.partial_cmp(&b.y)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal))
+        });
+        candidates
+            .into_iter()
+            .find(|p| {
+                obstacles.iter().zip(&boxes).all(|(piece, b)| {
+                    p.x <= b.0
+                        || p.x >= b.2
+                        || p.y <= b.1
+                        || p.y >= b.3
+                        || !strictly_inside_convex(piece, *p, EPS)
+                })
+            })
+            .map(|p| Point {
+                x: p.x.clamp(x0, x1),
+                y: p.y.clamp(y0, y1),
+            })
+    }
+
+    fn position(&mut self, sheet: &ShapeSheet, candidate: usize) -> Option<Point> {
+        let trim = self.config.trim;
+        let (w, h) = (
+            self.orientations[candidate].width,
+            self.orientations[candidate].height,
+        );
+        let (x0, y0) = (trim, trim);
+        let (x1, y1) = (sheet.stock.width - trim - w, sheet.stock.height - trim - h);
+        if x1 < x0 - EPS || y1 < y0 - EPS {
+            return None;
+        }
+        let (x1, y1) = (x1.max(x0), y1.max(y0));
+        let frame = [
+            Point { x: x0, y: y0 },
+            Point { x: x1, y: y0 },
+            Point { x: x1, y: y1 },
+            Point { x: x0, y: y1 },
+        ];
+        let obstacles = self.obstacles(sheet, candidate);
+        let pieces: Vec<&Vec<Point>> = obstacles.iter().map(|(_, piece)| piece).collect();
+        Self::search(&frame, &pieces)
+    }
+
+    // Bottom-left position inside a cutout of a placed part, keeping the tool's width clear of
+    // the cutout's edge. A part has to fit one convex piece of the cutout.
+    fn position_in_hollow(
+        &mut self,
+        sheet: &ShapeSheet,
+        host: usize,
+        hollow: usize,
+        candidate: usize,
+    ) -> Option<Point> {
+        let placed = &sheet.placed[host];
+        let region = translate(
+            &self.orientations[placed.orientation].hollows[hollow],
+            placed.at.x,
+            placed.at.y,
+        );
+        let reach: Vec<Point> = self.orientations[candidate]
+            .spaced
+            .iter()
+            .flatten()
+            .copied()
+            .collect();
+        let frame = inner_fit(&region, &reach);
+        if frame.is_empty() {
+            return None;
+        }
+        let obstacles = self.obstacles(sheet, candidate);
+        let pieces: Vec<&Vec<Point>> = obstacles
+            .iter()
+            .filter(|(owner, _)| *owner != host)
+            .map(|(_, piece)| piece)
+            .collect();
+        Self::search(&frame, &pieces)
+    }
+
+    // Cutouts of parts already on the sheet are tried first, in placement order; otherwise the
+    // best orientation on the open sheet by the same bottom-left rule.
+    fn place(&mut self, sheet: &mut ShapeSheet, part: usize) -> bool {
+        for host in 0..sheet.placed.len() {
+            let hollows = self.orientations[sheet.placed[host].orientation]
+                .hollows
+                .len();
+            for hollow in 0..hollows {
+                let best = self.best_orientation(part, sheet.stock.grain, |nester, candidate| {
+                    nester.position_in_hollow(sheet, host, hollow, candidate)
+                });
+                if let Some((orientation, at)) = best {
+                    sheet.placed.push(Placed {
+                        orientation,
+                        at,
+                        parent: Some(host),
+                    });
+                    return true;
+                }
+            }
+        }
+        let best = self.best_orientation(part, sheet.stock.grain, |nester, candidate| {
+            nester.position(sheet, candidate)
+        });
+        let Some((orientation, at)) = best else {
+            return false;
+        };
+        sheet.placed.push(Placed {
+            orientation,
+            at,
+            parent: None,
+        });
+        true
+    }
+
+    // lowest top edge, then leftmost, among the orientations that suit the sheet's grain
+    fn best_orientation(
+        &mut self,
+        part: usize,
+        sheet_grain: GrainDirection,
+        mut position: impl FnMut(&mut Self, usize) -> Option<Point>,
+    ) -> Option<(usize, Point)> {
+        let mut best: Option<(usize, Point)> = None;
+        let grain = self.parts[part].grain;
+        for candidate in self.by_part[part].clone() {
+            if !keeps_grain(grain, sheet_grain, self.orientations[candidate].rotation) {
+                continue;
+            }
+            let Some(at) = position(self, candidate) else {
+                continue;
+            };
+            let top = at.y + self.orientations[candidate].height;
+            let better = best.is_none_or(|(current, p)| {
+                let current_top = p.y + self.orientations[current].height;
+                top < current_top - EPS || (top - current_top).abs() <= EPS && at.x < p.x - EPS
+            });
+            if better {
+                best = Some((candidate, at));
+            }
+        }
+        best
+    }
+
+    fn finalize(&self, sheet: ShapeSheet) -> ShapeLayout {
+        let trim = self.config.trim;
+        let (width, height) = (sheet.stock.width, sheet.stock.height);
+        let mut metrics = UtilizationBreakdown::new(MetricKind::Area);
+        metrics.stock_total = width * height;
+        metrics.trim_loss = (2.0 * (width + height) * trim - 4.0 * trim * trim).max(0.0);
+
+        let mut placements = Vec::with_capacity(sheet.placed.len());
+        for placed in &sheet.placed {
+            let orientation = &self.orientations[placed.orientation];
+            let part = &self.parts[orientation.part];
+            metrics.utilized += part.shape.area();
+            // the router groove runs round every outline
+            metrics.kerf_loss += part.shape.perimeter() * self.config.tool_diameter;
+            placements.push(ShapePlacement {
+                part_id: part.id.clone(),
+                rotation: orientation.rotation,
+                x: placed.at.x + orientation.shift.x,
+                y: placed.at.y + orientation.shift.y,
+                parent: placed.parent,
+            });
+        }
+        metrics.defect_loss = excluded_area(&sheet.stock, trim);
+        let occupied =
+            metrics.utilized + metrics.kerf_loss + metrics.trim_loss + metrics.defect_loss;
+        metrics.offcut_loss = (metrics.stock_total - occupied).max(0.0);
+
+        ShapeLayout {
+            stock_id: sheet.stock.id,
+            index: sheet.index,
+            placements,
+            metrics,
+            cost: sheet.stock.cost,
+        }
+    }
+}
+
+// Grain-bound parts turn in quarter revolutions: an odd number of them when the part's grain
+// crosses the sheet's, an even number when they agree or the sheet has no declared grain.
+fn keeps_grain(grain: GrainDirection, sheet_grain: GrainDirection, angle: f64) -> bool {
+    if grain == GrainDirection::Either {
+        return true;
+    }
+    let quarter_turns = angle / 90.0;
+    if (quarter_turns - quarter_turns.round()).abs() >= 1e-9 {
+        return false;
+    }
+    let odd = (quarter_turns.round() as i64).rem_euclid(2) == 1;
+    match sheet_grain {
+        GrainDirection::Either => !odd,
+        sheet_grain => odd == (grain != sheet_grain),
+    }
+}
+
+/// True-shape nesting: largest parts first, each into a cutout of a part already placed when
+/// one takes it, else at the bottom-left-most position its no-fit polygons allow on the first
+/// open sheet that takes it, else on a fresh sheet of the first stock with sheets left.
+pub fn shape_sheets(
+    parts: &[ShapePart],
+    stock: &[SheetStock],
+    config: &ShapeNestConfig,
+) -> NestResult<Vec<ShapeLayout>> {
+    validate_inputs(parts, stock, config)?;
+    let mut nester = Nester::new(parts, config)?;
+
+    let mut order: Vec<(usize, usize)> = parts
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, part)| (0..part.quantity).map(move |seq| (idx, seq)))
+        .collect();
+    order.sort_by(|&(a, sa), &(b, sb)| {
+        cmp_f64_desc(parts[a].shape.area(), parts[b].shape.area()).then_with(|| {
+            let ha = hash_with_seed(&parts[a].id, config.seed ^ sa as u64);
+            let hb = hash_with_seed(&parts[b].id, config.seed ^ sb as u64);
+            ha.cmp(&hb)
+        })
+    });
+
+    let mut used = vec![0usize; stock.len()];
+    let mut sheets: Vec<ShapeSheet> = Vec::new();
+    for (part, _) in order {
+        if sheets.iter_mut().any(|sheet| nester.place(sheet, part)) {
+            continue;
+        }
+        let mut opened = false;
+        for (idx, sheet_stock) in stock.iter().enumerate() {
+            if used[idx] >= sheet_stock.quantity {
+                continue;
+            }
+            let mut sheet = nester.open(sheet_stock, used[idx]);
+            if nester.place(&mut sheet, part) {
+                used[idx] += 1;
+                sheets.push(sheet);
+                opened = true;
+                break;
+            }
+        }
+        if !opened {
+            return Err(NestError::InsufficientStock);
+        }
+    }
+
+    let mut layouts: Vec<ShapeLayout> = sheets.into_iter().map(|s| nester.finalize(s)).collect();
+    layouts.sort_by(|a, b| a.stock_id.cmp(&b.stock_id).then(a.index.cmp(&b.index)));
+    Ok(layouts)
+}
+
+fn validate_inputs(
+    parts: &[ShapePart],
+    stock: &[SheetStock],
+    config: &ShapeNestConfig,
+) -> NestResult<()> {
+    if parts.iter().any(|p| {
+        p.quantity == 0 || normalize_ring(&p.shape.outer).len() < 3 || p.shape.area() <= 0.0
+    }) {
+        return Err(NestError::InvalidDimension(
+            "part outlines must enclose a positive area",
+        ));
+    }
+    if stock
+        .iter()
+        .any(|s| s.width <= 2.0 * config.trim || s.height <= 2.0 * config.trim || s.quantity == 0)
+    {
+        return Err(NestError::InvalidDimension(
+            "sheet dimensions smaller than trim allowance",
+        ));
+    }
+    if config.tool_diameter < 0.0 {
+        return Err(NestError::InvalidDimension(
+            "tool diameter must not be negative",
+        ));
+    }
+    validate_exclusions(stock)
+}
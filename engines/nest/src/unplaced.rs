@@ -0,0 +1,46 @@
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnplacedReason {
+    LargerThanAnyStock,
+    StockExhausted,
+    NoSuitableFace,
+    NoMatchingStock,
+}
+
+impl Display for UnplacedReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnplacedReason::LargerThanAnyStock => write!(f, "larger than any stock"),
+            UnplacedReason::StockExhausted => write!(f, "stock exhausted"),
+            UnplacedReason::NoSuitableFace => write!(f, "no sheet has the face grade it needs"),
+            UnplacedReason::NoMatchingStock => write!(f, "no stock of its material and thickness"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnplacedPart {
+    pub part_id: String,
+    pub quantity: usize,
+    pub reason: UnplacedReason,
+}
+
+// one entry per part id and reason, in the order the parts were given up on
+pub(crate) fn record_unplaced(
+    unplaced: &mut Vec<UnplacedPart>,
+    part_id: &str,
+    reason: UnplacedReason,
+) {
+    match unplaced
+        .iter_mut()
+        .find(|u| u.part_id == part_id && u.reason == reason)
+    {
+        Some(entry) => entry.quantity += 1,
+        None => unplaced.push(UnplacedPart {
+            part_id: part_id.to_string(),
+            quantity: 1,
+            reason,
+        }),
+    }
+}
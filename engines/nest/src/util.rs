@@ -10,3 +10,28 @@ pub fn hash_with_seed<T: Hash>(value: &T, seed: u64) -> u64 {
 pub fn cmp_f64_desc(lhs: f64, rhs: f64) -> std::cmp::Ordering {
     rhs.partial_cmp(&lhs).unwrap_or(std::cmp::Ordering::Equal)
 }
+
+/// small deterministic generator for seeded searches
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
@@ -0,0 +1,729 @@
+use std::fmt::{self, Display};
+
+use crate::exclusion::ExclusionZone;
+use crate::guillotine::{CutAxis, GuillotineCut};
+use crate::linear::{LinearBoard, LinearNestConfig, LinearPart, LinearStock};
+use crate::metrics::UtilizationBreakdown;
+use crate::planar::{GrainDirection, PlanarNestConfig, RectPart, SheetLayout, SheetStock};
+use crate::shape::{Point, Polygon, ShapeLayout, ShapeNestConfig, ShapePart};
+
+const EPS: f64 = 1e-6;
+
+/// What is wrong on one layout or board, naming placements (cuts, on a board) by their
+/// position in it. Segments and guillotine cuts are named by their position in `segments` and
+/// `cuts`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayoutViolation {
+    NoSuchPlacement(usize),
+    Locked(usize),
+    OutOfBounds(usize),
+    /// in an exclusion zone or a defect, or within a kerf of one
+    InExclusionZone(usize),
+    Overlap(usize, usize),
+    /// apart, but by less than a kerf
+    KerfSpacing(usize, usize),
+    AgainstGrain(usize),
+    /// shows a face worse than its part allows
+    WrongFace(usize),
+    /// not the cut size of its part
+    WrongSize(usize),
+    /// a pass of the saw or router through a part
+    SegmentThroughPart(usize),
+    /// a pass that is not a guillotine cut into an exclusion zone or defect
+    SegmentInExclusionZone(usize),
+    /// a guillotine cut that stops short of, or runs past, the edges of the piece it splits
+    CutNotEdgeToEdge(usize),
+    MetricsDoNotReconcile,
+}
+
+impl Display for LayoutViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutViolation::NoSuchPlacement(idx) => write!(f, "no placement {idx}"),
+            LayoutViolation::Locked(idx) => write!(f, "placement {idx} is locked"),
+            LayoutViolation::OutOfBounds(idx) => {
+                write!(f, "placement {idx} leaves the trimmed stock")
+            }
+            LayoutViolation::InExclusionZone(idx) => {
+                write!(f, "placement {idx} enters an exclusion zone or defect")
+            }
+            LayoutViolation::Overlap(a, b) => write!(f, "placements {a} and {b} overlap"),
+            LayoutViolation::KerfSpacing(a, b) => {
+                write!(f, "placements {a} and {b} are closer than a kerf")
+            }
+            LayoutViolation::AgainstGrain(idx) => {
+                write!(f, "placement {idx} runs against the grain")
+            }
+            LayoutViolation::WrongFace(idx) => {
+                write!(f, "placement {idx} shows a face below its grade")
+            }
+            LayoutViolation::WrongSize(idx) => {
+                write!(f, "placement {idx} is not the cut size of its part")
+            }
+            LayoutViolation::SegmentThroughPart(idx) => {
+                write!(f, "segment {idx} runs through a part")
+            }
+            LayoutViolation::SegmentInExclusionZone(idx) => {
+                write!(f, "segment {idx} enters an exclusion zone or defect")
+            }
+            LayoutViolation::CutNotEdgeToEdge(idx) => {
+                write!(f, "guillotine cut {idx} does not run edge to edge")
+            }
+            LayoutViolation::MetricsDoNotReconcile => {
+                write!(f, "metrics do not add up to the stock total")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// on the layout or board at this position in the list verified
+    On(usize, LayoutViolation),
+    UnknownPart(String),
+    UnknownStock(String),
+    QuantityMismatch {
+        part_id: String,
+        requested: usize,
+        placed: usize,
+    },
+    StockOverused {
+        stock_id: String,
+        available: usize,
+        used: usize,
+    },
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::On(idx, violation) => write!(f, "layout {idx}: {violation}"),
+            Violation::UnknownPart(id) => write!(f, "part `{id}` was not requested"),
+            Violation::UnknownStock(id) => write!(f, "stock `{id}` is not listed"),
+            Violation::QuantityMismatch {
+                part_id,
+                requested,
+                placed,
+            } => write!(
+                f,
+                "part `{part_id}`: {placed} placed, {requested} requested"
+            ),
+            Violation::StockOverused {
+                stock_id,
+                available,
+                used,
+            } => write!(f, "stock `{stock_id}`: {used} used, {available} available"),
+        }
+    }
+}
+
+fn grain_holds(part: GrainDirection, sheet: GrainDirection, rotated: bool) -> bool {
+    match (part, sheet) {
+        (GrainDirection::Either, _) => true,
+        (_, GrainDirection::Either) => !rotated,
+        (part, sheet) => rotated == (part != sheet),
+    }
+}
+
+// gap between two spans on one axis; negative when they overlap
+fn gap(a: f64, a_len: f64, b: f64, b_len: f64) -> f64 {
+    (b - (a + a_len)).max(a - (b + b_len))
+}
+
+// The geometry below is the verifier's own, so a bug in the solvers' spacing cannot hide in
+// the check. Rings are closed implicitly, either way round.
+
+fn rect_ring(x: f64, y: f64, width: f64, height: f64) -> Vec<Point> {
+    vec![
+        Point { x, y },
+        Point { x: x + width, y },
+        Point {
+            x: x + width,
+            y: y + height,
+        },
+        Point { x, y: y + height },
+    ]
+}
+
+fn zone_ring(zone: &ExclusionZone) -> Vec<Point> {
+    match zone {
+        ExclusionZone::Rect {
+            x,
+            y,
+            width,
+            height,
+        } => rect_ring(*x, *y, *width, *height),
+        ExclusionZone::Polygon(points) => points.clone(),
+    }
+}
+
+fn ring_edges(ring: &[Point]) -> impl Iterator<Item = (Point, Point)> + '_ {
+    (0..ring.len()).map(move |i| (ring[i], ring[(i + 1) % ring.len()]))
+}
+
+fn orient(a: Point, b: Point, c: Point) -> f64 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+// the segments cross at a point inside both
+fn crosses(a0: Point, a1: Point, b0: Point, b1: Point) -> bool {
+    let opposite = |p: f64, q: f64| p > EPS && q < -EPS || p < -EPS && q > EPS;
+    opposite(orient(b0, b1, a0), orient(b0, b1, a1))
+        && opposite(orient(a0, a1, b0), orient(a0, a1, b1))
+}
+
+fn point_to_segment(p: Point, a: Point, b: Point) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 > 0.0 {
+        (((p.x - a.x) * dx + (p.y - a.y) * dy) / len2).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (p.x - a.x - t * dx).hypot(p.y - a.y - t * dy)
+}
+
+fn ring_distance(a: &[Point], b: &[Point]) -> f64 {
+    let mut nearest = f64::INFINITY;
+    for (a0, a1) in ring_edges(a) {
+        for (b0, b1) in ring_edges(b) {
+            if crosses(a0, a1, b0, b1) {
+                return 0.0;
+            }
+            nearest = nearest
+                .min(point_to_segment(a0, b0, b1))
+                .min(point_to_segment(a1, b0, b1))
+                .min(point_to_segment(b0, a0, a1))
+                .min(point_to_segment(b1, a0, a1));
+        }
+    }
+    nearest
+}
+
+// even-odd rule; points on the boundary may go either way
+fn inside(p: Point, ring: &[Point]) -> bool {
+    let mut odd = false;
+    for (a, b) in ring_edges(ring) {
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            odd = !odd;
+        }
+    }
+    odd
+}
+
+fn strictly_inside(p: Point, ring: &[Point]) -> bool {
+    inside(p, ring) && ring_edges(ring).all(|(a, b)| point_to_segment(p, a, b) > EPS)
+}
+
+// corners, edge midpoints and the vertex mean: enough to find a shared interior when no edges
+// cross, even for outlines that coincide
+fn probes(ring: &[Point]) -> impl Iterator<Item = Point> + '_ {
+    let n = ring.len() as f64;
+    let mean = Point {
+        x: ring.iter().map(|p| p.x).sum::<f64>() / n,
+        y: ring.iter().map(|p| p.y).sum::<f64>() / n,
+    };
+    ring_edges(ring)
+        .flat_map(|(a, b)| {
+            let mid = Point {
+                x: (a.x + b.x) / 2.0,
+                y: (a.y + b.y) / 2.0,
+            };
+            [a, mid]
+        })
+        .chain(std::iter::once(mean))
+}
+
+// distance between two outlines, or `None` where their insides overlap
+fn clearance(a: &[Point], b: &[Point]) -> Option<f64> {
+    let overlap = ring_edges(a)
+        .any(|(a0, a1)| ring_edges(b).any(|(b0, b1)| crosses(a0, a1, b0, b1)))
+        || probes(a).any(|p| strictly_inside(p, b))
+        || probes(b).any(|p| strictly_inside(p, a));
+    (!overlap).then(|| ring_distance(a, b))
+}
+
+// distance from an outline to the ring around it, or `None` where it is not inside the ring
+fn clearance_within(inner: &[Point], ring: &[Point]) -> Option<f64> {
+    let within = ring_edges(inner)
+        .all(|(a0, a1)| ring_edges(ring).all(|(b0, b1)| !crosses(a0, a1, b0, b1)))
+        && probes(inner).all(|p| {
+            inside(p, ring) || ring_edges(ring).any(|(a, b)| point_to_segment(p, a, b) <= EPS)
+        });
+    within.then(|| ring_distance(inner, ring))
+}
+
+// the part's size on the saw: less the banding glued on afterwards, plus the allowance milled off
+fn cut_size(part: &RectPart) -> (f64, f64) {
+    let (band, extra) = (&part.banding, &part.allowance);
+    (
+        part.width - band.left - band.right + extra.left + extra.right,
+        part.height - band.bottom - band.top + extra.bottom + extra.top,
+    )
+}
+
+// Each end of a guillotine cut lands on the trimmed edge or on the near side of an earlier
+// cut across it, and no earlier cut across it lies in between.
+fn edge_to_edge(idx: usize, cuts: &[GuillotineCut], sheet: &SheetStock, trim: f64) -> bool {
+    let cut = &cuts[idx];
+    let far = match cut.axis {
+        CutAxis::AlongX => sheet.width - trim,
+        CutAxis::AlongY => sheet.height - trim,
+    };
+    let mut across = cuts.iter().filter(|other| {
+        other.stage < cut.stage
+            && other.axis != cut.axis
+            && other.start <= cut.position + EPS
+            && other.end >= cut.position + cut.kerf - EPS
+    });
+    let (mut start, mut end) = (
+        (cut.start - trim).abs() <= EPS,
+        (cut.end - far).abs() <= EPS,
+    );
+    across.all(|other| {
+        start |= (other.position + other.kerf - cut.start).abs() <= EPS;
+        end |= (other.position - cut.end).abs() <= EPS;
+        other.position + other.kerf <= cut.start + EPS || other.position >= cut.end - EPS
+    }) && start
+        && end
+}
+
+fn reconciles(metrics: &UtilizationBreakdown) -> bool {
+    let sum = metrics.utilized
+        + metrics.kerf_loss
+        + metrics.trim_loss
+        + metrics.offcut_loss
+        + metrics.defect_loss;
+    (sum - metrics.stock_total).abs() <= 1e-9 * metrics.stock_total.max(1.0)
+}
+
+// Checks the placements at `checked` against the sheet and every other placement. Pairs of
+// checked placements are reported once.
+pub(crate) fn check_placements(
+    layout: &SheetLayout,
+    checked: &[usize],
+    sheet: &SheetStock,
+    parts: &[RectPart],
+    config: &PlanarNestConfig,
+) -> Vec<LayoutViolation> {
+    let (trim, kerf) = (config.trim, config.kerf);
+    let zones: Vec<Vec<Point>> = sheet.exclusions.iter().map(zone_ring).collect();
+    let mut is_checked = vec![false; layout.placements.len()];
+    for &idx in checked {
+        is_checked[idx] = true;
+    }
+    let mut violations = Vec::new();
+    for &idx in checked {
+        let p = &layout.placements[idx];
+        if p.x < trim - EPS
+            || p.y < trim - EPS
+            || p.x + p.width > sheet.width - trim + EPS
+            || p.y + p.height > sheet.height - trim + EPS
+        {
+            violations.push(LayoutViolation::OutOfBounds(idx));
+        }
+        let ring = rect_ring(p.x, p.y, p.width, p.height);
+        if zones
+            .iter()
+            .any(|zone| clearance(&ring, zone).is_none_or(|d| d < kerf - EPS))
+        {
+            violations.push(LayoutViolation::InExclusionZone(idx));
+        }
+        for (other, q) in layout.placements.iter().enumerate() {
+            if other == idx || other < idx && is_checked[other] {
+                continue;
+            }
+            let dx = gap(p.x, p.width, q.x, q.width);
+            let dy = gap(p.y, p.height, q.y, q.height);
+            let (first, second) = (idx.min(other), idx.max(other));
+            if dx < -EPS && dy < -EPS {
+                violations.push(LayoutViolation::Overlap(first, second));
+            } else if dx < kerf - EPS && dy < kerf - EPS {
+                violations.push(LayoutViolation::KerfSpacing(first, second));
+            }
+        }
+        let Some(part) = parts.iter().find(|part| part.id == p.part_id) else {
+            continue;
+        };
+        if !grain_holds(part.grain, sheet.grain, p.rotated) {
+            violations.push(LayoutViolation::AgainstGrain(idx));
+        }
+        let shown = if p.flipped {
+            sheet.bottom_face
+        } else {
+            sheet.top_face
+        };
+        if shown > part.face {
+            violations.push(LayoutViolation::WrongFace(idx));
+        }
+        let (cut_width, cut_height) = cut_size(part);
+        let turn = |w: f64, h: f64| if p.rotated { (h, w) } else { (w, h) };
+        let (width, height) = turn(cut_width, cut_height);
+        let (finished_width, finished_height) = turn(part.width, part.height);
+        if (p.width - width).abs() > EPS
+            || (p.height - height).abs() > EPS
+            || (p.finished_width - finished_width).abs() > EPS
+            || (p.finished_height - finished_height).abs() > EPS
+        {
+            violations.push(LayoutViolation::WrongSize(idx));
+        }
+    }
+    violations
+}
+
+// Every pass keeps out of the parts; passes other than guillotine cuts keep out of the zones
+// too, while guillotine cuts run edge to edge of the piece they split.
+fn check_cuts(layout: &SheetLayout, sheet: &SheetStock, trim: f64) -> Vec<LayoutViolation> {
+    let zones: Vec<Vec<Point>> = sheet.exclusions.iter().map(zone_ring).collect();
+    let mut violations = Vec::new();
+    for (idx, segment) in layout.segments.iter().enumerate() {
+        let (x, y, width, height) = match segment.axis {
+            CutAxis::AlongX => (
+                segment.start,
+                segment.position,
+                segment.end - segment.start,
+                segment.kerf,
+            ),
+            CutAxis::AlongY => (
+                segment.position,
+                segment.start,
+                segment.kerf,
+                segment.end - segment.start,
+            ),
+        };
+        if layout
+            .placements
+            .iter()
+            .any(|p| gap(x, width, p.x, p.width) < -EPS && gap(y, height, p.y, p.height) < -EPS)
+        {
+            violations.push(LayoutViolation::SegmentThroughPart(idx));
+        }
+        let guillotine = layout.cuts.iter().any(|cut| {
+            cut.axis == segment.axis
+                && (cut.position - segment.position).abs() <= EPS
+                && cut.start <= segment.start + EPS
+                && cut.end >= segment.end - EPS
+        });
+        let strip = rect_ring(x, y, width, height);
+        if !guillotine && zones.iter().any(|zone| clearance(&strip, zone).is_none()) {
+            violations.push(LayoutViolation::SegmentInExclusionZone(idx));
+        }
+    }
+    for idx in 0..layout.cuts.len() {
+        if !edge_to_edge(idx, &layout.cuts, sheet, trim) {
+            violations.push(LayoutViolation::CutNotEdgeToEdge(idx));
+        }
+    }
+    violations
+}
+
+fn count_parts<'a>(
+    placed: impl Iterator<Item = &'a str>,
+    requested: impl Iterator<Item = (&'a str, usize)>,
+    violations: &mut Vec<Violation>,
+) {
+    // a part listed twice is requested in both amounts together
+    let mut counts: Vec<(&str, usize, usize)> = Vec::new();
+    for (id, quantity) in requested {
+        match counts.iter_mut().find(|(part, _, _)| *part == id) {
+            Some(entry) => entry.1 += quantity,
+            None => counts.push((id, quantity, 0)),
+        }
+    }
+    let mut unknown: Vec<&str> = Vec::new();
+    for id in placed {
+        match counts.iter_mut().find(|(part, _, _)| *part == id) {
+            Some(entry) => entry.2 += 1,
+            None if !unknown.contains(&id) => unknown.push(id),
+            None => {}
+        }
+    }
+    violations.extend(
+        unknown
+            .into_iter()
+            .map(|id| Violation::UnknownPart(id.to_string())),
+    );
+    for (id, requested, placed) in counts {
+        if placed != requested {
+            violations.push(Violation::QuantityMismatch {
+                part_id: id.to_string(),
+                requested,
+                placed,
+            });
+        }
+    }
+}
+
+fn check_stock_use<'a>(
+    used: impl Iterator<Item = &'a str>,
+    available: impl Fn(&str) -> Option<usize>,
+    violations: &mut Vec<Violation>,
+) {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for id in used {
+        match counts.iter_mut().find(|(stock, _)| *stock == id) {
+            Some(entry) => entry.1 += 1,
+            None => counts.push((id, 1)),
+        }
+    }
+    for (id, used) in counts {
+        if let Some(available) = available(id)
+            && used > available
+        {
+            violations.push(Violation::StockOverused {
+                stock_id: id.to_string(),
+                available,
+                used,
+            });
+        }
+    }
+}
+
+/// Checks sheet layouts from any planar solver on their own terms: every placement inside the
+/// trim, a kerf clear of the zones' true outlines and of the others, turned only as grain
+/// allows, on a good enough face and at its part's cut and finished size; every saw pass clear
+/// of the parts, and of the zones unless it is a guillotine cut, which must run edge to edge;
+/// every part placed exactly its quantity; no stock used more often than listed; and each
+/// layout's metrics adding up to its stock total.
+pub fn verify_sheets(
+    layouts: &[SheetLayout],
+    parts: &[RectPart],
+    stock: &[SheetStock],
+    config: &PlanarNestConfig,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for (idx, layout) in layouts.iter().enumerate() {
+        let Some(sheet) = stock.iter().find(|s| s.id == layout.stock_id) else {
+            violations.push(Violation::UnknownStock(layout.stock_id.clone()));
+            continue;
+        };
+        let all: Vec<usize> = (0..layout.placements.len()).collect();
+        violations.extend(
+            check_placements(layout, &all, sheet, parts, config)
+                .into_iter()
+                .map(|v| Violation::On(idx, v)),
+        );
+        violations.extend(
+            check_cuts(layout, sheet, config.trim)
+                .into_iter()
+                .map(|v| Violation::On(idx, v)),
+        );
+        if !reconciles(&layout.metrics) {
+            violations.push(Violation::On(idx, LayoutViolation::MetricsDoNotReconcile));
+        }
+    }
+    count_parts(
+        layouts
+            .iter()
+            .flat_map(|l| l.placements.iter().map(|p| p.part_id.as_str())),
+        parts.iter().map(|p| (p.id.as_str(), p.quantity)),
+        &mut violations,
+    );
+    check_stock_use(
+        layouts.iter().map(|l| l.stock_id.as_str()),
+        |id| stock.iter().find(|s| s.id == id).map(|s| s.quantity),
+        &mut violations,
+    );
+    violations
+}
+
+/// The same checks for boards from the linear solvers, with defects in place of zones.
+pub fn verify_boards(
+    boards: &[LinearBoard],
+    parts: &[LinearPart],
+    stock: &[LinearStock],
+    config: &LinearNestConfig,
+) -> Vec<Violation> {
+    let kerf = config.kerf;
+    let mut violations = Vec::new();
+    for (idx, board) in boards.iter().enumerate() {
+        let Some(board_stock) = stock.iter().find(|s| s.id == board.stock_id) else {
+            violations.push(Violation::UnknownStock(board.stock_id.clone()));
+            continue;
+        };
+        // offcuts off the rack are cut square and carry no trim
+        let (leading, trailing) = if board_stock.source.is_some() {
+            (0.0, 0.0)
+        } else {
+            (config.trim_leading, config.trim_trailing)
+        };
+        let mut found = Vec::new();
+        for (i, cut) in board.cuts.iter().enumerate() {
+            if cut.start < leading - EPS
+                || cut.start + cut.length > board_stock.length - trailing + EPS
+            {
+                found.push(LayoutViolation::OutOfBounds(i));
+            }
+            if board_stock
+                .defects
+                .iter()
+                .any(|d| gap(cut.start, cut.length, d.start, d.length) < kerf - EPS)
+            {
+                found.push(LayoutViolation::InExclusionZone(i));
+            }
+            if parts
+                .iter()
+                .find(|p| p.id == cut.part_id)
+                .is_some_and(|p| (p.length - cut.length).abs() > EPS)
+            {
+                found.push(LayoutViolation::WrongSize(i));
+            }
+            for (j, other) in board.cuts.iter().enumerate().skip(i + 1) {
+                let between = gap(cut.start, cut.length, other.start, other.length);
+                if between < -EPS {
+                    found.push(LayoutViolation::Overlap(i, j));
+                } else if between < kerf - EPS {
+                    found.push(LayoutViolation::KerfSpacing(i, j));
+                }
+            }
+        }
+        if !reconciles(&board.metrics) {
+            found.push(LayoutViolation::MetricsDoNotReconcile);
+        }
+        violations.extend(found.into_iter().map(|v| Violation::On(idx, v)));
+    }
+    count_parts(
+        boards
+            .iter()
+            .flat_map(|b| b.cuts.iter().map(|c| c.part_id.as_str())),
+        parts.iter().map(|p| (p.id.as_str(), p.quantity)),
+        &mut violations,
+    );
+    check_stock_use(
+        boards.iter().map(|b| b.stock_id.as_str()),
+        |id| stock.iter().find(|s| s.id == id).map(|s| s.quantity),
+        &mut violations,
+    );
+    violations
+}
+
+// Grain-bound shapes turn only in quarter turns, an odd number of them where the part's grain
+// crosses the sheet's.
+fn shape_grain_holds(part: GrainDirection, sheet: GrainDirection, rotation: f64) -> bool {
+    if part == GrainDirection::Either {
+        return true;
+    }
+    let quarters = rotation / 90.0;
+    (quarters - quarters.round()).abs() < 1e-9
+        && grain_holds(part, sheet, (quarters.round() as i64).rem_euclid(2) == 1)
+}
+
+// whether `placement` sits inside `ancestor`, following the parents
+fn nested_in(layout: &ShapeLayout, mut placement: usize, ancestor: usize) -> bool {
+    for _ in 0..layout.placements.len() {
+        match layout.placements[placement].parent {
+            Some(parent) if parent == ancestor => return true,
+            Some(parent) if parent < layout.placements.len() => placement = parent,
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// The same checks for true-shape layouts: every outline inside the trim and a tool diameter
+/// clear of the zones and of the others, a part nested in another's cutout sitting a tool
+/// diameter inside one of its holes, and grain-bound parts turned only as their grain allows.
+pub fn verify_shapes(
+    layouts: &[ShapeLayout],
+    parts: &[ShapePart],
+    stock: &[SheetStock],
+    config: &ShapeNestConfig,
+) -> Vec<Violation> {
+    let (trim, spacing) = (config.trim, config.tool_diameter);
+    let mut violations = Vec::new();
+    for (idx, layout) in layouts.iter().enumerate() {
+        let Some(sheet) = stock.iter().find(|s| s.id == layout.stock_id) else {
+            violations.push(Violation::UnknownStock(layout.stock_id.clone()));
+            continue;
+        };
+        let zones: Vec<Vec<Point>> = sheet.exclusions.iter().map(zone_ring).collect();
+        // each placement's outline on the sheet; unknown parts have none
+        let outlines: Vec<Option<Polygon>> = layout
+            .placements
+            .iter()
+            .map(|p| {
+                let part = parts.iter().find(|part| part.id == p.part_id)?;
+                let place = |ring: &Vec<Point>| ring.iter().map(|&q| p.transform(q)).collect();
+                Some(Polygon {
+                    outer: place(&part.shape.outer),
+                    holes: part.shape.holes.iter().map(place).collect(),
+                })
+            })
+            .collect();
+        let mut found = Vec::new();
+        for (i, p) in layout.placements.iter().enumerate() {
+            if p.parent
+                .is_some_and(|parent| parent >= layout.placements.len() || parent == i)
+            {
+                found.push(LayoutViolation::NoSuchPlacement(i));
+            }
+            let Some(shape) = &outlines[i] else {
+                continue;
+            };
+            let outer = &shape.outer;
+            if outer.iter().any(|q| {
+                q.x < trim - EPS
+                    || q.y < trim - EPS
+                    || q.x > sheet.width - trim + EPS
+                    || q.y > sheet.height - trim + EPS
+            }) {
+                found.push(LayoutViolation::OutOfBounds(i));
+            }
+            if zones
+                .iter()
+                .any(|zone| clearance(outer, zone).is_none_or(|d| d < spacing - EPS))
+            {
+                found.push(LayoutViolation::InExclusionZone(i));
+            }
+            let part = parts.iter().find(|part| part.id == p.part_id);
+            if part.is_some_and(|part| !shape_grain_holds(part.grain, sheet.grain, p.rotation)) {
+                found.push(LayoutViolation::AgainstGrain(i));
+            }
+            for (j, other) in outlines.iter().enumerate().skip(i + 1) {
+                let Some(other) = other else {
+                    continue;
+                };
+                let (inner, holes) = if nested_in(layout, i, j) {
+                    (outer, &other.holes)
+                } else if nested_in(layout, j, i) {
+                    (&other.outer, &shape.holes)
+                } else {
+                    match clearance(outer, &other.outer) {
+                        None => found.push(LayoutViolation::Overlap(i, j)),
+                        Some(d) if d < spacing - EPS => {
+                            found.push(LayoutViolation::KerfSpacing(i, j));
+                        }
+                        Some(_) => {}
+                    }
+                    continue;
+                };
+                match holes
+                    .iter()
+                    .filter_map(|hole| clearance_within(inner, hole))
+                    .reduce(f64::max)
+                {
+                    None => found.push(LayoutViolation::Overlap(i, j)),
+                    Some(d) if d < spacing - EPS => found.push(LayoutViolation::KerfSpacing(i, j)),
+                    Some(_) => {}
+                }
+            }
+        }
+        if !reconciles(&layout.metrics) {
+            found.push(LayoutViolation::MetricsDoNotReconcile);
+        }
+        violations.extend(found.into_iter().map(|v| Violation::On(idx, v)));
+    }
+    count_parts(
+        layouts
+            .iter()
+            .flat_map(|l| l.placements.iter().map(|p| p.part_id.as_str())),
+        parts.iter().map(|p| (p.id.as_str(), p.quantity)),
+        &mut violations,
+    );
+    check_stock_use(
+        layouts.iter().map(|l| l.stock_id.as_str()),
+        |id| stock.iter().find(|s| s.id == id).map(|s| s.quantity),
+        &mut violations,
+    );
+    violations
+}
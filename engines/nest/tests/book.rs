@@ -0,0 +1,191 @@
+use nest::{
+    BookConfig, FaceGrade, GrainDirection, GuillotineConfig, NestError, PlanarNestConfig, RectPart,
+    SheetStock, book_sheets,
+};
+
+fn stock() -> Vec<SheetStock> {
+    vec![SheetStock {
+        id: "sheet".into(),
+        width: 1000.0,
+        height: 1000.0,
+        quantity: 10,
+        grain: GrainDirection::AlongX,
+        thickness: 18.0,
+        ..SheetStock::default()
+    }]
+}
+
+fn sides(quantity: usize) -> Vec<RectPart> {
+    vec![RectPart {
+        id: "side".into(),
+        width: 1000.0,
+        height: 498.0,
+        quantity,
+        grain: GrainDirection::AlongX,
+        face: FaceGrade::B,
+        thickness: 18.0,
+        ..RectPart::default()
+    }]
+}
+
+fn config() -> PlanarNestConfig {
+    PlanarNestConfig {
+        kerf: 4.0,
+        ..PlanarNestConfig::default()
+    }
+}
+
+#[test]
+fn sheets_are_cut_in_books_the_saw_can_take() {
+    let books = BookConfig {
+        max_book_height: 40.0,
+        max_overproduction: 0,
+        max_extra_sheets: 0,
+    };
+
+    let result = book_sheets(
+        &sides(10),
+        &stock(),
+        &config(),
+        &GuillotineConfig::default(),
+        &books,
+    )
+    .expect("nest succeeds");
+
+    assert_eq!(result.patterns.len(), 1);
+    assert_eq!(result.sheets, 5);
+    assert_eq!(result.patterns[0].books, vec![2, 2, 1]);
+    assert_eq!(result.books, 3);
+    assert_eq!(result.cycles, 3 * result.patterns[0].layout.cuts.len());
+    assert!(result.overproduced.is_empty());
+}
+
+#[test]
+fn overproduction_is_traded_for_saw_cycles_within_the_limit() {
+    let nest = |max_overproduction: usize| {
+        book_sheets(
+            &sides(5),
+            &stock(),
+            &config(),
+            &GuillotineConfig::default(),
+            &BookConfig {
+                max_book_height: 60.0,
+                max_overproduction,
+                max_extra_sheets: 0,
+            },
+        )
+        .expect("nest succeeds")
+    };
+
+    // two full sheets and one with a single side
+    let exact = nest(0);
+    assert_eq!(exact.patterns.len(), 2);
+    assert_eq!(exact.sheets, 3);
+    assert_eq!(exact.books, 2);
+
+    // a third full sheet joins the first book and the odd sheet goes
+    let stacked = nest(1);
+    assert_eq!(stacked.patterns.len(), 1);
+    assert_eq!(stacked.sheets, 3);
+    assert_eq!(stacked.patterns[0].books, vec![3]);
+    assert!(stacked.cycles < exact.cycles);
+    assert_eq!(stacked.overproduced.len(), 1);
+    assert_eq!(stacked.overproduced[0].quantity, 1);
+}
+
+#[test]
+fn a_book_must_hold_a_sheet() {
+    let err = book_sheets(
+        &sides(1),
+        &stock(),
+        &config(),
+        &GuillotineConfig::default(),
+        &BookConfig {
+            max_book_height: 10.0,
+            max_overproduction: 0,
+            max_extra_sheets: 0,
+        },
+    )
+    .unwrap_err();
+
+    assert!(matches!(err, NestError::InvalidDimension(_)));
+}
+
+#[test]
+fn a_pattern_is_given_up_for_one_more_sheet_when_allowed() {
+    let part = |id: &str, height: f64, quantity: usize| RectPart {
+        id: id.into(),
+        height,
+        quantity,
+        ..sides(0).remove(0)
+    };
+    // each sheet takes a side and a shelf; the two shelves left over get a sheet of their own
+    let parts = vec![part("side", 600.0, 3), part("shelf", 200.0, 5)];
+    let nest = |max_extra_sheets: usize| {
+        book_sheets(
+            &parts,
+            &stock(),
+            &config(),
+            &GuillotineConfig::default(),
+            &BookConfig {
+                max_book_height: 150.0,
+                max_overproduction: 2,
+                max_extra_sheets,
+            },
+        )
+        .expect("nest succeeds")
+    };
+
+    let tight = nest(0);
+    assert_eq!(tight.patterns.len(), 2);
+    assert_eq!(tight.sheets, 4);
+
+    // two more side-and-shelf sheets join the one book and the shelf sheet goes
+    let loose = nest(1);
+    assert_eq!(loose.patterns.len(), 1);
+    assert_eq!(loose.sheets, 5);
+    assert_eq!(loose.patterns[0].books, vec![5]);
+    assert!(loose.cycles < tight.cycles);
+    assert_eq!(loose.overproduced.len(), 1);
+    assert_eq!(loose.overproduced[0].part_id, "side");
+    assert_eq!(loose.overproduced[0].quantity, 2);
+}
+
+#[test]
+fn the_book_height_comes_from_the_stock_thickness() {
+    let thin = vec![SheetStock {
+        thickness: 6.0,
+        ..stock().remove(0)
+    }];
+    let result = book_sheets(
+        &sides(10),
+        &thin,
+        &config(),
+        &GuillotineConfig::default(),
+        &BookConfig {
+            max_book_height: 40.0,
+            max_overproduction: 0,
+            max_extra_sheets: 0,
+        },
+    )
+    .expect("nest succeeds");
+    assert_eq!(result.patterns[0].books, vec![5]);
+
+    let unknown = vec![SheetStock {
+        thickness: 0.0,
+        ..stock().remove(0)
+    }];
+    let err = book_sheets(
+        &sides(1),
+        &unknown,
+        &config(),
+        &GuillotineConfig::default(),
+        &BookConfig {
+            max_book_height: 40.0,
+            max_overproduction: 0,
+            max_extra_sheets: 0,
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, NestError::InvalidDimension(_)));
+}
@@ -0,0 +1,140 @@
+use nest::{
+    FaceGrade, GrainDirection, LayoutEdit, LayoutViolation, NestError, PlanarNestConfig, RectPart,
+    SheetStock, best_fit_sheets, edit_layout,
+};
+
+fn part(id: &str, width: f64, height: f64, quantity: usize, grain: GrainDirection) -> RectPart {
+    RectPart {
+        id: id.into(),
+        width,
+        height,
+        quantity,
+        grain,
+        face: FaceGrade::B,
+        ..RectPart::default()
+    }
+}
+
+fn sheet() -> SheetStock {
+    SheetStock {
+        id: "mdf".into(),
+        width: 2000.0,
+        height: 1000.0,
+        quantity: 1,
+        grain: GrainDirection::AlongX,
+        ..SheetStock::default()
+    }
+}
+
+fn violations(result: Result<nest::SheetLayout, NestError>) -> Vec<LayoutViolation> {
+    match result {
+        Err(NestError::InvalidEdit(violations)) => violations,
+        other => panic!("expected a rejected edit, got {other:?}"),
+    }
+}
+
+#[test]
+fn edits_are_checked_and_the_layout_measured_again() {
+    let parts = vec![
+        part("rail", 400.0, 100.0, 1, GrainDirection::AlongX),
+        part("block", 200.0, 200.0, 2, GrainDirection::Either),
+    ];
+    let config = PlanarNestConfig {
+        kerf: 4.0,
+        trim: 5.0,
+        ..PlanarNestConfig::default()
+    };
+    let layout = best_fit_sheets(&parts, &[sheet()], &config)
+        .unwrap()
+        .remove(0);
+    let find = |id: &str| {
+        layout
+            .placements
+            .iter()
+            .position(|p| p.part_id == id)
+            .unwrap()
+    };
+    let (rail, block) = (find("rail"), find("block"));
+    let other = (0..3).find(|&i| i != rail && i != block).unwrap();
+
+    let moved = edit_layout(
+        &layout,
+        LayoutEdit::Move {
+            index: block,
+            x: 1500.0,
+            y: 600.0,
+        },
+        &sheet(),
+        &parts,
+        &config,
+    )
+    .expect("open space");
+    assert_eq!(
+        (moved.placements[block].x, moved.placements[block].y),
+        (1500.0, 600.0)
+    );
+    assert!((moved.metrics.utilized - layout.metrics.utilized).abs() < 1e-6);
+    for offcut in &moved.offcuts {
+        for p in &moved.placements {
+            assert!(
+                offcut.x >= p.x + p.width
+                    || p.x >= offcut.x + offcut.width
+                    || offcut.y >= p.y + p.height
+                    || p.y >= offcut.y + offcut.height
+            );
+        }
+    }
+
+    let rotated = edit_layout(
+        &layout,
+        LayoutEdit::Rotate { index: rail },
+        &sheet(),
+        &parts,
+        &config,
+    );
+    assert!(violations(rotated).contains(&LayoutViolation::AgainstGrain(rail)));
+
+    let target = &layout.placements[other];
+    let onto = LayoutEdit::Move {
+        index: block,
+        x: target.x + 10.0,
+        y: target.y,
+    };
+    let pair = (block.min(other), block.max(other));
+    let overlap = violations(edit_layout(&layout, onto, &sheet(), &parts, &config));
+    assert!(overlap.contains(&LayoutViolation::Overlap(pair.0, pair.1)));
+
+    let near = LayoutEdit::Move {
+        index: block,
+        x: target.x,
+        y: target.y + target.height + 2.0,
+    };
+    let tight = violations(edit_layout(&layout, near, &sheet(), &parts, &config));
+    assert_eq!(tight, vec![LayoutViolation::KerfSpacing(pair.0, pair.1)]);
+
+    let off = LayoutEdit::Move {
+        index: block,
+        x: 1900.0,
+        y: 600.0,
+    };
+    let outside = violations(edit_layout(&layout, off, &sheet(), &parts, &config));
+    assert_eq!(outside, vec![LayoutViolation::OutOfBounds(block)]);
+
+    let missing = LayoutEdit::Swap {
+        first: block,
+        second: 9,
+    };
+    let missing = violations(edit_layout(&layout, missing, &sheet(), &parts, &config));
+    assert_eq!(missing, vec![LayoutViolation::NoSuchPlacement(9)]);
+
+    let deleted = edit_layout(
+        &layout,
+        LayoutEdit::Delete { index: rail },
+        &sheet(),
+        &parts,
+        &config,
+    )
+    .unwrap();
+    assert_eq!(deleted.placements.len(), 2);
+    assert!((layout.metrics.utilized - deleted.metrics.utilized - 40_000.0).abs() < 1e-6);
+}
@@ -0,0 +1,220 @@
+use nest::{
+    ExclusionZone, FaceGrade, GrainDirection, GuillotineConfig, MaxRectsHeuristic, NestError,
+    PlanarNestConfig, PlanarStrategy, Point, Polygon, RectPart, ShapeNestConfig, ShapePart,
+    SheetStock, UtilizationBreakdown, partial_sheets, shape_sheets, verify_shapes, verify_sheets,
+};
+
+// a clamp pad in the middle and a broken corner
+fn damaged_sheet() -> SheetStock {
+    SheetStock {
+        id: "ply".into(),
+        width: 1000.0,
+        height: 1000.0,
+        quantity: 3,
+        exclusions: vec![
+            ExclusionZone::Rect {
+                x: 400.0,
+                y: 400.0,
+                width: 200.0,
+                height: 200.0,
+            },
+            ExclusionZone::Polygon(vec![
+                Point { x: 0.0, y: 0.0 },
+                Point { x: 150.0, y: 0.0 },
+                Point { x: 0.0, y: 150.0 },
+            ]),
+        ],
+        ..SheetStock::default()
+    }
+}
+
+// (x0, y0, x1, y1) of each zone
+const ZONES: [(f64, f64, f64, f64); 2] = [(400.0, 400.0, 600.0, 600.0), (0.0, 0.0, 150.0, 150.0)];
+const EXCLUDED: f64 = 200.0 * 200.0 + 150.0 * 150.0 / 2.0;
+// the rest of the broken corner's bounding box, which the rectangle solvers keep clear of too
+const CORNER_REST: f64 = 150.0 * 150.0 / 2.0;
+
+fn assert_accounted(m: &UtilizationBreakdown, defect: f64) {
+    assert!((m.defect_loss - defect).abs() < 1e-6);
+    let total = m.utilized + m.kerf_loss + m.trim_loss + m.offcut_loss + m.defect_loss;
+    assert!((total - m.stock_total).abs() < 1e-6);
+}
+
+#[test]
+fn every_rectangle_strategy_keeps_parts_and_offcuts_off_the_zones() {
+    let parts = vec![RectPart {
+        id: "shelf".into(),
+        width: 300.0,
+        height: 280.0,
+        quantity: 8,
+        face: FaceGrade::B,
+        ..RectPart::default()
+    }];
+    let config = PlanarNestConfig {
+        kerf: 4.0,
+        ..PlanarNestConfig::default()
+    };
+    let strategies = [
+        PlanarStrategy::BestFit,
+        PlanarStrategy::Skyline,
+        PlanarStrategy::Guillotine(GuillotineConfig::default()),
+        PlanarStrategy::MaxRects(MaxRectsHeuristic::BestShortSideFit),
+    ];
+    for strategy in strategies {
+        let result = partial_sheets(&parts, &[damaged_sheet()], &config, strategy).expect("nest");
+        assert!(result.unplaced.is_empty(), "{strategy:?}");
+        let placed: usize = result.layouts.iter().map(|l| l.placements.len()).sum();
+        assert_eq!(placed, 8, "{strategy:?}");
+        let found = verify_sheets(&result.layouts, &parts, &[damaged_sheet()], &config);
+        assert!(found.is_empty(), "{strategy:?}: {found:?}");
+        for layout in &result.layouts {
+            for p in &layout.placements {
+                for (x0, y0, x1, y1) in ZONES {
+                    let clear = p.x + p.width + config.kerf <= x0 + 1e-6
+                        || p.x >= x1 + config.kerf - 1e-6
+                        || p.y + p.height + config.kerf <= y0 + 1e-6
+                        || p.y >= y1 + config.kerf - 1e-6;
+                    assert!(clear, "{strategy:?}: {p:?} too close to a zone");
+                }
+            }
+            for o in &layout.offcuts {
+                for (x0, y0, x1, y1) in ZONES {
+                    let clear = o.x + o.width <= x0 + 1e-6
+                        || o.x >= x1 - 1e-6
+                        || o.y + o.height <= y0 + 1e-6
+                        || o.y >= y1 - 1e-6;
+                    assert!(clear, "{strategy:?}: offcut {o:?} covers a zone");
+                }
+            }
+            assert_accounted(&layout.metrics, EXCLUDED);
+            assert!(layout.metrics.offcut_loss >= CORNER_REST - 1e-6);
+        }
+    }
+}
+
+#[test]
+fn shapes_keep_tool_spacing_from_the_zones() {
+    let square = |size: f64| Polygon {
+        outer: vec![
+            Point { x: 0.0, y: 0.0 },
+            Point { x: size, y: 0.0 },
+            Point { x: size, y: size },
+            Point { x: 0.0, y: size },
+        ],
+        holes: Vec::new(),
+    };
+    let parts = vec![ShapePart {
+        id: "tile".into(),
+        shape: square(250.0),
+        quantity: 6,
+        grain: GrainDirection::Either,
+    }];
+    let config = ShapeNestConfig {
+        tool_diameter: 6.0,
+        rotations: vec![0.0],
+        ..ShapeNestConfig::default()
+    };
+    let layouts = shape_sheets(&parts, &[damaged_sheet()], &config).expect("nest");
+    let found = verify_shapes(&layouts, &parts, &[damaged_sheet()], &config);
+    assert!(found.is_empty(), "{found:?}");
+    for layout in &layouts {
+        for p in &layout.placements {
+            let (x0, y0) = (p.x, p.y);
+            let (x1, y1) = (x0 + 250.0, y0 + 250.0);
+            let (zx0, zy0, zx1, zy1) = ZONES[0];
+            let clear = x1 + config.tool_diameter <= zx0 + 1e-6
+                || x0 >= zx1 + config.tool_diameter - 1e-6
+                || y1 + config.tool_diameter <= zy0 + 1e-6
+                || y0 >= zy1 + config.tool_diameter - 1e-6;
+            assert!(clear, "{p:?} too close to the clamp pad");
+            // the lower-left corner is the nearest point to the broken edge from (150, 0)
+            // to (0, 150)
+            let t = ((150.0 - x0 + y0) / 300.0).clamp(0.0, 1.0);
+            let gap = (x0 - 150.0 * (1.0 - t)).hypot(y0 - 150.0 * t);
+            assert!(
+                gap >= config.tool_diameter - 1e-6,
+                "{p:?} too close to the broken corner"
+            );
+        }
+        assert_accounted(&layout.metrics, EXCLUDED);
+    }
+    let placed: usize = layouts.iter().map(|l| l.placements.len()).sum();
+    assert_eq!(placed, 6);
+}
+
+#[test]
+fn overlapping_zones_are_rejected() {
+    let parts = vec![RectPart {
+        id: "shelf".into(),
+        width: 300.0,
+        height: 280.0,
+        quantity: 1,
+        face: FaceGrade::B,
+        ..RectPart::default()
+    }];
+    let mut sheet = damaged_sheet();
+    // reaches into the broken corner's triangle
+    sheet.exclusions.push(ExclusionZone::Rect {
+        x: 50.0,
+        y: 50.0,
+        width: 100.0,
+        height: 100.0,
+    });
+    let err = partial_sheets(
+        &parts,
+        &[sheet.clone()],
+        &PlanarNestConfig::default(),
+        PlanarStrategy::BestFit,
+    )
+    .expect_err("overlap");
+    assert!(matches!(err, NestError::InvalidDimension(_)));
+
+    // touching the diagonal edge from outside is fine
+    sheet.exclusions.pop();
+    sheet.exclusions.push(ExclusionZone::Polygon(vec![
+        Point { x: 150.0, y: 0.0 },
+        Point { x: 150.0, y: 150.0 },
+        Point { x: 0.0, y: 150.0 },
+    ]));
+    partial_sheets(
+        &parts,
+        &[sheet],
+        &PlanarNestConfig::default(),
+        PlanarStrategy::BestFit,
+    )
+    .expect("touching zones");
+}
+
+#[test]
+fn parts_on_either_side_of_a_zone_corner_keep_a_kerf_apart() {
+    // the free pieces left of the pad and below it meet along x = 197
+    let sheets = vec![SheetStock {
+        id: "ply".into(),
+        width: 1000.0,
+        height: 1000.0,
+        quantity: 1,
+        exclusions: vec![ExclusionZone::Rect {
+            x: 200.0,
+            y: 300.0,
+            width: 200.0,
+            height: 200.0,
+        }],
+        ..SheetStock::default()
+    }];
+    let part = |id: &str, width: f64, height: f64| RectPart {
+        id: id.into(),
+        width,
+        height,
+        quantity: 1,
+        ..RectPart::default()
+    };
+    let parts = vec![part("stile", 197.0, 1000.0), part("block", 206.0, 297.0)];
+    let config = PlanarNestConfig {
+        kerf: 3.0,
+        ..PlanarNestConfig::default()
+    };
+    let result = partial_sheets(&parts, &sheets, &config, PlanarStrategy::BestFit).unwrap();
+    assert!(result.unplaced.is_empty());
+    let found = verify_sheets(&result.layouts, &parts, &sheets, &config);
+    assert!(found.is_empty(), "{found:?}");
+}
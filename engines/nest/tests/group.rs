@@ -0,0 +1,175 @@
+use nest::{
+    CutAxis, FaceGrade, GrainDirection, GroupArrangement, GuillotineConfig, MaxRectsHeuristic,
+    NestError, PartGroup, PlanarNestConfig, PlanarStrategy, RectPart, SheetStock, grouped_sheets,
+    verify_sheets,
+};
+
+fn part(id: &str, width: f64, height: f64, quantity: usize) -> RectPart {
+    RectPart {
+        id: id.into(),
+        width,
+        height,
+        quantity,
+        grain: GrainDirection::AlongX,
+        face: FaceGrade::B,
+        ..RectPart::default()
+    }
+}
+
+fn stock() -> Vec<SheetStock> {
+    vec![SheetStock {
+        id: "oak-ply".into(),
+        width: 2440.0,
+        height: 1220.0,
+        quantity: 3,
+        ..SheetStock::default()
+    }]
+}
+
+#[test]
+fn drawer_fronts_stack_in_order_on_one_sheet() {
+    let parts = vec![
+        part("front-top", 596.0, 140.0, 1),
+        part("front-mid", 596.0, 176.0, 2),
+        part("front-bottom", 596.0, 250.0, 1),
+        part("side", 500.0, 700.0, 6),
+    ];
+    let groups = vec![PartGroup {
+        id: "chest-fronts".into(),
+        members: vec![
+            "front-top".into(),
+            "front-mid".into(),
+            "front-mid".into(),
+            "front-bottom".into(),
+        ],
+        arrangement: GroupArrangement::Column,
+    }];
+    let config = PlanarNestConfig {
+        kerf: 4.0,
+        trim: 5.0,
+        ..PlanarNestConfig::default()
+    };
+    let strategies = [
+        PlanarStrategy::BestFit,
+        PlanarStrategy::Guillotine(GuillotineConfig::default()),
+        PlanarStrategy::MaxRects(MaxRectsHeuristic::BestShortSideFit),
+    ];
+    for strategy in strategies {
+        let layouts = grouped_sheets(&parts, &groups, &stock(), &config, strategy).expect("nest");
+        let placed: usize = layouts.iter().map(|l| l.placements.len()).sum();
+        assert_eq!(placed, 10, "{strategy:?}");
+
+        let holder = layouts
+            .iter()
+            .find(|l| l.placements.iter().any(|p| p.part_id == "front-top"))
+            .unwrap();
+        let fronts: Vec<_> = holder
+            .placements
+            .iter()
+            .filter(|p| p.part_id.starts_with("front"))
+            .collect();
+        let ids: Vec<&str> = fronts.iter().map(|p| p.part_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            ["front-top", "front-mid", "front-mid", "front-bottom"],
+            "{strategy:?}"
+        );
+        for pair in fronts.windows(2) {
+            assert!((pair[0].x - pair[1].x).abs() < 1e-9);
+            assert!((pair[0].y + pair[0].height + config.kerf - pair[1].y).abs() < 1e-9);
+        }
+        for layout in &layouts {
+            let m = &layout.metrics;
+            let area: f64 = layout.placements.iter().map(|p| p.width * p.height).sum();
+            assert!((m.utilized - area).abs() < 1e-6);
+            let total = m.utilized + m.kerf_loss + m.trim_loss + m.offcut_loss + m.defect_loss;
+            assert!((total - m.stock_total).abs() < 1e-6, "{strategy:?}");
+        }
+        if matches!(strategy, PlanarStrategy::Guillotine(_)) {
+            // three last-stage cuts part the four fronts
+            let between = fronts
+                .windows(2)
+                .filter(|pair| {
+                    holder.cuts.iter().any(|c| {
+                        c.stage == 3
+                            && c.axis == CutAxis::AlongX
+                            && (c.position - pair[0].y - pair[0].height).abs() < 1e-9
+                    })
+                })
+                .count();
+            assert_eq!(between, 3);
+            let found = verify_sheets(&layouts, &parts, &stock(), &config);
+            assert!(found.is_empty(), "{found:?}");
+        }
+    }
+}
+
+#[test]
+fn a_group_wider_than_any_sheet_is_named_in_the_error() {
+    let parts = vec![part("door", 900.0, 700.0, 3)];
+    let groups = vec![PartGroup {
+        id: "wardrobe-doors".into(),
+        members: vec!["door".into(), "door".into(), "door".into()],
+        arrangement: GroupArrangement::Row,
+    }];
+    let err = grouped_sheets(
+        &parts,
+        &groups,
+        &stock(),
+        &PlanarNestConfig::default(),
+        PlanarStrategy::BestFit,
+    )
+    .expect_err("three doors side by side need 2700");
+    assert_eq!(err, NestError::GroupDoesNotFit("wardrobe-doors".into()));
+}
+
+#[test]
+fn guillotine_groups_are_parted_within_the_stage_limit() {
+    let parts = vec![
+        part("rail", 800.0, 120.0, 3),
+        part("panel", 400.0, 300.0, 4),
+    ];
+    let groups = vec![PartGroup {
+        id: "rails".into(),
+        members: vec!["rail".into(), "rail".into(), "rail".into()],
+        arrangement: GroupArrangement::Column,
+    }];
+    let config = PlanarNestConfig {
+        kerf: 3.0,
+        trim: 8.0,
+        ..PlanarNestConfig::default()
+    };
+    for (stages, first_cut) in [
+        (2, CutAxis::AlongY),
+        (3, CutAxis::AlongX),
+        (1, CutAxis::AlongX),
+    ] {
+        let guillotine = GuillotineConfig { stages, first_cut };
+        let strategy = PlanarStrategy::Guillotine(guillotine);
+        let layouts = grouped_sheets(&parts, &groups, &stock(), &config, strategy).unwrap();
+        assert!(
+            layouts
+                .iter()
+                .flat_map(|l| &l.cuts)
+                .all(|c| c.stage <= stages + 1)
+        );
+        let found = verify_sheets(&layouts, &parts, &stock(), &config);
+        assert!(found.is_empty(), "{stages} from {first_cut:?}: {found:?}");
+    }
+
+    // held to the grain, the column can only stack up the sheet, and two stages from X leave
+    // the last cuts running up it too
+    let guillotine = GuillotineConfig {
+        stages: 2,
+        first_cut: CutAxis::AlongX,
+    };
+    let err = grouped_sheets(
+        &parts,
+        &groups,
+        &stock(),
+        &config,
+        PlanarStrategy::Guillotine(guillotine),
+    )
+    .expect_err("no stage is left to part the rails");
+    assert_eq!(err, NestError::GroupDoesNotFit("rails".into()));
+}
@@ -0,0 +1,154 @@
+use nest::{
+    CutAxis, FaceGrade, GrainDirection, GuillotineConfig, PlanarNestConfig, RectPart, SheetLayout,
+    SheetStock, guillotine_sheets,
+};
+
+fn cabinet_parts() -> Vec<RectPart> {
+    vec![
+        RectPart {
+            id: "side".into(),
+            width: 720.0,
+            height: 560.0,
+            quantity: 4,
+            grain: GrainDirection::AlongX,
+            face: FaceGrade::B,
+            ..RectPart::default()
+        },
+        RectPart {
+            id: "shelf".into(),
+            width: 764.0,
+            height: 540.0,
+            quantity: 3,
+            face: FaceGrade::B,
+            ..RectPart::default()
+        },
+        RectPart {
+            id: "rail".into(),
+            width: 764.0,
+            height: 100.0,
+            quantity: 6,
+            grain: GrainDirection::AlongX,
+            face: FaceGrade::B,
+            ..RectPart::default()
+        },
+    ]
+}
+
+fn stock() -> Vec<SheetStock> {
+    vec![SheetStock {
+        id: "birch-2440".into(),
+        width: 2440.0,
+        height: 1220.0,
+        quantity: 4,
+        ..SheetStock::default()
+    }]
+}
+
+fn approx(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-6
+}
+
+// every cut must run from the trimmed sheet edge or an earlier cross cut to the next one
+fn assert_cuttable(layout: &SheetLayout, sheet: &SheetStock, config: &PlanarNestConfig) {
+    for (idx, cut) in layout.cuts.iter().enumerate() {
+        let (low, high) = match cut.axis {
+            CutAxis::AlongX => (config.trim, sheet.width - config.trim),
+            CutAxis::AlongY => (config.trim, sheet.height - config.trim),
+        };
+        let earlier = &layout.cuts[..idx];
+        let crossing = |value: f64, after_kerf: bool| {
+            earlier.iter().any(|c| {
+                c.axis != cut.axis
+                    && c.stage < cut.stage
+                    && approx(value, c.position + if after_kerf { c.kerf } else { 0.0 })
+            })
+        };
+        assert!(
+            approx(cut.start, low) || crossing(cut.start, true),
+            "{cut:?}"
+        );
+        assert!(approx(cut.end, high) || crossing(cut.end, false), "{cut:?}");
+
+        for p in &layout.placements {
+            let (lo, hi, span_lo, span_hi) = match cut.axis {
+                CutAxis::AlongX => (p.y, p.y + p.height, p.x, p.x + p.width),
+                CutAxis::AlongY => (p.x, p.x + p.width, p.y, p.y + p.height),
+            };
+            let overlaps_span = span_lo < cut.end - 1e-6 && span_hi > cut.start + 1e-6;
+            let band_hits = lo < cut.position + cut.kerf - 1e-6 && hi > cut.position + 1e-6;
+            assert!(
+                !(overlaps_span && band_hits),
+                "cut {cut:?} runs through {p:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn two_stage_layouts_are_cuttable_edge_to_edge() {
+    let config = PlanarNestConfig {
+        kerf: 4.0,
+        trim: 10.0,
+        seed: 5,
+        ..PlanarNestConfig::default()
+    };
+    let guillotine = GuillotineConfig {
+        stages: 2,
+        first_cut: CutAxis::AlongX,
+    };
+    let layouts =
+        guillotine_sheets(&cabinet_parts(), &stock(), &config, &guillotine).expect("nest");
+
+    let placed: usize = layouts.iter().map(|l| l.placements.len()).sum();
+    assert_eq!(placed, 13);
+    for layout in &layouts {
+        assert_cuttable(layout, &stock()[0], &config);
+        assert!(layout.cuts.iter().all(|c| c.stage <= 3));
+        assert!(
+            layout
+                .cuts
+                .iter()
+                .filter(|c| c.stage == 1)
+                .all(|c| c.axis == CutAxis::AlongX && c.is_rip())
+        );
+        let m = &layout.metrics;
+        let cut_kerf: f64 = layout.cuts.iter().map(|c| (c.end - c.start) * c.kerf).sum();
+        assert!(approx(m.kerf_loss, cut_kerf));
+        assert!(approx(
+            m.utilized + m.kerf_loss + m.trim_loss + m.offcut_loss,
+            m.stock_total
+        ));
+    }
+}
+
+#[test]
+fn first_cut_direction_and_stage_count_are_honoured() {
+    let config = PlanarNestConfig {
+        kerf: 3.2,
+        trim: 5.0,
+        seed: 1,
+        ..PlanarNestConfig::default()
+    };
+    let guillotine = GuillotineConfig {
+        stages: 3,
+        first_cut: CutAxis::AlongY,
+    };
+    let layouts =
+        guillotine_sheets(&cabinet_parts(), &stock(), &config, &guillotine).expect("nest");
+
+    for layout in &layouts {
+        assert_cuttable(layout, &stock()[0], &config);
+        for cut in &layout.cuts {
+            let expected = if cut.stage % 2 == 1 {
+                CutAxis::AlongY
+            } else {
+                CutAxis::AlongX
+            };
+            assert_eq!(cut.axis, expected);
+            assert!(cut.stage <= 4);
+        }
+        for p in layout.placements.iter().filter(|p| p.part_id != "shelf") {
+            assert!(!p.rotated);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use nest::{
+    FaceGrade, GrainDirection, ImproveBudget, MaxRectsHeuristic, PlanarNestConfig, PlanarStrategy,
+    RectPart, SheetStock, best_fit_sheets, improve_sheets, max_rects_sheets,
+    summarize_sheet_layouts,
+};
+
+fn pinwheel_parts() -> Vec<RectPart> {
+    vec![
+        RectPart {
+            id: "long".into(),
+            width: 700.0,
+            height: 300.0,
+            quantity: 2,
+            grain: GrainDirection::AlongX,
+            face: FaceGrade::B,
+            ..RectPart::default()
+        },
+        RectPart {
+            id: "tall".into(),
+            width: 300.0,
+            height: 700.0,
+            quantity: 2,
+            grain: GrainDirection::AlongX,
+            face: FaceGrade::B,
+            ..RectPart::default()
+        },
+        RectPart {
+            id: "core".into(),
+            width: 400.0,
+            height: 400.0,
+            quantity: 1,
+            grain: GrainDirection::AlongX,
+            face: FaceGrade::B,
+            ..RectPart::default()
+        },
+    ]
+}
+
+fn square_stock() -> Vec<SheetStock> {
+    vec![SheetStock {
+        id: "square".into(),
+        width: 1000.0,
+        height: 1000.0,
+        quantity: 4,
+        ..SheetStock::default()
+    }]
+}
+
+#[test]
+fn search_finds_fewer_sheets_than_the_plain_heuristic() {
+    let config = PlanarNestConfig {
+        seed: 21,
+        ..PlanarNestConfig::default()
+    };
+    let heuristic = MaxRectsHeuristic::BottomLeft;
+    let plain = max_rects_sheets(&pinwheel_parts(), &square_stock(), &config, heuristic).unwrap();
+    let improved = improve_sheets(
+        &pinwheel_parts(),
+        &square_stock(),
+        &config,
+        PlanarStrategy::MaxRects(heuristic),
+        &ImproveBudget::default(),
+    )
+    .unwrap();
+    assert_eq!(plain.len(), 2);
+    assert_eq!(improved.len(), 1);
+    assert_eq!(improved[0].placements.len(), 5);
+}
+
+#[test]
+fn same_seed_repeats_and_never_loses_to_the_start() {
+    let parts: Vec<RectPart> = (0..9)
+        .map(|idx| RectPart {
+            id: format!("panel-{idx}"),
+            width: 300.0 + 45.0 * idx as f64,
+            height: 200.0 + 30.0 * (idx % 4) as f64,
+            quantity: 2,
+            face: FaceGrade::B,
+            ..RectPart::default()
+        })
+        .collect();
+    let stock = vec![
+        SheetStock {
+            id: "full".into(),
+            width: 2440.0,
+            height: 1220.0,
+            quantity: 3,
+            ..SheetStock::default()
+        },
+        SheetStock {
+            id: "half".into(),
+            width: 1220.0,
+            height: 1220.0,
+            quantity: 3,
+            ..SheetStock::default()
+        },
+    ];
+    let config = PlanarNestConfig {
+        kerf: 3.0,
+        trim: 5.0,
+        seed: 8,
+        ..PlanarNestConfig::default()
+    };
+    let budget = ImproveBudget {
+        iterations: 150,
+        ..ImproveBudget::default()
+    };
+
+    let first = improve_sheets(&parts, &stock, &config, PlanarStrategy::BestFit, &budget).unwrap();
+    let again = improve_sheets(&parts, &stock, &config, PlanarStrategy::BestFit, &budget).unwrap();
+    assert_eq!(first, again);
+
+    let start = best_fit_sheets(&parts, &stock, &config).unwrap();
+    assert!(first.len() <= start.len());
+    if first.len() == start.len() {
+        let eff = summarize_sheet_layouts(&first).efficiency();
+        assert!(eff >= summarize_sheet_layouts(&start).efficiency() - 1e-9);
+    }
+}
@@ -0,0 +1,134 @@
+use nest::{
+    LinearNestConfig, LinearPart, LinearStock, OffcutInventory, SearchBudget, first_fit_boards,
+    optimal_boards,
+};
+
+fn fresh_stock() -> Vec<LinearStock> {
+    vec![LinearStock {
+        id: "maple-2400".into(),
+        length: 2400.0,
+        quantity: 10,
+        cost: 30.0,
+        ..LinearStock::default()
+    }]
+}
+
+fn cfg() -> LinearNestConfig {
+    LinearNestConfig {
+        kerf: 3.0,
+        trim_leading: 10.0,
+        trim_trailing: 10.0,
+        ..LinearNestConfig::default()
+    }
+}
+
+#[test]
+fn offcuts_carry_provenance_and_are_reused_first() {
+    let mut inventory = OffcutInventory::new(400.0);
+
+    let first_job = vec![LinearPart {
+        id: "leg".into(),
+        length: 700.0,
+        quantity: 2,
+    }];
+    let first = first_fit_boards(&first_job, &fresh_stock(), &cfg()).unwrap();
+    assert_eq!(inventory.record("job-1", &first, &cfg()), 1);
+
+    let racked = inventory.stock()[0].clone();
+    let source = racked.source.as_ref().expect("offcut keeps provenance");
+    assert_eq!(source.stock_id, "maple-2400");
+    assert_eq!(source.board_index, 0);
+    assert_eq!(source.job, "job-1");
+    assert!(racked.length >= 900.0);
+    assert!(racked.cost.abs() < 1e-9);
+
+    let second_job = vec![LinearPart {
+        id: "rail".into(),
+        length: 450.0,
+        quantity: 1,
+    }];
+    let stock = inventory.stock_with(&fresh_stock());
+    let second = first_fit_boards(&second_job, &stock, &cfg()).unwrap();
+    assert_eq!(second.boards.len(), 1);
+    assert_eq!(second.boards[0].stock_id, racked.id);
+    assert!(second.boards[0].source.is_some());
+    assert!(second.cost.abs() < 1e-9);
+
+    inventory.record("job-2", &second, &cfg());
+    assert!(inventory.stock().iter().all(|s| s.id != racked.id));
+    assert!(
+        inventory
+            .stock()
+            .iter()
+            .all(|s| s.source.as_ref().unwrap().job == "job-2")
+    );
+}
+
+#[test]
+fn short_offcuts_are_not_racked() {
+    let mut inventory = OffcutInventory::new(1000.0);
+    let parts = vec![LinearPart {
+        id: "slat".into(),
+        length: 1500.0,
+        quantity: 1,
+    }];
+    let result = first_fit_boards(&parts, &fresh_stock(), &cfg()).unwrap();
+    assert_eq!(inventory.record("job-1", &result, &cfg()), 0);
+    assert!(inventory.is_empty());
+}
+
+#[test]
+fn optimal_uses_racked_offcuts_before_fresh_boards() {
+    let mut inventory = OffcutInventory::new(300.0);
+    let setup = vec![LinearPart {
+        id: "stile".into(),
+        length: 1800.0,
+        quantity: 2,
+    }];
+    let first = first_fit_boards(&setup, &fresh_stock(), &cfg()).unwrap();
+    assert_eq!(inventory.record("job-1", &first, &cfg()), 2);
+
+    let parts = vec![
+        LinearPart {
+            id: "cleat".into(),
+            length: 500.0,
+            quantity: 2,
+        },
+        LinearPart {
+            id: "rail".into(),
+            length: 2000.0,
+            quantity: 1,
+        },
+    ];
+    let stock = inventory.stock_with(&fresh_stock());
+    let result = optimal_boards(&parts, &stock, &cfg(), &SearchBudget::default()).unwrap();
+    let fresh = result.boards.iter().filter(|b| b.source.is_none()).count();
+    assert_eq!(fresh, 1);
+    assert_eq!(result.boards.len(), 3);
+}
+
+#[test]
+fn racked_offcuts_leave_the_freeing_kerf_and_skip_the_end_trims() {
+    let mut inventory = OffcutInventory::new(400.0);
+    let legs = vec![LinearPart {
+        id: "leg".into(),
+        length: 700.0,
+        quantity: 2,
+    }];
+    let first = first_fit_boards(&legs, &fresh_stock(), &cfg()).unwrap();
+    inventory.record("job-1", &first, &cfg());
+
+    // 2400 less the leading trim, two legs and their kerf, the freeing kerf and the end trim
+    let racked = inventory.stock()[0].clone();
+    assert!((racked.length - 974.0).abs() < 1e-9);
+
+    // a part the racked length exactly still fits, with no trim taken off again
+    let rail = vec![LinearPart {
+        id: "rail".into(),
+        length: 974.0,
+        quantity: 1,
+    }];
+    let second = first_fit_boards(&rail, &inventory.stock_with(&fresh_stock()), &cfg()).unwrap();
+    assert_eq!(second.boards[0].stock_id, racked.id);
+    assert!(second.boards[0].metrics.trim_loss.abs() < 1e-9);
+}
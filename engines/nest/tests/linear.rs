@@ -1,5 +1,7 @@
 use nest::{
-    LinearNestConfig, LinearNestResult, LinearPart, LinearStock, NestError, first_fit_boards,
+    LinearDefect, LinearNestConfig, LinearNestResult, LinearPart, LinearStock, NestError,
+    NestObjective, SearchBudget, UnplacedPart, UnplacedReason, first_fit_boards, optimal_boards,
+    partial_boards,
 };
 
 fn approx_eq(a: f64, b: f64) -> bool {
@@ -30,6 +32,7 @@ fn first_fit_accounts_for_kerf_and_trim() {
         id: "spruce-3m".into(),
         length: 3000.0,
         quantity: 2,
+        ..LinearStock::default()
     }];
 
     let config = LinearNestConfig {
@@ -37,6 +40,7 @@ fn first_fit_accounts_for_kerf_and_trim() {
         trim_leading: 10.0,
         trim_trailing: 12.0,
         seed: 11,
+        ..LinearNestConfig::default()
     };
 
     let result = first_fit_boards(&parts, &stock, &config).expect("nesting succeeds");
@@ -89,6 +93,7 @@ fn seed_controls_tie_breaks() {
         id: "beam".into(),
         length: 1205.0,
         quantity: 2,
+        ..LinearStock::default()
     }];
 
     let cfg_a = LinearNestConfig {
@@ -96,6 +101,7 @@ fn seed_controls_tie_breaks() {
         trim_leading: 0.0,
         trim_trailing: 0.0,
         seed: 1,
+        ..LinearNestConfig::default()
     };
     let cfg_b = LinearNestConfig {
         seed: 99,
@@ -129,14 +135,343 @@ fn insufficient_stock_errors() {
         id: "stick".into(),
         length: 1000.0,
         quantity: 2,
+        ..LinearStock::default()
     }];
     let cfg = LinearNestConfig {
         kerf: 1.0,
         trim_leading: 0.0,
         trim_trailing: 0.0,
         seed: 0,
+        ..LinearNestConfig::default()
     };
 
     let err = first_fit_boards(&parts, &stock, &cfg).expect_err("should fail");
     assert!(matches!(err, NestError::InsufficientStock));
 }
+
+fn part(id: &str, length: f64, quantity: usize) -> LinearPart {
+    LinearPart {
+        id: id.into(),
+        length,
+        quantity,
+    }
+}
+
+#[test]
+fn optimal_beats_first_fit_decreasing() {
+    let parts = vec![
+        part("stile", 500.0, 1),
+        part("rail", 400.0, 1),
+        part("slat", 300.0, 3),
+        part("cleat", 200.0, 1),
+    ];
+    let stock = vec![LinearStock {
+        id: "oak-1m".into(),
+        length: 1000.0,
+        quantity: 3,
+        ..LinearStock::default()
+    }];
+    let cfg = LinearNestConfig::default();
+
+    let greedy = first_fit_boards(&parts, &stock, &cfg).unwrap();
+    assert_eq!(greedy.boards.len(), 3);
+    assert!(greedy.bound.is_none());
+
+    let exact = optimal_boards(&parts, &stock, &cfg, &SearchBudget::default()).unwrap();
+    assert_eq!(exact.boards.len(), 2);
+    let bound = exact.bound.expect("exact solver reports a bound");
+    assert!(bound.proven_optimal);
+    assert!(approx_eq(bound.lower_bound, 2.0));
+    assert!(approx_eq(bound.gap, 0.0));
+    assert!(approx_eq(exact.metrics.utilized, 2000.0));
+}
+
+#[test]
+fn optimal_prefers_shorter_boards_on_equal_count() {
+    let parts = vec![part("leg", 700.0, 2)];
+    let stock = vec![
+        LinearStock {
+            id: "long".into(),
+            length: 2400.0,
+            quantity: 2,
+            ..LinearStock::default()
+        },
+        LinearStock {
+            id: "short".into(),
+            length: 1500.0,
+            quantity: 1,
+            ..LinearStock::default()
+        },
+    ];
+    let cfg = LinearNestConfig {
+        kerf: 3.0,
+        trim_leading: 5.0,
+        trim_trailing: 5.0,
+        ..LinearNestConfig::default()
+    };
+
+    let exact = optimal_boards(&parts, &stock, &cfg, &SearchBudget::default()).unwrap();
+    assert_eq!(exact.boards.len(), 1);
+    assert_eq!(exact.boards[0].stock_id, "short");
+    let cut = &exact.boards[0].cuts[1];
+    assert!(cut.start + cut.length <= 1500.0 - cfg.trim_trailing + 1e-6);
+}
+
+#[test]
+fn optimal_reports_gap_when_budget_runs_out() {
+    let parts = vec![
+        part("a", 430.0, 5),
+        part("b", 370.0, 5),
+        part("c", 260.0, 7),
+        part("d", 190.0, 6),
+    ];
+    let stock = vec![LinearStock {
+        id: "board".into(),
+        length: 1000.0,
+        quantity: 20,
+        ..LinearStock::default()
+    }];
+    let budget = SearchBudget {
+        max_nodes: 40,
+        time_limit: None,
+    };
+
+    let result = optimal_boards(&parts, &stock, &LinearNestConfig::default(), &budget).unwrap();
+    let bound = result.bound.unwrap();
+    assert!(!bound.proven_optimal);
+    assert!(bound.nodes <= 40);
+    let used = result.boards.len() as f64;
+    assert!(bound.lower_bound <= used);
+    assert!(approx_eq(bound.gap, (used - bound.lower_bound) / used));
+}
+
+#[test]
+fn optimal_fails_when_part_exceeds_stock() {
+    let parts = vec![part("beam", 1200.0, 1)];
+    let stock = vec![LinearStock {
+        id: "stick".into(),
+        length: 1000.0,
+        quantity: 4,
+        ..LinearStock::default()
+    }];
+    let err = optimal_boards(
+        &parts,
+        &stock,
+        &LinearNestConfig::default(),
+        &SearchBudget::default(),
+    )
+    .expect_err("should fail");
+    assert!(matches!(err, NestError::InsufficientStock));
+}
+
+fn yard_stock() -> Vec<LinearStock> {
+    vec![
+        LinearStock {
+            id: "12ft".into(),
+            length: 3658.0,
+            quantity: 4,
+            cost: 16.0,
+            ..LinearStock::default()
+        },
+        LinearStock {
+            id: "10ft".into(),
+            length: 3048.0,
+            quantity: 4,
+            cost: 12.0,
+            ..LinearStock::default()
+        },
+        LinearStock {
+            id: "8ft".into(),
+            length: 2438.0,
+            quantity: 4,
+            cost: 8.0,
+            ..LinearStock::default()
+        },
+    ]
+}
+
+#[test]
+fn objective_steers_stock_choice() {
+    let parts = vec![part("rail", 1150.0, 4)];
+    let stock = yard_stock();
+
+    let by_count = first_fit_boards(&parts, &stock, &LinearNestConfig::default()).unwrap();
+    assert_eq!(by_count.boards.len(), 2);
+    assert!(by_count.boards.iter().any(|b| b.stock_id == "12ft"));
+    assert!(approx_eq(by_count.cost, 24.0));
+
+    let cfg = LinearNestConfig {
+        objective: NestObjective::MinCost,
+        ..LinearNestConfig::default()
+    };
+    let by_cost = first_fit_boards(&parts, &stock, &cfg).unwrap();
+    assert!(by_cost.boards.iter().all(|b| b.stock_id == "8ft"));
+    assert!(approx_eq(by_cost.cost, 16.0));
+    assert!(approx_eq(
+        by_cost.cost,
+        by_cost.boards.iter().map(|b| b.cost).sum::<f64>()
+    ));
+
+    let cfg = LinearNestConfig {
+        objective: NestObjective::MinWaste,
+        ..LinearNestConfig::default()
+    };
+    let by_waste = first_fit_boards(&parts, &stock, &cfg).unwrap();
+    assert!(by_waste.metrics.stock_total <= by_count.metrics.stock_total);
+}
+
+#[test]
+fn min_cost_prices_the_whole_mix() {
+    // a short board and a long one cost more than two short ones
+    let parts = vec![part("slat", 50.0, 4)];
+    let stock = vec![
+        LinearStock {
+            id: "short".into(),
+            length: 100.0,
+            quantity: 4,
+            cost: 10.0,
+            ..LinearStock::default()
+        },
+        LinearStock {
+            id: "long".into(),
+            length: 150.0,
+            quantity: 4,
+            cost: 14.0,
+            ..LinearStock::default()
+        },
+    ];
+    let cfg = LinearNestConfig {
+        objective: NestObjective::MinCost,
+        ..LinearNestConfig::default()
+    };
+    let result = first_fit_boards(&parts, &stock, &cfg).unwrap();
+    assert!(result.boards.iter().all(|b| b.stock_id == "short"));
+    assert!(approx_eq(result.cost, 20.0));
+}
+
+#[test]
+fn optimal_minimises_cost() {
+    let parts = vec![part("rail", 1150.0, 4), part("block", 100.0, 2)];
+    let cfg = LinearNestConfig {
+        objective: NestObjective::MinCost,
+        ..LinearNestConfig::default()
+    };
+
+    let result = optimal_boards(&parts, &yard_stock(), &cfg, &SearchBudget::default()).unwrap();
+    let bound = result.bound.unwrap();
+    assert!(bound.proven_optimal);
+    assert!(approx_eq(result.cost, 16.0));
+    assert!(approx_eq(bound.lower_bound, result.cost));
+}
+
+fn knotty_board() -> Vec<LinearStock> {
+    vec![LinearStock {
+        id: "knotty".into(),
+        length: 2000.0,
+        quantity: 2,
+        defects: vec![LinearDefect {
+            start: 900.0,
+            length: 50.0,
+        }],
+        ..LinearStock::default()
+    }]
+}
+
+fn assert_clear_of_defects(result: &LinearNestResult, stock: &[LinearStock], kerf: f64) {
+    for board in &result.boards {
+        let defects = &stock
+            .iter()
+            .find(|s| s.id == board.stock_id)
+            .unwrap()
+            .defects;
+        for cut in &board.cuts {
+            for defect in defects {
+                let clear = cut.start + cut.length <= defect.start - kerf + 1e-6
+                    || cut.start >= defect.start + defect.length + kerf - 1e-6;
+                assert!(clear, "cut {cut:?} overlaps defect {defect:?}");
+            }
+        }
+        let m = &board.metrics;
+        let accounted = m.utilized + m.kerf_loss + m.trim_loss + m.offcut_loss + m.defect_loss;
+        assert!(approx_eq(accounted, m.stock_total));
+    }
+}
+
+#[test]
+fn first_fit_cuts_around_defects() {
+    let parts = vec![part("apron", 880.0, 2)];
+    let stock = knotty_board();
+    let cfg = LinearNestConfig {
+        kerf: 3.0,
+        ..LinearNestConfig::default()
+    };
+
+    let result = first_fit_boards(&parts, &stock, &cfg).unwrap();
+    assert_eq!(result.boards.len(), 1);
+    assert_clear_of_defects(&result, &stock, cfg.kerf);
+    assert!(approx_eq(result.metrics.defect_loss, 56.0));
+    assert!(approx_eq(result.boards[0].cuts[1].start, 953.0));
+    assert_eq!(result.boards[0].offcuts.len(), 2);
+}
+
+#[test]
+fn optimal_fills_clear_segments() {
+    let parts = vec![part("stile", 880.0, 1), part("rail", 500.0, 2)];
+    let stock = knotty_board();
+    let cfg = LinearNestConfig {
+        kerf: 3.0,
+        ..LinearNestConfig::default()
+    };
+
+    let result = optimal_boards(&parts, &stock, &cfg, &SearchBudget::default()).unwrap();
+    assert_eq!(result.boards.len(), 1);
+    assert_eq!(result.boards[0].cuts.len(), 3);
+    assert_clear_of_defects(&result, &stock, cfg.kerf);
+}
+
+#[test]
+fn partial_boards_report_unplaced_parts() {
+    let parts = vec![
+        part("rail", 1400.0, 3),
+        part("beam", 2600.0, 1),
+        part("stile", 500.0, 2),
+    ];
+    let stock = vec![LinearStock {
+        id: "oak-2m".into(),
+        length: 2000.0,
+        quantity: 2,
+        ..LinearStock::default()
+    }];
+    let cfg = LinearNestConfig {
+        kerf: 3.0,
+        ..LinearNestConfig::default()
+    };
+
+    let err = first_fit_boards(&parts, &stock, &cfg).expect_err("strict nest still fails");
+    assert!(matches!(err, NestError::InsufficientStock));
+
+    let expected = vec![
+        UnplacedPart {
+            part_id: "beam".into(),
+            quantity: 1,
+            reason: UnplacedReason::LargerThanAnyStock,
+        },
+        UnplacedPart {
+            part_id: "rail".into(),
+            quantity: 1,
+            reason: UnplacedReason::StockExhausted,
+        },
+    ];
+    let greedy = partial_boards(&parts, &stock, &cfg).expect("partial nest");
+    assert_eq!(greedy.boards.len(), 2);
+    assert_eq!(greedy.unplaced, expected);
+    assert_eq!(expected[0].reason.to_string(), "larger than any stock");
+
+    let cut: usize = greedy.boards.iter().map(|b| b.cuts.len()).sum();
+    assert_eq!(cut, 4);
+
+    // the exact search never hands back a heuristic nest in its place
+    let err = optimal_boards(&parts, &stock, &cfg, &SearchBudget::default())
+        .expect_err("no complete assignment");
+    assert!(matches!(err, NestError::InsufficientStock));
+}
@@ -0,0 +1,145 @@
+use nest::{LumberNestConfig, LumberPart, NestError, RoughBoard, rip_first_boards};
+
+fn rough_boards() -> Vec<RoughBoard> {
+    vec![
+        RoughBoard {
+            id: "walnut-1".into(),
+            width: 190.0,
+            length: 2400.0,
+        },
+        RoughBoard {
+            id: "walnut-2".into(),
+            width: 145.0,
+            length: 3100.0,
+        },
+        RoughBoard {
+            id: "walnut-3".into(),
+            width: 230.0,
+            length: 1800.0,
+        },
+    ]
+}
+
+fn cfg() -> LumberNestConfig {
+    LumberNestConfig {
+        rip_kerf: 3.0,
+        crosscut_kerf: 3.0,
+        edge_trim: 5.0,
+        trim_leading: 20.0,
+        trim_trailing: 20.0,
+        seed: 3,
+    }
+}
+
+#[test]
+fn rips_strips_and_crosscuts_parts_along_the_grain() {
+    let parts = vec![
+        LumberPart {
+            id: "apron".into(),
+            width: 90.0,
+            length: 1100.0,
+            quantity: 3,
+        },
+        LumberPart {
+            id: "stretcher".into(),
+            width: 60.0,
+            length: 700.0,
+            quantity: 4,
+        },
+        LumberPart {
+            id: "slat".into(),
+            width: 40.0,
+            length: 450.0,
+            quantity: 6,
+        },
+    ];
+    let boards = rough_boards();
+    let config = cfg();
+    let result = rip_first_boards(&parts, &boards, &config).expect("yield succeeds");
+
+    let placed: usize = result
+        .layouts
+        .iter()
+        .flat_map(|l| &l.strips)
+        .map(|s| s.board.cuts.len())
+        .sum();
+    assert_eq!(placed, 13);
+
+    for layout in &result.layouts {
+        let board = boards.iter().find(|b| b.id == layout.board_id).unwrap();
+        let mut edge = config.edge_trim;
+        for strip in &layout.strips {
+            assert!(strip.offset >= edge - 1e-6);
+            assert!(strip.offset + strip.width <= board.width - config.edge_trim + 1e-6);
+            edge = strip.offset + strip.width + config.rip_kerf;
+            for cut in &strip.board.cuts {
+                let part = parts.iter().find(|p| p.id == cut.part_id).unwrap();
+                assert!(part.width <= strip.width + 1e-9);
+                assert_eq!(cut.length, part.length);
+                assert!(cut.start + cut.length <= board.length - config.trim_trailing + 1e-6);
+            }
+        }
+        let m = &layout.metrics;
+        let accounted = m.utilized + m.kerf_loss + m.trim_loss + m.offcut_loss;
+        assert!((accounted - m.stock_total).abs() < 1e-6);
+    }
+    let part_area: f64 = parts
+        .iter()
+        .map(|p| p.width * p.length * p.quantity as f64)
+        .sum();
+    assert!((result.metrics.utilized - part_area).abs() < 1e-6);
+}
+
+#[test]
+fn fails_when_parts_are_wider_than_every_board() {
+    let parts = vec![LumberPart {
+        id: "top".into(),
+        width: 300.0,
+        length: 1000.0,
+        quantity: 1,
+    }];
+    let err = rip_first_boards(&parts, &rough_boards(), &cfg()).expect_err("should fail");
+    assert!(matches!(err, NestError::InsufficientStock));
+}
+
+#[test]
+fn narrower_parts_share_a_strip_only_with_room_for_the_second_rip() {
+    let boards = vec![
+        RoughBoard {
+            id: "oak-1".into(),
+            width: 100.0,
+            length: 2000.0,
+        },
+        RoughBoard {
+            id: "oak-2".into(),
+            width: 100.0,
+            length: 2000.0,
+        },
+    ];
+    let config = LumberNestConfig {
+        rip_kerf: 3.0,
+        ..LumberNestConfig::default()
+    };
+    let part = |id: &str, width: f64| LumberPart {
+        id: id.into(),
+        width,
+        length: 900.0,
+        quantity: 1,
+    };
+
+    // 85 plus the rip fits the 90 strip; the sliver is offcut and the rip is kerf
+    let shared = rip_first_boards(&[part("rail", 90.0), part("stile", 85.0)], &boards, &config)
+        .expect("yield succeeds");
+    assert_eq!(shared.layouts.len(), 1);
+    assert_eq!(shared.layouts[0].strips.len(), 1);
+    let m = &shared.layouts[0].metrics;
+    assert!(m.kerf_loss >= 3.0 * 900.0 - 1e-6);
+    assert!((m.utilized + m.kerf_loss + m.trim_loss + m.offcut_loss - m.stock_total).abs() < 1e-6);
+
+    // 88 plus the rip does not, so it gets its own strip
+    let apart = rip_first_boards(&[part("rail", 90.0), part("stile", 88.0)], &boards, &config)
+        .expect("yield succeeds");
+    for strip in apart.layouts.iter().flat_map(|l| &l.strips) {
+        assert_eq!(strip.board.cuts.len(), 1);
+    }
+}
@@ -0,0 +1,88 @@
+use nest::{
+    FaceGrade, PlanarNestConfig, PlanarStrategy, RectPart, SheetStock, UnplacedReason,
+    material_sheets, summarize_sheet_layouts,
+};
+
+fn part(id: &str, width: f64, height: f64, material: &str, thickness: f64) -> RectPart {
+    RectPart {
+        id: id.into(),
+        width,
+        height,
+        quantity: 2,
+        face: FaceGrade::B,
+        material: material.into(),
+        thickness,
+        ..RectPart::default()
+    }
+}
+
+fn sheet(id: &str, material: &str, thickness: f64) -> SheetStock {
+    SheetStock {
+        id: id.into(),
+        width: 2440.0,
+        height: 1220.0,
+        quantity: 3,
+        material: material.into(),
+        thickness,
+        ..SheetStock::default()
+    }
+}
+
+#[test]
+fn each_material_nests_on_its_own_stock() {
+    let parts = vec![
+        part("side", 720.0, 560.0, "birch", 18.0),
+        part("back", 720.0, 600.0, "birch", 6.0),
+        part("shelf", 560.0, 300.0, "birch", 18.0),
+        part("door", 716.0, 396.0, "mdf", 19.0),
+        part("drawer-base", 500.0, 400.0, "birch", 12.0),
+    ];
+    let stock = vec![
+        sheet("birch-18", "birch", 18.0),
+        sheet("birch-6", "birch", 6.0),
+        sheet("mdf-18", "mdf", 18.0),
+    ];
+    let config = PlanarNestConfig {
+        kerf: 3.0,
+        ..PlanarNestConfig::default()
+    };
+
+    let result = material_sheets(&parts, &stock, &config, PlanarStrategy::BestFit).unwrap();
+    let keys: Vec<(&str, f64)> = result
+        .groups
+        .iter()
+        .map(|g| (g.material.as_str(), g.thickness))
+        .collect();
+    assert_eq!(keys, vec![("birch", 18.0), ("birch", 6.0)]);
+
+    let birch = &result.groups[0];
+    assert!(birch.unplaced.is_empty());
+    assert!(birch.layouts.iter().all(|l| l.stock_id == "birch-18"));
+    let mut ids: Vec<&str> = birch
+        .layouts
+        .iter()
+        .flat_map(|l| l.placements.iter().map(|p| p.part_id.as_str()))
+        .collect();
+    ids.sort();
+    assert_eq!(ids, vec!["shelf", "shelf", "side", "side"]);
+    assert_eq!(birch.metrics, summarize_sheet_layouts(&birch.layouts));
+    assert!(
+        result.groups[1]
+            .layouts
+            .iter()
+            .all(|l| l.stock_id == "birch-6")
+    );
+
+    let unmatched: Vec<(&str, usize, UnplacedReason)> = result
+        .unmatched
+        .iter()
+        .map(|u| (u.part_id.as_str(), u.quantity, u.reason))
+        .collect();
+    assert_eq!(
+        unmatched,
+        vec![
+            ("door", 2, UnplacedReason::NoMatchingStock),
+            ("drawer-base", 2, UnplacedReason::NoMatchingStock),
+        ]
+    );
+}
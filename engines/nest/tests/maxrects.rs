@@ -0,0 +1,135 @@
+use nest::{
+    FaceGrade, GrainDirection, MaxRectsHeuristic, PlanarNestConfig, RectPart, SheetLayout,
+    SheetStock, best_fit_sheets, max_rects_sheets,
+};
+
+const HEURISTICS: [MaxRectsHeuristic; 5] = [
+    MaxRectsHeuristic::BestShortSideFit,
+    MaxRectsHeuristic::BestLongSideFit,
+    MaxRectsHeuristic::BestAreaFit,
+    MaxRectsHeuristic::BottomLeft,
+    MaxRectsHeuristic::ContactPoint,
+];
+
+fn mixed_parts() -> Vec<RectPart> {
+    let sizes = [
+        ("door", 596.0, 716.0, 4, GrainDirection::AlongY),
+        ("drawer-front", 596.0, 176.0, 6, GrainDirection::AlongX),
+        ("carcass-side", 560.0, 720.0, 4, GrainDirection::Either),
+        ("back", 764.0, 420.0, 3, GrainDirection::Either),
+        ("filler", 80.0, 720.0, 5, GrainDirection::Either),
+    ];
+    sizes
+        .into_iter()
+        .map(|(id, width, height, quantity, grain)| RectPart {
+            id: id.into(),
+            width,
+            height,
+            quantity,
+            grain,
+            face: FaceGrade::B,
+            ..RectPart::default()
+        })
+        .collect()
+}
+
+fn stock() -> Vec<SheetStock> {
+    vec![SheetStock {
+        id: "mdf-2800".into(),
+        width: 2800.0,
+        height: 2070.0,
+        quantity: 6,
+        ..SheetStock::default()
+    }]
+}
+
+fn assert_valid(layout: &SheetLayout, sheet: &SheetStock, config: &PlanarNestConfig) {
+    let k = config.kerf;
+    for (i, a) in layout.placements.iter().enumerate() {
+        assert!(a.x >= config.trim - 1e-6 && a.y >= config.trim - 1e-6);
+        assert!(a.x + a.width <= sheet.width - config.trim + 1e-6);
+        assert!(a.y + a.height <= sheet.height - config.trim + 1e-6);
+        for b in &layout.placements[i + 1..] {
+            let apart = a.x + a.width + k <= b.x + 1e-6
+                || b.x + b.width + k <= a.x + 1e-6
+                || a.y + a.height + k <= b.y + 1e-6
+                || b.y + b.height + k <= a.y + 1e-6;
+            assert!(apart, "{a:?} and {b:?} closer than a kerf");
+        }
+        for o in &layout.offcuts {
+            let apart = a.x + a.width <= o.x + 1e-6
+                || o.x + o.width <= a.x + 1e-6
+                || a.y + a.height <= o.y + 1e-6
+                || o.y + o.height <= a.y + 1e-6;
+            assert!(apart, "offcut {o:?} overlaps {a:?}");
+        }
+    }
+    let m = &layout.metrics;
+    assert!((m.utilized + m.kerf_loss + m.trim_loss + m.offcut_loss - m.stock_total).abs() < 1e-6);
+    let offcut_area: f64 = layout.offcuts.iter().map(|o| o.width * o.height).sum();
+    assert!((offcut_area - m.offcut_loss).abs() < 1e-3);
+}
+
+#[test]
+fn every_heuristic_packs_a_valid_layout() {
+    let config = PlanarNestConfig {
+        kerf: 4.0,
+        trim: 8.0,
+        seed: 17,
+        ..PlanarNestConfig::default()
+    };
+    let sheet = &stock()[0];
+    for heuristic in HEURISTICS {
+        let layouts = max_rects_sheets(&mixed_parts(), &stock(), &config, heuristic)
+            .unwrap_or_else(|err| panic!("{heuristic:?}: {err}"));
+        let placed: usize = layouts.iter().map(|l| l.placements.len()).sum();
+        assert_eq!(placed, 22, "{heuristic:?}");
+        for layout in &layouts {
+            assert_valid(layout, sheet, &config);
+            for p in &layout.placements {
+                if p.part_id == "door" || p.part_id == "drawer-front" {
+                    assert!(!p.rotated);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn max_rects_reuses_space_the_split_packer_loses() {
+    // the split packer only leaves a strip as wide as the slab above it
+    let parts = vec![
+        RectPart {
+            id: "slab".into(),
+            width: 600.0,
+            height: 600.0,
+            quantity: 1,
+            grain: GrainDirection::AlongX,
+            face: FaceGrade::B,
+            ..RectPart::default()
+        },
+        RectPart {
+            id: "rail".into(),
+            width: 800.0,
+            height: 400.0,
+            quantity: 1,
+            grain: GrainDirection::AlongX,
+            face: FaceGrade::B,
+            ..RectPart::default()
+        },
+    ];
+    let stock = vec![SheetStock {
+        id: "square".into(),
+        width: 1000.0,
+        height: 1000.0,
+        quantity: 3,
+        ..SheetStock::default()
+    }];
+    let config = PlanarNestConfig::default();
+
+    let split = best_fit_sheets(&parts, &stock, &config).expect("split packer");
+    let max_rects = max_rects_sheets(&parts, &stock, &config, MaxRectsHeuristic::BestShortSideFit)
+        .expect("max rects");
+    assert_eq!(split.len(), 2);
+    assert_eq!(max_rects.len(), 1);
+}
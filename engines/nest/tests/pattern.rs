@@ -0,0 +1,64 @@
+use nest::{
+    FaceGrade, GrainDirection, PlanarNestConfig, RectPart, SheetStock, best_fit_sheets,
+    sheet_patterns,
+};
+
+fn stock() -> Vec<SheetStock> {
+    vec![SheetStock {
+        id: "sheet".into(),
+        width: 1000.0,
+        height: 1000.0,
+        quantity: 6,
+        grain: GrainDirection::AlongX,
+        ..SheetStock::default()
+    }]
+}
+
+fn part(id: &str, width: f64, height: f64, quantity: usize) -> RectPart {
+    RectPart {
+        id: id.into(),
+        width,
+        height,
+        quantity,
+        grain: GrainDirection::AlongX,
+        face: FaceGrade::B,
+        ..RectPart::default()
+    }
+}
+
+#[test]
+fn repeated_sheets_collapse_into_one_pattern() {
+    let parts = vec![part("side", 1000.0, 500.0, 6), part("top", 600.0, 400.0, 1)];
+    let layouts =
+        best_fit_sheets(&parts, &stock(), &PlanarNestConfig::default()).expect("nest succeeds");
+    assert_eq!(layouts.len(), 4);
+
+    let patterns = sheet_patterns(&layouts);
+
+    assert_eq!(patterns.len(), 2);
+    assert_eq!(patterns[0].count, 3);
+    assert_eq!(patterns[0].indices, vec![0, 1, 2]);
+    assert!(
+        patterns[0]
+            .layout
+            .placements
+            .iter()
+            .all(|p| p.part_id == "side")
+    );
+    assert_eq!(patterns[1].count, 1);
+    assert_eq!(patterns[1].indices, vec![3]);
+}
+
+#[test]
+fn placement_order_does_not_split_a_pattern() {
+    let parts = vec![part("side", 1000.0, 500.0, 4)];
+    let mut layouts =
+        best_fit_sheets(&parts, &stock(), &PlanarNestConfig::default()).expect("nest succeeds");
+    layouts[1].placements.reverse();
+
+    assert_eq!(sheet_patterns(&layouts).len(), 1);
+
+    // the same parts elsewhere on the sheet are another pattern
+    layouts[1].placements[0].x += 1.0;
+    assert_eq!(sheet_patterns(&layouts).len(), 2);
+}
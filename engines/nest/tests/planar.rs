@@ -1,6 +1,8 @@
 use nest::{
-    GrainDirection, PlanarNestConfig, RectPart, SheetStock, best_fit_sheets, skyline_sheets,
-    summarize_sheet_layouts,
+    CutAxis, CutSegment, FaceGrade, GrainDirection, GuillotineConfig, MaxRectsHeuristic, NestError,
+    NestObjective, PartEdges, PlanarNestConfig, PlanarStrategy, RectPart, SheetStock,
+    UnplacedReason, best_fit_sheets, partial_sheets, skyline_sheets, summarize_sheet_layouts,
+    total_sheet_cost,
 };
 
 fn sheet_fixture() -> Vec<SheetStock> {
@@ -9,6 +11,7 @@ fn sheet_fixture() -> Vec<SheetStock> {
         width: 2438.4,
         height: 1219.2,
         quantity: 2,
+        ..SheetStock::default()
     }]
 }
 
@@ -20,6 +23,7 @@ fn grain_constraints_prevent_rotation() {
         height: 300.0,
         quantity: 2,
         grain: GrainDirection::AlongX,
+        ..RectPart::default()
     }];
 
     let layouts = best_fit_sheets(
@@ -29,6 +33,7 @@ fn grain_constraints_prevent_rotation() {
             kerf: 3.0,
             trim: 6.0,
             seed: 123,
+            ..PlanarNestConfig::default()
         },
     )
     .expect("nest succeeds");
@@ -44,6 +49,7 @@ fn skyline_allows_rotation_when_either() {
         height: 600.0,
         quantity: 4,
         grain: GrainDirection::Either,
+        ..RectPart::default()
     }];
 
     let layouts = skyline_sheets(
@@ -53,6 +59,7 @@ fn skyline_allows_rotation_when_either() {
             kerf: 2.0,
             trim: 10.0,
             seed: 99,
+            ..PlanarNestConfig::default()
         },
     )
     .expect("nest succeeds");
@@ -73,6 +80,7 @@ fn deterministic_with_seed() {
             height: 400.0,
             quantity: 1,
             grain: GrainDirection::Either,
+            ..RectPart::default()
         })
         .collect();
 
@@ -82,6 +90,7 @@ fn deterministic_with_seed() {
         kerf: 1.5,
         trim: 5.0,
         seed: 4,
+        ..PlanarNestConfig::default()
     };
     let cfg_b = PlanarNestConfig { seed: 10, ..cfg_a };
 
@@ -104,12 +113,14 @@ fn utilization_breakdown_reports_totals() {
         height: 400.0,
         quantity: 5,
         grain: GrainDirection::Either,
+        ..RectPart::default()
     }];
     let stock = sheet_fixture();
     let cfg = PlanarNestConfig {
         kerf: 1.0,
         trim: 8.0,
         seed: 7,
+        ..PlanarNestConfig::default()
     };
 
     let layouts = best_fit_sheets(&parts, &stock, &cfg).unwrap();
@@ -119,3 +130,392 @@ fn utilization_breakdown_reports_totals() {
     assert!(summary.utilized > 0.0);
     assert!(summary.efficiency() > 0.25);
 }
+
+#[test]
+fn objective_picks_cheaper_sheet_mix() {
+    let parts = vec![RectPart {
+        id: "door".into(),
+        width: 1100.0,
+        height: 1100.0,
+        quantity: 2,
+        face: FaceGrade::B,
+        ..RectPart::default()
+    }];
+    let stock = vec![
+        SheetStock {
+            id: "full".into(),
+            width: 2440.0,
+            height: 1220.0,
+            quantity: 2,
+            cost: 40.0,
+            ..SheetStock::default()
+        },
+        SheetStock {
+            id: "half".into(),
+            width: 1220.0,
+            height: 1220.0,
+            quantity: 2,
+            cost: 18.0,
+            ..SheetStock::default()
+        },
+    ];
+
+    for solver in [best_fit_sheets, skyline_sheets] {
+        let by_count = solver(&parts, &stock, &PlanarNestConfig::default()).unwrap();
+        assert_eq!(by_count.len(), 1);
+        assert_eq!(by_count[0].stock_id, "full");
+        assert!((total_sheet_cost(&by_count) - 40.0).abs() < 1e-9);
+
+        let cfg = PlanarNestConfig {
+            objective: NestObjective::MinCost,
+            ..PlanarNestConfig::default()
+        };
+        let by_cost = solver(&parts, &stock, &cfg).unwrap();
+        assert_eq!(by_cost.len(), 2);
+        assert!(by_cost.iter().all(|layout| layout.stock_id == "half"));
+        assert!((total_sheet_cost(&by_cost) - 36.0).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn min_cost_prices_the_whole_mix() {
+    // one wide sheet and one narrow one cost more than two narrow ones
+    let parts = vec![RectPart {
+        id: "slat".into(),
+        width: 50.0,
+        height: 100.0,
+        quantity: 4,
+        grain: GrainDirection::AlongY,
+        ..RectPart::default()
+    }];
+    let stock = vec![
+        SheetStock {
+            id: "narrow".into(),
+            width: 100.0,
+            height: 100.0,
+            quantity: 4,
+            cost: 10.0,
+            ..SheetStock::default()
+        },
+        SheetStock {
+            id: "wide".into(),
+            width: 150.0,
+            height: 100.0,
+            quantity: 4,
+            cost: 14.0,
+            ..SheetStock::default()
+        },
+    ];
+    let cfg = PlanarNestConfig {
+        objective: NestObjective::MinCost,
+        ..PlanarNestConfig::default()
+    };
+    for strategy in [
+        PlanarStrategy::BestFit,
+        PlanarStrategy::Skyline,
+        PlanarStrategy::Guillotine(GuillotineConfig::default()),
+        PlanarStrategy::MaxRects(MaxRectsHeuristic::BestAreaFit),
+    ] {
+        let result = partial_sheets(&parts, &stock, &cfg, strategy).unwrap();
+        assert!(result.unplaced.is_empty());
+        assert!(
+            (total_sheet_cost(&result.layouts) - 20.0).abs() < 1e-9,
+            "{strategy:?}"
+        );
+    }
+}
+
+#[test]
+fn partial_sheets_keep_layouts_and_list_leftovers() {
+    let parts = vec![
+        RectPart {
+            id: "side".into(),
+            width: 2400.0,
+            height: 1200.0,
+            quantity: 3,
+            grain: GrainDirection::AlongX,
+            face: FaceGrade::B,
+            ..RectPart::default()
+        },
+        RectPart {
+            id: "table-top".into(),
+            width: 3000.0,
+            height: 900.0,
+            quantity: 1,
+            face: FaceGrade::B,
+            ..RectPart::default()
+        },
+    ];
+    let stock = sheet_fixture();
+    let cfg = PlanarNestConfig {
+        kerf: 3.0,
+        trim: 5.0,
+        ..PlanarNestConfig::default()
+    };
+
+    let err = best_fit_sheets(&parts, &stock, &cfg).expect_err("strict mode fails");
+    assert!(matches!(err, NestError::InsufficientStock));
+
+    for strategy in [PlanarStrategy::BestFit, PlanarStrategy::Skyline] {
+        let result = partial_sheets(&parts, &stock, &cfg, strategy).expect("partial nest");
+        assert_eq!(result.layouts.len(), 2);
+        assert!(result.layouts.iter().all(|l| l.placements.len() == 1));
+        let reasons: Vec<(&str, usize, UnplacedReason)> = result
+            .unplaced
+            .iter()
+            .map(|u| (u.part_id.as_str(), u.quantity, u.reason))
+            .collect();
+        assert!(reasons.contains(&("side", 1, UnplacedReason::StockExhausted)));
+        assert!(reasons.contains(&("table-top", 1, UnplacedReason::LargerThanAnyStock)));
+    }
+}
+
+#[test]
+fn part_grain_follows_sheet_grain_and_show_faces_land_on_the_good_side() {
+    let parts = vec![
+        RectPart {
+            id: "rail".into(),
+            width: 900.0,
+            height: 100.0,
+            quantity: 2,
+            grain: GrainDirection::AlongX,
+            ..RectPart::default()
+        },
+        RectPart {
+            id: "stile".into(),
+            width: 100.0,
+            height: 900.0,
+            quantity: 2,
+            grain: GrainDirection::AlongY,
+            face: FaceGrade::B,
+            ..RectPart::default()
+        },
+    ];
+    // grain up the sheet, good face on the underside
+    let stock = vec![SheetStock {
+        grain: GrainDirection::AlongY,
+        top_face: FaceGrade::B,
+        bottom_face: FaceGrade::A,
+        ..sheet_fixture().remove(0)
+    }];
+    let cfg = PlanarNestConfig {
+        kerf: 3.0,
+        ..PlanarNestConfig::default()
+    };
+    for strategy in [
+        PlanarStrategy::BestFit,
+        PlanarStrategy::Skyline,
+        PlanarStrategy::MaxRects(MaxRectsHeuristic::BestAreaFit),
+    ] {
+        let result = partial_sheets(&parts, &stock, &cfg, strategy).expect("nest");
+        assert!(result.unplaced.is_empty());
+        for p in result.layouts.iter().flat_map(|l| &l.placements) {
+            // both run their grain up the sheet
+            assert!(p.height > p.width, "{strategy:?}: {p:?}");
+            assert_eq!(p.rotated, p.part_id == "rail");
+            assert_eq!(p.flipped, p.part_id == "rail");
+        }
+    }
+
+    let poor = vec![SheetStock {
+        top_face: FaceGrade::B,
+        bottom_face: FaceGrade::B,
+        ..sheet_fixture().remove(0)
+    }];
+    let result = partial_sheets(&parts, &poor, &cfg, PlanarStrategy::BestFit).expect("nest");
+    assert_eq!(result.unplaced.len(), 1);
+    assert_eq!(result.unplaced[0].part_id, "rail");
+    assert_eq!(result.unplaced[0].reason, UnplacedReason::NoSuitableFace);
+    let stiles = &result.layouts[0].placements;
+    assert!(stiles.iter().all(|p| !p.flipped && !p.rotated));
+}
+
+#[test]
+fn banding_and_allowances_set_the_cut_size() {
+    let parts = vec![RectPart {
+        id: "door".into(),
+        width: 600.0,
+        height: 400.0,
+        quantity: 2,
+        grain: GrainDirection::AlongX,
+        face: FaceGrade::B,
+        // banded on both long edges and the top, bottom edge milled after cutting
+        banding: PartEdges {
+            left: 2.0,
+            right: 2.0,
+            bottom: 0.0,
+            top: 1.0,
+        },
+        allowance: PartEdges {
+            bottom: 1.5,
+            ..PartEdges::default()
+        },
+        ..RectPart::default()
+    }];
+    let stock = vec![SheetStock {
+        grain: GrainDirection::AlongY,
+        ..sheet_fixture().remove(0)
+    }];
+    let cfg = PlanarNestConfig {
+        kerf: 3.0,
+        ..PlanarNestConfig::default()
+    };
+    for strategy in [
+        PlanarStrategy::BestFit,
+        PlanarStrategy::Skyline,
+        PlanarStrategy::Guillotine(GuillotineConfig::default()),
+        PlanarStrategy::MaxRects(MaxRectsHeuristic::BestAreaFit),
+    ] {
+        let result = partial_sheets(&parts, &stock, &cfg, strategy).expect("nest");
+        assert!(result.unplaced.is_empty());
+        for p in &result.layouts[0].placements {
+            assert!(p.rotated, "{strategy:?}");
+            assert_eq!((p.width, p.height), (400.5, 596.0), "{strategy:?}");
+            assert_eq!((p.finished_width, p.finished_height), (400.0, 600.0));
+        }
+    }
+
+    let mut thin = parts.clone();
+    thin[0].banding.left = 300.0;
+    thin[0].banding.right = 300.0;
+    let err = best_fit_sheets(&thin, &stock, &cfg).expect_err("nothing left to cut");
+    assert!(matches!(err, NestError::InvalidDimension(_)));
+}
+
+#[test]
+fn kerf_loss_is_the_area_of_the_cut_segments() {
+    let parts = vec![RectPart {
+        id: "panel".into(),
+        width: 1000.0,
+        height: 500.0,
+        quantity: 1,
+        grain: GrainDirection::AlongX,
+        face: FaceGrade::B,
+        ..RectPart::default()
+    }];
+    let cfg = PlanarNestConfig {
+        kerf: 4.0,
+        ..PlanarNestConfig::default()
+    };
+    for strategy in [
+        PlanarStrategy::BestFit,
+        PlanarStrategy::Skyline,
+        PlanarStrategy::MaxRects(MaxRectsHeuristic::BestAreaFit),
+    ] {
+        let layout = partial_sheets(&parts, &sheet_fixture(), &cfg, strategy)
+            .unwrap()
+            .layouts
+            .remove(0);
+        // the sheet edges free the bottom and left; the cuts up the right and along the top
+        // run a kerf past the corner and cross there once
+        assert_eq!(
+            layout.segments,
+            vec![
+                CutSegment {
+                    axis: CutAxis::AlongX,
+                    position: 500.0,
+                    start: 0.0,
+                    end: 1004.0,
+                    kerf: 4.0,
+                },
+                CutSegment {
+                    axis: CutAxis::AlongY,
+                    position: 1000.0,
+                    start: 0.0,
+                    end: 504.0,
+                    kerf: 4.0,
+                },
+            ],
+            "{strategy:?}"
+        );
+        assert!((layout.metrics.kerf_loss - (4.0 * 1004.0 + 4.0 * 504.0 - 16.0)).abs() < 1e-6);
+    }
+
+    let layout = partial_sheets(
+        &parts,
+        &sheet_fixture(),
+        &cfg,
+        PlanarStrategy::Guillotine(GuillotineConfig::default()),
+    )
+    .unwrap()
+    .layouts
+    .remove(0);
+    assert_eq!(layout.segments.len(), layout.cuts.len());
+    let cut_kerf: f64 = layout.cuts.iter().map(|c| (c.end - c.start) * c.kerf).sum();
+    assert!((layout.metrics.kerf_loss - cut_kerf).abs() < 1e-6);
+}
+
+fn square_sheet() -> Vec<SheetStock> {
+    vec![SheetStock {
+        id: "square".into(),
+        width: 1000.0,
+        height: 1000.0,
+        quantity: 2,
+        ..SheetStock::default()
+    }]
+}
+
+fn fixed_part(id: &str, width: f64, height: f64, quantity: usize) -> RectPart {
+    RectPart {
+        id: id.into(),
+        width,
+        height,
+        quantity,
+        grain: GrainDirection::AlongX,
+        face: FaceGrade::B,
+        ..RectPart::default()
+    }
+}
+
+#[test]
+fn skyline_stacks_parts_beside_a_taller_one() {
+    // a shelf as tall as the stile holds only one panel beside it
+    let parts = vec![
+        fixed_part("stile", 400.0, 1000.0, 1),
+        fixed_part("panel", 598.0, 499.0, 2),
+    ];
+    let config = PlanarNestConfig {
+        kerf: 2.0,
+        trim: 0.0,
+        ..PlanarNestConfig::default()
+    };
+
+    let layouts = skyline_sheets(&parts, &square_sheet(), &config).expect("nest succeeds");
+
+    assert_eq!(layouts.len(), 1);
+    let mut panels: Vec<(f64, f64)> = layouts[0]
+        .placements
+        .iter()
+        .filter(|p| p.part_id == "panel")
+        .map(|p| (p.x, p.y))
+        .collect();
+    panels.sort_by(|a, b| a.1.total_cmp(&b.1));
+    assert_eq!(panels, vec![(402.0, 0.0), (402.0, 501.0)]);
+}
+
+#[test]
+fn skyline_fills_the_waste_under_a_raised_part() {
+    let parts = vec![
+        fixed_part("post", 400.0, 800.0, 1),
+        fixed_part("rail", 1000.0, 198.0, 1),
+        fixed_part("block", 300.0, 100.0, 1),
+    ];
+    let config = PlanarNestConfig {
+        kerf: 2.0,
+        trim: 0.0,
+        ..PlanarNestConfig::default()
+    };
+
+    let layouts = skyline_sheets(&parts, &square_sheet(), &config).expect("nest succeeds");
+
+    assert_eq!(layouts.len(), 1);
+    let find = |id: &str| {
+        layouts[0]
+            .placements
+            .iter()
+            .find(|p| p.part_id == id)
+            .expect("part placed")
+    };
+    assert_eq!((find("rail").x, find("rail").y), (0.0, 802.0));
+    assert_eq!((find("block").x, find("block").y), (402.0, 0.0));
+}
@@ -0,0 +1,133 @@
+use nest::{
+    FaceGrade, GrainDirection, PlanarNestConfig, RectPart, SheetInventory, SheetStock,
+    best_fit_sheets, extract_remnants,
+};
+
+fn part(id: &str, width: f64, height: f64, quantity: usize) -> RectPart {
+    RectPart {
+        id: id.into(),
+        width,
+        height,
+        quantity,
+        face: FaceGrade::B,
+        ..RectPart::default()
+    }
+}
+
+fn stock() -> Vec<SheetStock> {
+    vec![SheetStock {
+        id: "birch".into(),
+        width: 2440.0,
+        height: 1220.0,
+        quantity: 4,
+        cost: 55.0,
+        grain: GrainDirection::AlongX,
+        bottom_face: FaceGrade::B,
+        ..SheetStock::default()
+    }]
+}
+
+fn config() -> PlanarNestConfig {
+    PlanarNestConfig {
+        kerf: 4.0,
+        trim: 5.0,
+        ..PlanarNestConfig::default()
+    }
+}
+
+#[test]
+fn remnants_clear_the_parts_and_each_other() {
+    let parts = vec![
+        part("top", 1200.0, 600.0, 1),
+        part("shelf", 800.0, 300.0, 2),
+    ];
+    let layouts = best_fit_sheets(&parts, &stock(), &config()).unwrap();
+    assert_eq!(layouts.len(), 1);
+
+    let remnants = extract_remnants(&layouts[0], &stock()[0], &config(), 300.0, 200.0);
+    assert!(!remnants.is_empty());
+    let clear = |ax: f64, aw: f64, bx: f64, bw: f64| {
+        ax + aw + 4.0 <= bx + 1e-6 || bx + bw + 4.0 <= ax + 1e-6
+    };
+    for (idx, r) in remnants.iter().enumerate() {
+        assert!(r.width.min(r.height) >= 200.0 && r.width.max(r.height) >= 300.0);
+        assert!(r.x >= 5.0 && r.y >= 5.0);
+        assert!(r.x + r.width <= 2435.0 + 1e-6 && r.y + r.height <= 1215.0 + 1e-6);
+        for p in &layouts[0].placements {
+            assert!(
+                clear(r.x, r.width, p.x, p.width) || clear(r.y, r.height, p.y, p.height),
+                "{r:?} touches {p:?}"
+            );
+        }
+        for other in &remnants[idx + 1..] {
+            assert!(
+                clear(r.x, r.width, other.x, other.width)
+                    || clear(r.y, r.height, other.y, other.height)
+            );
+            assert!(r.width * r.height >= other.width * other.height);
+        }
+    }
+}
+
+#[test]
+fn racked_remnants_are_used_before_fresh_sheets() {
+    let mut inventory = SheetInventory::new(300.0, 200.0);
+    let first = best_fit_sheets(&[part("top", 1800.0, 1000.0, 1)], &stock(), &config()).unwrap();
+    let added = inventory.record("job-1", &first, &stock(), &config());
+    assert!(added > 0);
+    assert_eq!(inventory.len(), added);
+    for remnant in inventory.stock() {
+        assert_eq!(remnant.cost, 0.0);
+        assert_eq!(remnant.grain, GrainDirection::AlongX);
+        assert_eq!(remnant.bottom_face, FaceGrade::B);
+    }
+
+    let supply = inventory.stock_with(&stock());
+    let second = best_fit_sheets(&[part("rail", 300.0, 150.0, 1)], &supply, &config()).unwrap();
+    assert_eq!(second.len(), 1);
+    let used = second[0].stock_id.clone();
+    assert!(inventory.stock().iter().any(|s| s.id == used));
+    assert_eq!(second[0].cost, 0.0);
+    let source = second[0].source.as_ref().expect("came from a remnant");
+    assert_eq!(source.stock_id, "birch");
+    assert_eq!(source.job, "job-1");
+
+    inventory.record("job-2", &second, &supply, &config());
+    assert!(inventory.stock().iter().all(|s| s.id != used));
+}
+
+#[test]
+fn remnants_never_close_a_pinwheel_with_the_parts() {
+    let sheet = SheetStock {
+        width: 1000.0,
+        height: 1000.0,
+        ..stock()[0].clone()
+    };
+    let config = PlanarNestConfig::default();
+    let mut layout = best_fit_sheets(
+        &[part("base", 700.0, 300.0, 1)],
+        std::slice::from_ref(&sheet),
+        &config,
+    )
+    .unwrap()
+    .remove(0);
+    // the free corners right of the base and left of the lid are both maximal; taking both
+    // would leave no cut across the sheet
+    let at = |x: f64, y: f64, width: f64, height: f64| nest::RectPlacement {
+        x,
+        y,
+        width,
+        height,
+        ..layout.placements[0].clone()
+    };
+    layout.placements = vec![
+        at(0.0, 0.0, 700.0, 300.0),
+        at(300.0, 300.0, 400.0, 400.0),
+        at(300.0, 700.0, 700.0, 300.0),
+    ];
+
+    let remnants = extract_remnants(&layout, &sheet, &config, 200.0, 200.0);
+    assert_eq!(remnants.len(), 1);
+    let r = &remnants[0];
+    assert!((r.width - 300.0).abs() < 1e-6 && (r.height - 700.0).abs() < 1e-6);
+}
@@ -0,0 +1,145 @@
+use nest::{
+    CutAxis, FaceGrade, GuillotineConfig, MaxRectsHeuristic, PlanarNestConfig, PlanarStrategy,
+    RectPart, RectPlacement, SheetStock, partial_sheets, renest_sheets, verify_sheets,
+};
+
+fn part(id: &str, width: f64, height: f64, quantity: usize) -> RectPart {
+    RectPart {
+        id: id.into(),
+        width,
+        height,
+        quantity,
+        face: FaceGrade::B,
+        ..RectPart::default()
+    }
+}
+
+fn stock() -> Vec<SheetStock> {
+    vec![SheetStock {
+        id: "ply".into(),
+        width: 2440.0,
+        height: 1220.0,
+        quantity: 3,
+        ..SheetStock::default()
+    }]
+}
+
+fn apart(a: &RectPlacement, b: &RectPlacement, kerf: f64) -> bool {
+    a.x + a.width + kerf <= b.x + 1e-6
+        || b.x + b.width + kerf <= a.x + 1e-6
+        || a.y + a.height + kerf <= b.y + 1e-6
+        || b.y + b.height + kerf <= a.y + 1e-6
+}
+
+#[test]
+fn new_parts_fill_around_locked_ones_before_fresh_sheets() {
+    let config = PlanarNestConfig {
+        kerf: 3.0,
+        trim: 5.0,
+        ..PlanarNestConfig::default()
+    };
+    for strategy in [
+        PlanarStrategy::BestFit,
+        PlanarStrategy::Skyline,
+        PlanarStrategy::Guillotine(GuillotineConfig::default()),
+        PlanarStrategy::MaxRects(MaxRectsHeuristic::BestAreaFit),
+    ] {
+        let first = partial_sheets(
+            &[part("side", 1000.0, 600.0, 2)],
+            &stock(),
+            &config,
+            strategy,
+        )
+        .unwrap();
+        assert_eq!(first.layouts.len(), 1);
+        let mut existing = first.layouts.clone();
+        // one side is cut already, the other changes size
+        existing[0].placements[0].locked = true;
+        let kept = existing[0].placements[0].clone();
+
+        let parts = vec![
+            part("side", 1000.0, 650.0, 1),
+            part("shelf", 900.0, 300.0, 2),
+        ];
+        let result = renest_sheets(&existing, &parts, &stock(), &config, strategy).unwrap();
+        assert!(result.unplaced.is_empty());
+        assert_eq!(result.layouts.len(), 1, "{strategy:?}");
+        let sheet = &result.layouts[0];
+        assert_eq!(sheet.placements[0], kept);
+        assert_eq!(sheet.placements.len(), 4);
+        for (idx, a) in sheet.placements.iter().enumerate() {
+            assert_eq!(a.locked, idx == 0);
+            for b in &sheet.placements[idx + 1..] {
+                assert!(apart(a, b, 3.0), "{strategy:?}: {a:?} {b:?}");
+            }
+        }
+        let area: f64 = sheet.placements.iter().map(|p| p.width * p.height).sum();
+        assert!((sheet.metrics.utilized - area).abs() < 1e-6);
+        assert!(sheet.metrics.defect_loss.abs() < 1e-6);
+
+        // more than the rest of the sheet holds opens the next one
+        let parts = vec![part("top", 2000.0, 1000.0, 1)];
+        let result = renest_sheets(&existing, &parts, &stock(), &config, strategy).unwrap();
+        assert_eq!(result.layouts.len(), 2);
+        assert_eq!(result.layouts[0].placements, vec![kept.clone()]);
+        assert_eq!(result.layouts[1].index, 1);
+    }
+}
+
+#[test]
+fn no_saw_pass_runs_through_a_locked_part() {
+    // a small linear congruential stream so every job is the same on every run
+    let mut state = 11u64;
+    let mut next = |span: u64| {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) % span
+    };
+    let guillotine = |stages, first_cut| GuillotineConfig { stages, first_cut };
+    for job in 0..40 {
+        let config = PlanarNestConfig {
+            kerf: [0.0, 3.0][job % 2],
+            trim: 8.0,
+            ..PlanarNestConfig::default()
+        };
+        let first: Vec<RectPart> = (0..5)
+            .map(|k| {
+                let (width, height) = (100.0 + next(700) as f64, 50.0 + next(400) as f64);
+                part(&format!("p{job}-{k}"), width, height, 1 + next(3) as usize)
+            })
+            .collect();
+        for strategy in [
+            PlanarStrategy::BestFit,
+            PlanarStrategy::Skyline,
+            PlanarStrategy::Guillotine(guillotine(2, CutAxis::AlongX)),
+            PlanarStrategy::Guillotine(guillotine(3, CutAxis::AlongY)),
+            PlanarStrategy::MaxRects(MaxRectsHeuristic::BestShortSideFit),
+        ] {
+            let mut existing = partial_sheets(&first, &stock(), &config, strategy)
+                .unwrap()
+                .layouts;
+            // every other part is cut already; the rest are nested again with a new one
+            let mut parts = first.clone();
+            for layout in &mut existing {
+                for placement in layout.placements.iter_mut().step_by(2) {
+                    placement.locked = true;
+                    let left = parts
+                        .iter_mut()
+                        .find(|p| p.id == placement.part_id)
+                        .unwrap();
+                    left.quantity -= 1;
+                }
+            }
+            parts.retain(|p| p.quantity > 0);
+            parts.push(part(&format!("n{job}"), 300.0, 200.0, 2));
+
+            let result = renest_sheets(&existing, &parts, &stock(), &config, strategy).unwrap();
+            assert!(result.unplaced.is_empty());
+            let mut cut = first.clone();
+            cut.push(part(&format!("n{job}"), 300.0, 200.0, 2));
+            let found = verify_sheets(&result.layouts, &cut, &stock(), &config);
+            assert!(found.is_empty(), "job {job}, {strategy:?}: {found:?}");
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use nest::{
+    BoardEnd, CutAction, LinearDefect, LinearNestConfig, LinearPart, LinearStock, cut_schedule,
+    first_fit_boards,
+};
+
+fn part(id: &str, length: f64, quantity: usize) -> LinearPart {
+    LinearPart {
+        id: id.into(),
+        length,
+        quantity,
+    }
+}
+
+#[test]
+fn groups_identical_lengths_under_one_stop() {
+    let parts = vec![
+        part("rail", 900.0, 2),
+        part("stile", 600.0, 3),
+        part("cleat", 300.0, 2),
+    ];
+    let stock = vec![LinearStock {
+        id: "ash-2400".into(),
+        length: 2400.0,
+        quantity: 3,
+        ..LinearStock::default()
+    }];
+    let cfg = LinearNestConfig {
+        kerf: 3.0,
+        trim_leading: 10.0,
+        trim_trailing: 10.0,
+        ..LinearNestConfig::default()
+    };
+    let result = first_fit_boards(&parts, &stock, &cfg).unwrap();
+    let schedule = cut_schedule(&result, &cfg);
+
+    let stops: Vec<f64> = schedule.setups.iter().map(|s| s.stop).collect();
+    assert_eq!(stops, vec![900.0, 600.0, 300.0]);
+    let counts: Vec<usize> = schedule.setups.iter().map(|s| s.cut_count()).collect();
+    assert_eq!(counts, vec![2, 3, 2]);
+
+    let first = &schedule.setups[0].steps[0];
+    assert_eq!(
+        first.action,
+        CutAction::TrimEnd {
+            end: BoardEnd::Leading,
+            length: 10.0
+        }
+    );
+    assert!(
+        schedule
+            .setups
+            .iter()
+            .flat_map(|s| &s.steps)
+            .all(|step| step.action != CutAction::Flip)
+    );
+}
+
+#[test]
+fn flips_board_to_reach_pieces_past_a_defect() {
+    let parts = vec![part("top", 1000.0, 1), part("leg", 400.0, 2)];
+    let stock = vec![LinearStock {
+        id: "checked".into(),
+        length: 2000.0,
+        quantity: 1,
+        defects: vec![LinearDefect {
+            start: 500.0,
+            length: 50.0,
+        }],
+        ..LinearStock::default()
+    }];
+    let cfg = LinearNestConfig::default();
+    let result = first_fit_boards(&parts, &stock, &cfg).unwrap();
+    let schedule = cut_schedule(&result, &cfg);
+
+    let steps: Vec<&CutAction> = schedule
+        .setups
+        .iter()
+        .flat_map(|s| &s.steps)
+        .map(|s| &s.action)
+        .collect();
+    assert!(steps.contains(&&CutAction::Flip));
+    assert!(steps.iter().any(|a| matches!(
+        a,
+        CutAction::TrimEnd {
+            end: BoardEnd::Trailing,
+            ..
+        }
+    )));
+    let cuts = steps
+        .iter()
+        .filter(|a| matches!(a, CutAction::Cut { .. }))
+        .count();
+    assert_eq!(cuts, 3);
+}
+
+#[test]
+fn exports_csv_and_fixed_width_feeder_list() {
+    let parts = vec![part("shelf-with-a-very-long-name", 500.0, 3)];
+    let stock = vec![LinearStock {
+        id: "pine".into(),
+        length: 1600.0,
+        quantity: 1,
+        ..LinearStock::default()
+    }];
+    let cfg = LinearNestConfig {
+        kerf: 2.0,
+        ..LinearNestConfig::default()
+    };
+    let result = first_fit_boards(&parts, &stock, &cfg).unwrap();
+    let schedule = cut_schedule(&result, &cfg);
+
+    let csv = schedule.to_csv();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("seq,stop,stock_id,board_index,action,end,part_id,length")
+    );
+    assert_eq!(
+        lines.next(),
+        Some("1,500.00,pine,0,cut,,shelf-with-a-very-long-name,500.00")
+    );
+    assert_eq!(csv.lines().count(), 4);
+
+    let feeder = schedule.to_feeder_list();
+    assert_eq!(feeder, "    1 shelf-with-a-very-lo     500.00     3\n");
+}
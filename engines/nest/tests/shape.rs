@@ -0,0 +1,243 @@
+use nest::{
+    GrainDirection, Point, Polygon, ShapeLayout, ShapeNestConfig, ShapePart, SheetStock,
+    shape_sheets,
+};
+
+fn pt(x: f64, y: f64) -> Point {
+    Point { x, y }
+}
+
+fn ring(points: &[(f64, f64)]) -> Vec<Point> {
+    points.iter().map(|&(x, y)| pt(x, y)).collect()
+}
+
+fn sheet(width: f64, height: f64, quantity: usize) -> Vec<SheetStock> {
+    vec![SheetStock {
+        id: "ply".into(),
+        width,
+        height,
+        quantity,
+        ..SheetStock::default()
+    }]
+}
+
+fn placed_outlines<'a>(
+    layout: &'a ShapeLayout,
+    parts: &'a [ShapePart],
+) -> impl Iterator<Item = Vec<Point>> + 'a {
+    layout.placements.iter().map(|placement| {
+        let part = parts.iter().find(|p| p.id == placement.part_id).unwrap();
+        part.shape
+            .outer
+            .iter()
+            .map(|&p| placement.transform(p))
+            .collect()
+    })
+}
+
+fn segment_distance(p: Point, a: Point, b: Point) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let t = (((p.x - a.x) * dx + (p.y - a.y) * dy) / (dx * dx + dy * dy)).clamp(0.0, 1.0);
+    (p.x - a.x - t * dx).hypot(p.y - a.y - t * dy)
+}
+
+fn inside(ring: &[Point], p: Point) -> bool {
+    let n = ring.len();
+    let mut hit = false;
+    for i in 0..n {
+        let (a, b) = (ring[i], ring[(i + n - 1) % n]);
+        if (a.y > p.y) != (b.y > p.y) && p.x < (b.x - a.x) * (p.y - a.y) / (b.y - a.y) + a.x {
+            hit = !hit;
+        }
+    }
+    hit
+}
+
+// vertex-to-edge distances cover polygons that do not cross; a vertex inside the other
+// outline catches the ones that do
+fn assert_spaced(a: &[Point], b: &[Point], spacing: f64) {
+    for (from, to) in [(a, b), (b, a)] {
+        for &p in from {
+            assert!(!inside(to, p), "outlines overlap");
+            let n = to.len();
+            for i in 0..n {
+                let d = segment_distance(p, to[i], to[(i + 1) % n]);
+                assert!(d >= spacing - 1e-6, "parts {d:.3} apart, need {spacing}");
+            }
+        }
+    }
+}
+
+#[test]
+fn triangles_share_a_sheet_their_bounding_boxes_could_not() {
+    let parts = vec![ShapePart {
+        id: "gusset".into(),
+        shape: Polygon {
+            outer: ring(&[(0.0, 0.0), (1000.0, 0.0), (0.0, 1000.0)]),
+            holes: Vec::new(),
+        },
+        quantity: 2,
+        grain: GrainDirection::Either,
+    }];
+    let layouts = shape_sheets(
+        &parts,
+        &sheet(1000.0, 1000.0, 2),
+        &ShapeNestConfig::default(),
+    )
+    .expect("nest");
+    assert_eq!(layouts.len(), 1);
+    assert_eq!(layouts[0].placements.len(), 2);
+    assert!((layouts[0].metrics.efficiency() - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn curved_parts_keep_tool_spacing_grain_and_true_area() {
+    let arch: Vec<(f64, f64)> = (0..=8)
+        .map(|k| {
+            let angle = std::f64::consts::PI * k as f64 / 8.0;
+            (300.0 + 300.0 * angle.cos(), 300.0 * angle.sin())
+        })
+        .chain((0..=8).map(|k| {
+            let angle = std::f64::consts::PI * (8 - k) as f64 / 8.0;
+            (300.0 + 220.0 * angle.cos(), 220.0 * angle.sin())
+        }))
+        .collect();
+    let parts = vec![
+        ShapePart {
+            id: "arched-rail".into(),
+            shape: Polygon {
+                outer: ring(&arch),
+                holes: Vec::new(),
+            },
+            quantity: 3,
+            grain: GrainDirection::AlongX,
+        },
+        ShapePart {
+            id: "bracket".into(),
+            shape: Polygon {
+                outer: ring(&[
+                    (0.0, 0.0),
+                    (240.0, 0.0),
+                    (240.0, 60.0),
+                    (60.0, 60.0),
+                    (60.0, 240.0),
+                    (0.0, 240.0),
+                ]),
+                holes: vec![ring(&[
+                    (15.0, 15.0),
+                    (35.0, 15.0),
+                    (35.0, 35.0),
+                    (15.0, 35.0),
+                ])],
+            },
+            quantity: 4,
+            grain: GrainDirection::Either,
+        },
+    ];
+    let config = ShapeNestConfig {
+        tool_diameter: 6.0,
+        trim: 10.0,
+        rotations: vec![0.0, 90.0, 180.0, 270.0],
+        seed: 4,
+    };
+    let stock = sheet(1220.0, 1220.0, 3);
+    let layouts = shape_sheets(&parts, &stock, &config).expect("nest");
+
+    let placed: usize = layouts.iter().map(|l| l.placements.len()).sum();
+    assert_eq!(placed, 7);
+    for layout in &layouts {
+        let outlines: Vec<Vec<Point>> = placed_outlines(layout, &parts).collect();
+        for (i, a) in outlines.iter().enumerate() {
+            for p in a {
+                assert!(p.x >= config.trim - 1e-6 && p.x <= 1220.0 - config.trim + 1e-6);
+                assert!(p.y >= config.trim - 1e-6 && p.y <= 1220.0 - config.trim + 1e-6);
+            }
+            for b in &outlines[i + 1..] {
+                assert_spaced(a, b, config.tool_diameter);
+            }
+        }
+        for placement in layout
+            .placements
+            .iter()
+            .filter(|p| p.part_id == "arched-rail")
+        {
+            assert!(placement.rotation == 0.0 || placement.rotation == 180.0);
+        }
+        let true_area: f64 = layout
+            .placements
+            .iter()
+            .map(|p| parts.iter().find(|part| part.id == p.part_id).unwrap())
+            .map(|part| part.shape.area())
+            .sum();
+        assert!((layout.metrics.utilized - true_area).abs() < 1e-6);
+        let m = &layout.metrics;
+        assert!(
+            (m.utilized + m.kerf_loss + m.trim_loss + m.offcut_loss - m.stock_total).abs() < 1e-6
+        );
+    }
+    let bracket = &parts[1].shape;
+    assert!((bracket.area() - (240.0 * 60.0 + 180.0 * 60.0 - 400.0)).abs() < 1e-9);
+}
+
+#[test]
+fn small_parts_nest_inside_a_frame_cutout_and_are_cut_first() {
+    let rect = |x0: f64, y0: f64, x1: f64, y1: f64| ring(&[(x0, y0), (x1, y0), (x1, y1), (x0, y1)]);
+    let parts = vec![
+        ShapePart {
+            id: "face-frame".into(),
+            shape: Polygon {
+                outer: rect(0.0, 0.0, 600.0, 400.0),
+                holes: vec![rect(50.0, 50.0, 550.0, 350.0)],
+            },
+            quantity: 1,
+            grain: GrainDirection::AlongX,
+        },
+        ShapePart {
+            id: "drawer-bottom".into(),
+            shape: Polygon {
+                outer: rect(0.0, 0.0, 200.0, 120.0),
+                holes: Vec::new(),
+            },
+            quantity: 4,
+            grain: GrainDirection::Either,
+        },
+    ];
+    let config = ShapeNestConfig {
+        tool_diameter: 6.0,
+        trim: 10.0,
+        ..ShapeNestConfig::default()
+    };
+    let layouts = shape_sheets(&parts, &sheet(620.0, 420.0, 3), &config).expect("nest");
+    assert_eq!(layouts.len(), 1);
+
+    let layout = &layouts[0];
+    let frame = layout
+        .placements
+        .iter()
+        .position(|p| p.part_id == "face-frame")
+        .unwrap();
+    assert_eq!(layout.placements[frame].parent, None);
+    let cutout: Vec<Point> = parts[0].shape.holes[0]
+        .iter()
+        .map(|&p| layout.placements[frame].transform(p))
+        .collect();
+    let outlines: Vec<Vec<Point>> = placed_outlines(layout, &parts).collect();
+    let inner: Vec<usize> = (0..layout.placements.len())
+        .filter(|&i| i != frame)
+        .collect();
+    assert_eq!(inner.len(), 4);
+    for &i in &inner {
+        assert_eq!(layout.placements[i].parent, Some(frame));
+        for &p in &outlines[i] {
+            assert!(inside(&cutout, p));
+            for k in 0..cutout.len() {
+                let d = segment_distance(p, cutout[k], cutout[(k + 1) % cutout.len()]);
+                assert!(d >= config.tool_diameter - 1e-6);
+            }
+        }
+        for &j in inner.iter().filter(|&&j| j > i) {
+            assert_spaced(&outlines[i], &outlines[j], config.tool_diameter);
+        }
+    }
+    assert_eq!(*layout.cut_order().last().unwrap(), frame);
+}
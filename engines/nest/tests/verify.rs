@@ -0,0 +1,330 @@
+use nest::{
+    CutAxis, CutSegment, ExclusionZone, FaceGrade, GrainDirection, GuillotineConfig,
+    LayoutViolation, LinearDefect, LinearNestConfig, LinearPart, LinearStock, MaxRectsHeuristic,
+    PartEdges, PlanarNestConfig, PlanarStrategy, Point, Polygon, RectPart, ShapeNestConfig,
+    ShapePart, SheetStock, Violation, first_fit_boards, partial_sheets, shape_sheets,
+    verify_boards, verify_shapes, verify_sheets,
+};
+
+fn part(id: &str, width: f64, height: f64, quantity: usize, grain: GrainDirection) -> RectPart {
+    RectPart {
+        id: id.into(),
+        width,
+        height,
+        quantity,
+        grain,
+        face: FaceGrade::B,
+        banding: PartEdges {
+            left: 1.0,
+            ..PartEdges::default()
+        },
+        ..RectPart::default()
+    }
+}
+
+fn stock() -> Vec<SheetStock> {
+    vec![SheetStock {
+        id: "oak".into(),
+        width: 2440.0,
+        height: 1220.0,
+        quantity: 4,
+        exclusions: vec![ExclusionZone::Rect {
+            x: 1100.0,
+            y: 400.0,
+            width: 200.0,
+            height: 300.0,
+        }],
+        grain: GrainDirection::AlongY,
+        bottom_face: FaceGrade::B,
+        ..SheetStock::default()
+    }]
+}
+
+#[test]
+fn every_planar_solver_passes_and_tampering_is_caught() {
+    let parts = vec![
+        part("side", 720.0, 560.0, 4, GrainDirection::AlongY),
+        part("rail", 900.0, 90.0, 6, GrainDirection::AlongX),
+        part("filler", 300.0, 250.0, 5, GrainDirection::Either),
+    ];
+    let config = PlanarNestConfig {
+        kerf: 3.0,
+        trim: 8.0,
+        ..PlanarNestConfig::default()
+    };
+    for strategy in [
+        PlanarStrategy::BestFit,
+        PlanarStrategy::Skyline,
+        PlanarStrategy::Guillotine(GuillotineConfig::default()),
+        PlanarStrategy::MaxRects(MaxRectsHeuristic::BestAreaFit),
+    ] {
+        let result = partial_sheets(&parts, &stock(), &config, strategy).unwrap();
+        assert!(result.unplaced.is_empty());
+        let clean = verify_sheets(&result.layouts, &parts, &stock(), &config);
+        assert!(clean.is_empty(), "{strategy:?}: {clean:?}");
+
+        let mut broken = result.layouts.clone();
+        let dropped = broken[0].placements.pop().unwrap();
+        let (x, y) = (broken[0].placements[0].x, broken[0].placements[0].y);
+        broken[0].placements[1].x = x + 1.0;
+        broken[0].placements[1].y = y + 1.0;
+        broken[0].metrics.utilized += 1.0;
+        let found = verify_sheets(&broken, &parts, &stock(), &config);
+        assert!(found.contains(&Violation::On(0, LayoutViolation::Overlap(0, 1))));
+        assert!(found.contains(&Violation::On(0, LayoutViolation::MetricsDoNotReconcile)));
+        let requested = parts
+            .iter()
+            .find(|p| p.id == dropped.part_id)
+            .unwrap()
+            .quantity;
+        assert!(found.contains(&Violation::QuantityMismatch {
+            part_id: dropped.part_id.clone(),
+            requested,
+            placed: requested - 1,
+        }));
+    }
+}
+
+#[test]
+fn kerfed_mixed_jobs_leave_a_kerf_between_every_part() {
+    let sheets = vec![SheetStock {
+        id: "birch".into(),
+        width: 1220.0,
+        height: 1220.0,
+        quantity: 20,
+        ..SheetStock::default()
+    }];
+    let config = PlanarNestConfig {
+        kerf: 4.0,
+        trim: 5.0,
+        ..PlanarNestConfig::default()
+    };
+    // a small linear congruential stream so every job is the same on every run
+    let mut state = 7u64;
+    let mut next = |span: u64| {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) % span
+    };
+    for job in 0..30 {
+        let parts: Vec<RectPart> = (0..6)
+            .map(|k| {
+                part(
+                    &format!("p{job}-{k}"),
+                    60.0 + next(500) as f64,
+                    60.0 + next(500) as f64,
+                    1 + next(3) as usize,
+                    GrainDirection::Either,
+                )
+            })
+            .collect();
+        for strategy in [
+            PlanarStrategy::BestFit,
+            PlanarStrategy::Skyline,
+            PlanarStrategy::Guillotine(GuillotineConfig::default()),
+            PlanarStrategy::MaxRects(MaxRectsHeuristic::BestShortSideFit),
+        ] {
+            let result = partial_sheets(&parts, &sheets, &config, strategy).unwrap();
+            let found = verify_sheets(&result.layouts, &parts, &sheets, &config);
+            assert!(found.is_empty(), "job {job}, {strategy:?}: {found:?}");
+        }
+    }
+}
+
+#[test]
+fn boards_pass_and_cuts_into_defects_are_caught() {
+    let parts = vec![
+        LinearPart {
+            id: "leg".into(),
+            length: 700.0,
+            quantity: 4,
+        },
+        LinearPart {
+            id: "apron".into(),
+            length: 1100.0,
+            quantity: 2,
+        },
+    ];
+    let stock = vec![LinearStock {
+        id: "ash".into(),
+        length: 2400.0,
+        quantity: 3,
+        defects: vec![LinearDefect {
+            start: 1500.0,
+            length: 40.0,
+        }],
+        ..LinearStock::default()
+    }];
+    let config = LinearNestConfig {
+        kerf: 3.0,
+        trim_leading: 10.0,
+        trim_trailing: 10.0,
+        ..LinearNestConfig::default()
+    };
+    let result = first_fit_boards(&parts, &stock, &config).unwrap();
+    let clean = verify_boards(&result.boards, &parts, &stock, &config);
+    assert!(clean.is_empty(), "{clean:?}");
+
+    let mut broken = result.boards.clone();
+    broken[0].cuts[0].start = 1480.0;
+    broken.push(broken[0].clone());
+    broken.push(broken[0].clone());
+    let found = verify_boards(&broken, &parts, &stock, &config);
+    assert!(found.contains(&Violation::On(0, LayoutViolation::InExclusionZone(0))));
+    assert!(found.contains(&Violation::StockOverused {
+        stock_id: "ash".into(),
+        available: 3,
+        used: broken.len(),
+    }));
+}
+
+// a broken corner: inside its bounding box but off the diagonal is sound sheet
+fn corner_sheet() -> Vec<SheetStock> {
+    vec![SheetStock {
+        width: 1000.0,
+        height: 1000.0,
+        exclusions: vec![ExclusionZone::Polygon(vec![
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 300.0, y: 0.0 },
+            Point { x: 0.0, y: 300.0 },
+        ])],
+        ..stock().remove(0)
+    }]
+}
+
+#[test]
+fn polygon_zones_are_checked_against_their_outline() {
+    let parts = vec![part("block", 100.0, 100.0, 1, GrainDirection::Either)];
+    let config = PlanarNestConfig {
+        kerf: 4.0,
+        ..PlanarNestConfig::default()
+    };
+    let result = partial_sheets(&parts, &corner_sheet(), &config, PlanarStrategy::BestFit).unwrap();
+    let clean = verify_sheets(&result.layouts, &parts, &corner_sheet(), &config);
+    assert!(clean.is_empty(), "{clean:?}");
+
+    let zone = Violation::On(0, LayoutViolation::InExclusionZone(0));
+    let mut moved = result.layouts.clone();
+    moved[0].segments.clear();
+    (moved[0].placements[0].x, moved[0].placements[0].y) = (200.0, 200.0);
+    assert!(!verify_sheets(&moved, &parts, &corner_sheet(), &config).contains(&zone));
+    (moved[0].placements[0].x, moved[0].placements[0].y) = (50.0, 50.0);
+    assert!(verify_sheets(&moved, &parts, &corner_sheet(), &config).contains(&zone));
+}
+
+#[test]
+fn saw_passes_keep_out_of_parts_and_zones_and_cuts_run_edge_to_edge() {
+    let parts = vec![
+        part("side", 720.0, 560.0, 2, GrainDirection::AlongY),
+        part("filler", 300.0, 250.0, 3, GrainDirection::Either),
+    ];
+    let config = PlanarNestConfig {
+        kerf: 3.0,
+        trim: 8.0,
+        ..PlanarNestConfig::default()
+    };
+    let strategy = PlanarStrategy::Guillotine(GuillotineConfig::default());
+    let cut = partial_sheets(&parts, &stock(), &config, strategy).unwrap();
+    let mut short = cut.layouts.clone();
+    short[0].cuts[0].end -= 10.0;
+    let found = verify_sheets(&short, &parts, &stock(), &config);
+    assert!(found.contains(&Violation::On(0, LayoutViolation::CutNotEdgeToEdge(0))));
+
+    let free = partial_sheets(&parts, &stock(), &config, PlanarStrategy::BestFit).unwrap();
+    let mut broken = free.layouts.clone();
+    let p = broken[0].placements[0].clone();
+    broken[0].segments = vec![
+        CutSegment {
+            axis: CutAxis::AlongX,
+            position: p.y + p.height / 2.0,
+            start: p.x,
+            end: p.x + p.width,
+            kerf: 3.0,
+        },
+        // across the clamp pad at (1100, 400)
+        CutSegment {
+            axis: CutAxis::AlongY,
+            position: 1200.0,
+            start: 300.0,
+            end: 800.0,
+            kerf: 3.0,
+        },
+    ];
+    let found = verify_sheets(&broken, &parts, &stock(), &config);
+    assert!(found.contains(&Violation::On(0, LayoutViolation::SegmentThroughPart(0))));
+    assert!(found.contains(&Violation::On(
+        0,
+        LayoutViolation::SegmentInExclusionZone(1)
+    )));
+}
+
+#[test]
+fn shapes_pass_and_overlaps_are_caught() {
+    let square = |x: f64, y: f64, size: f64| {
+        vec![
+            Point { x, y },
+            Point { x: x + size, y },
+            Point {
+                x: x + size,
+                y: y + size,
+            },
+            Point { x, y: y + size },
+        ]
+    };
+    let parts = vec![
+        ShapePart {
+            id: "frame".into(),
+            shape: Polygon {
+                outer: square(0.0, 0.0, 300.0),
+                holes: vec![square(100.0, 100.0, 100.0)],
+            },
+            quantity: 2,
+            grain: GrainDirection::Either,
+        },
+        ShapePart {
+            id: "tile".into(),
+            shape: Polygon {
+                outer: square(0.0, 0.0, 80.0),
+                holes: Vec::new(),
+            },
+            quantity: 3,
+            grain: GrainDirection::Either,
+        },
+    ];
+    let config = ShapeNestConfig {
+        tool_diameter: 6.0,
+        rotations: vec![0.0],
+        ..ShapeNestConfig::default()
+    };
+    let layouts = shape_sheets(&parts, &corner_sheet(), &config).unwrap();
+    assert!(
+        layouts
+            .iter()
+            .flat_map(|l| &l.placements)
+            .any(|p| p.parent.is_some())
+    );
+    let clean = verify_shapes(&layouts, &parts, &corner_sheet(), &config);
+    assert!(clean.is_empty(), "{clean:?}");
+
+    let mut broken = layouts.clone();
+    let frames: Vec<usize> = (0..broken[0].placements.len())
+        .filter(|&i| broken[0].placements[i].part_id == "frame")
+        .collect();
+    let (a, b) = (frames[0], frames[1]);
+    broken[0].placements[b].x = broken[0].placements[a].x + 50.0;
+    broken[0].placements[b].y = broken[0].placements[a].y;
+    let found = verify_shapes(&broken, &parts, &corner_sheet(), &config);
+    assert!(found.contains(&Violation::On(
+        0,
+        LayoutViolation::Overlap(a.min(b), a.max(b))
+    )));
+
+    let mut broken = layouts.clone();
+    let loose = (0..broken[0].placements.len())
+        .find(|&i| broken[0].placements[i].parent.is_none())
+        .unwrap();
+    (broken[0].placements[loose].x, broken[0].placements[loose].y) = (10.0, 10.0);
+    let found = verify_shapes(&broken, &parts, &corner_sheet(), &config);
+    assert!(found.contains(&Violation::On(0, LayoutViolation::InExclusionZone(loose))));
+}